@@ -0,0 +1,255 @@
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use crate::format::{self, FileKind, FileType, Format};
+use crate::object::{DatObject, ObjectType};
+use crate::ratings::{self, Ratings};
+use crate::ride_type::RideType;
+use crate::s6::SavedGame;
+use crate::td6::{self, TrackDesign};
+
+/// Main facts about a file, as printed by `info`. Money is in tenths of the currency unit.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
+pub enum Summary {
+    TrackDesign{
+        ride_type: String,
+        vehicle: String,
+        /// Track elements, or tiles of a maze.
+        elements: usize,
+        ratings: Ratings,
+        checksum_valid: bool
+    },
+    SavedGame{
+        scenario: bool,
+        months: u16,
+        cash: Option<i32>,
+        loan: Option<i32>,
+        park_rating: Option<u16>,
+        guests_in_park: Option<u16>,
+        rides: usize,
+        objects: usize,
+        packed: usize
+    },
+    Object{
+        entry: String,
+        object_type: ObjectType,
+        /// Name of ride objects.
+        name: Option<String>,
+        checksum_valid: bool
+    }
+}
+
+fn unsupported(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Detects and reads a file.
+pub fn summarize(b: &[u8]) -> io::Result<(Format, Summary)> {
+    let f = format::detect_format(b);
+    let summary = match f.file_type.kind() {
+        FileKind::TrackDesign => {
+            let td = TrackDesign::read(b)?;
+            Summary::TrackDesign{
+                ride_type: RideType::from(td.ride_type).name().to_string(),
+                vehicle: td.vehicle_object.name(),
+                elements: if td.is_maze() { td.maze.len() } else { td.elements.len() },
+                ratings: ratings::stored_td6(&td),
+                checksum_valid: td6::validate_checksum(b)
+            }
+        },
+        FileKind::SavedGame | FileKind::Scenario => {
+            let sg = SavedGame::read(Cursor::new(b))?;
+            Summary::SavedGame{
+                scenario: sg.is_scenario(),
+                months: sg.months(),
+                cash: sg.cash(),
+                loan: sg.loan(),
+                park_rating: sg.park_rating(),
+                guests_in_park: sg.guests_in_park(),
+                rides: sg.rides().len(),
+                objects: sg.objects().len(),
+                packed: sg.packed.len()
+            }
+        },
+        FileKind::Object => {
+            let obj = DatObject::read(&mut Cursor::new(b))?;
+            Summary::Object{
+                entry: obj.entry.name(),
+                object_type: obj.entry.object_type(),
+                name: obj.ride_object().and_then(Result::ok).map(|r| r.name.english()),
+                checksum_valid: obj.is_valid()
+            }
+        },
+        FileKind::Unknown if f.file_type == FileType::Unknown => return Err(unsupported("unrecognised file format".to_string())),
+        FileKind::Unknown => return Err(unsupported(format!("{} files are not supported", f.file_type.name())))
+    };
+    Ok((f, summary))
+}
+
+/// Reads and summarizes a file, with the error as a message if it cannot be read.
+pub fn summarize_file(path: &Path) -> Result<(Format, Summary), String> {
+    let b = fs::read(path).map_err(|e| e.to_string())?;
+    summarize(&b).map_err(|e| e.to_string())
+}
+
+/// Every regular file in a directory tree, sorted, and the directories and entries which could
+/// not be read with their errors. Files are not picked by extension, as `summarize` sniffs them.
+pub fn find_files(dir: &Path) -> (Vec<PathBuf>, Vec<(PathBuf, io::Error)>) {
+    let (mut files, mut errors) = (Vec::new(), Vec::new());
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(d) = dirs.pop() {
+        let entries = match fs::read_dir(&d) {
+            Ok(entries) => entries,
+            Err(e) => {
+                errors.push((d, e));
+                continue;
+            }
+        };
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    errors.push((d.clone(), e));
+                    continue;
+                }
+            };
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(ft) if ft.is_dir() => dirs.push(path),
+                Ok(ft) if ft.is_file() => files.push(path),
+                Ok(_) => (),
+                Err(e) => errors.push((path, e))
+            }
+        }
+    }
+    files.sort();
+    errors.sort_by(|a, b| a.0.cmp(&b.0));
+    (files, errors)
+}
+
+/// Runs `f` on every path with a pool of `threads` worker threads. `out` is called on the
+/// calling thread with each result as soon as it is ready, so results come in no set order.
+pub fn run<T, F, O>(paths: &[PathBuf], threads: usize, f: F, mut out: O)
+where T: Send, F: Fn(&Path) -> T + Sync, O: FnMut(&Path, T) {
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            let (tx, next, f) = (tx.clone(), &next, &f);
+            s.spawn(move || {
+                while let Some(p) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if tx.send((p, f(p))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        for (p, r) in rx {
+            out(p, r);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::sawyer::{self, Chunk, ChunkWriter, Encoding};
+
+    /// Wooden roller coaster design with a station and a flat piece.
+    fn design() -> Vec<u8> {
+        let mut v = vec![0u8; 0xA3];
+        v[0x00] = 0x34;
+        v[0x07] = 2 << 2;
+        v[0x70..0x74].copy_from_slice(&0x80u32.to_le_bytes());
+        v[0x74..0x7C].copy_from_slice(b"WOODCARS");
+        v.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF]);
+        let mut f = Vec::new();
+        TrackDesign::from_data(&v).unwrap().write(&mut f).unwrap();
+        f
+    }
+
+    /// Signed saved game with a header claiming `packed` objects, followed by `chunks`.
+    fn save(packed: u16, chunks: &[&[u8]]) -> Vec<u8> {
+        let mut header = vec![0u8; 0x20];
+        header[2..4].copy_from_slice(&packed.to_le_bytes());
+        let mut v = Vec::new();
+        let mut cw = ChunkWriter::new(&mut v);
+        cw.write_chunk(&Chunk::new(Encoding::RLE, header)).unwrap();
+        for c in chunks {
+            cw.write_chunk(&Chunk::new(Encoding::RLE, c.to_vec())).unwrap();
+        }
+        let checksum = sawyer::calculate_checksum(&v);
+        v.extend_from_slice(&checksum.to_le_bytes());
+        v
+    }
+
+    #[test]
+    fn finds_files() {
+        let dir = std::env::temp_dir().join(format!("rct2read-test-find-{}", std::process::id()));
+        fs::create_dir_all(dir.join("b/deep")).unwrap();
+        fs::create_dir_all(dir.join("empty")).unwrap();
+        for f in ["z.td6", "a.sv6", "b/deep/park", "b/c.dat"] {
+            fs::write(dir.join(f), b"x").unwrap();
+        }
+        let (files, errors) = find_files(&dir);
+        let names: Vec<_> = files.iter().map(|p| p.strip_prefix(&dir).unwrap().to_path_buf()).collect();
+        assert_eq!(names, ["a.sv6", "b/c.dat", "b/deep/park", "z.td6"].iter().map(PathBuf::from).collect::<Vec<_>>());
+        assert!(errors.is_empty());
+
+        let missing = dir.join("missing");
+        let (files, errors) = find_files(&missing);
+        assert!(files.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, missing);
+        assert_eq!(errors[0].1.kind(), io::ErrorKind::NotFound);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_files_are_errors() {
+        let dir = std::env::temp_dir().join(format!("rct2read-test-corrupt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("design.td6"), design()).unwrap();
+        fs::write(dir.join("park.sv6"), save(0, &[&[0xFF; 16], &[40, 0, 0, 0]])).unwrap();
+        // A date chunk too short to hold the month
+        fs::write(dir.join("short-date.sv6"), save(0, &[&[0xFF; 16], &[40]])).unwrap();
+        // The header lists a packed object which is not there
+        fs::write(dir.join("missing-object.sv6"), save(1, &[])).unwrap();
+        fs::write(dir.join("notes.txt"), b"not a park").unwrap();
+        let (mut paths, _) = find_files(&dir);
+        paths.push(dir.join("deleted.sv6"));
+
+        let mut results = BTreeMap::new();
+        run(&paths, 3, summarize_file, |p, r| {
+            results.insert(p.file_name().unwrap().to_string_lossy().into_owned(), r);
+        });
+        assert_eq!(results.len(), 6);
+        match &results["design.td6"] {
+            Ok((f, Summary::TrackDesign{ride_type, elements, checksum_valid, ..})) => {
+                assert_eq!(f.file_type, FileType::Td6);
+                assert_eq!((ride_type.as_str(), *elements, *checksum_valid), ("Wooden Roller Coaster", 2, true));
+            },
+            _ => panic!("design not summarized")
+        }
+        for (name, months) in [("park.sv6", 40), ("short-date.sv6", 0)] {
+            match &results[name] {
+                Ok((f, Summary::SavedGame{scenario, months: m, cash, ..})) => {
+                    assert_eq!(f.file_type, FileType::Sv6);
+                    assert_eq!((*scenario, *m, *cash), (false, months, None));
+                },
+                _ => panic!("{} not summarized", name)
+            }
+        }
+        assert_eq!(results["missing-object.sv6"].as_ref().err().unwrap(), "failed to fill whole buffer");
+        assert_eq!(results["notes.txt"].as_ref().err().unwrap(), "unrecognised file format");
+        assert!(results["deleted.sv6"].is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::object::{DatObject, ObjectEntry};
+use crate::s6::SavedGame;
+use crate::sawyer::{Chunk, Encoding};
+
+/// Lists the chunk files of an extracted save, in file order.
+pub const MANIFEST: &str = "manifest.txt";
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn entry_hex(e: &ObjectEntry) -> String {
+    let mut b = e.flags.to_le_bytes().to_vec();
+    b.extend_from_slice(&e.name);
+    b.extend_from_slice(&e.checksum.to_le_bytes());
+    b.iter().map(|x| format!("{:02X}", x)).collect()
+}
+
+fn parse_entry(s: &str) -> Option<ObjectEntry> {
+    if s.len() != 32 {
+        return None;
+    }
+    let b = (0..16).map(|i| u8::from_str_radix(s.get(i * 2..i * 2 + 2)?, 16).ok()).collect::<Option<Vec<_>>>()?;
+    Some(ObjectEntry::from_slice(&b))
+}
+
+/// Writes every decoded chunk of a save as `chunk_N.bin`, with a manifest giving each chunk's
+/// role and encoding, and the checksum salt. Packed objects keep their object entry in the manifest.
+pub fn extract<P: AsRef<Path>>(sg: &SavedGame, dir: P) -> io::Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let mut manifest = format!("# chunk file, role, encoding and object entry of packed objects\nsalt {:08X}\n", sg.checksum_salt);
+    let mut paths = Vec::new();
+    let mut write = |role: &str, enc: Encoding, data: &[u8], entry: Option<&ObjectEntry>| -> io::Result<()> {
+        let name = format!("chunk_{}.bin", paths.len());
+        let p = dir.join(&name);
+        fs::write(&p, data)?;
+        manifest += &format!("{} {} {}", name, role, enc.name());
+        if let Some(e) = entry {
+            manifest += &format!(" {}", entry_hex(e));
+        }
+        manifest.push('\n');
+        paths.push(p);
+        Ok(())
+    };
+    write("header", sg.header.encoding(), sg.header.data(), None)?;
+    if let Some(info) = &sg.info {
+        write("info", info.encoding(), info.data(), None)?;
+    }
+    for o in &sg.packed {
+        write("object", o.encoding, &o.data, Some(&o.entry))?;
+    }
+    for c in &sg.chunks {
+        write("chunk", c.encoding(), c.data(), None)?;
+    }
+    let p = dir.join(MANIFEST);
+    fs::write(&p, manifest)?;
+    paths.push(p);
+    Ok(paths)
+}
+
+/// Reads a save back from a directory written by `extract`. The chunks are encoded again with
+/// the encodings in the manifest, and the file checksum is computed with the original salt.
+pub fn pack<P: AsRef<Path>>(dir: P) -> io::Result<SavedGame> {
+    let dir = dir.as_ref();
+    let manifest = fs::read_to_string(dir.join(MANIFEST))?;
+    let mut salt = None;
+    let (mut header, mut info, mut packed, mut chunks) = (None, None, Vec::new(), Vec::new());
+    for (n, line) in manifest.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad = |what: &str| invalid(format!("{} line {}: {}", MANIFEST, n + 1, what));
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if let ["salt", s] = fields[..] {
+            salt = Some(u32::from_str_radix(s, 16).map_err(|_| bad("invalid salt"))?);
+            continue;
+        }
+        let (file, role, enc, entry) = match fields[..] {
+            [file, role, enc] => (file, role, enc, None),
+            [file, role, enc, entry] => (file, role, enc, Some(entry)),
+            _ => return Err(bad("expected a chunk file, role and encoding"))
+        };
+        let enc = Encoding::from_name(enc).ok_or_else(|| bad("unknown encoding"))?;
+        let data = fs::read(dir.join(file)).map_err(|e| bad(&format!("{}: {}", file, e)))?;
+        match (role, entry) {
+            ("header", None) if header.is_none() => header = Some(Chunk::new(enc, data)),
+            ("info", None) if info.is_none() => info = Some(Chunk::new(enc, data)),
+            ("object", Some(e)) => {
+                let entry = parse_entry(e).ok_or_else(|| bad("invalid object entry"))?;
+                packed.push(DatObject{entry, encoding: enc, data});
+            },
+            ("chunk", None) => chunks.push(Chunk::new(enc, data)),
+            _ => return Err(bad("unexpected role"))
+        }
+    }
+    let header = header.ok_or_else(|| invalid("no header chunk".to_string()))?;
+    let checksum_salt = salt.ok_or_else(|| invalid("no checksum salt".to_string()))?;
+    let h = header.data();
+    if h.len() < 4 {
+        return Err(invalid("header chunk is too short".to_string()));
+    }
+    if (h[0] == 1) != info.is_some() {
+        return Err(invalid("scenarios, and only scenarios, have an info chunk".to_string()));
+    }
+    if usize::from(u16::from_le_bytes([h[2], h[3]])) != packed.len() {
+        return Err(invalid(format!("header lists {} packed objects but {} are given", u16::from_le_bytes([h[2], h[3]]), packed.len())));
+    }
+    Ok(SavedGame{header, info, packed, chunks, checksum_salt})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Scenario with a packed object, written and read back so its checksum salt is real.
+    fn scenario() -> SavedGame {
+        let entry = ObjectEntry{flags: 0, name: *b"MYCOASTR", checksum: 0xDEAD_BEEF};
+        let mut header = vec![0u8; 0x20];
+        header[0] = 1;
+        header[2] = 1;
+        let sg = SavedGame{
+            header: Chunk::new(Encoding::RLE, header),
+            info: Some(Chunk::new(Encoding::RLE, vec![7; 0x198])),
+            packed: vec![DatObject{entry, encoding: Encoding::Rotate, data: vec![1, 2, 3, 4]}],
+            chunks: vec![
+                Chunk::new(Encoding::RLE, vec![0xFF; 16]),
+                Chunk::new(Encoding::None, vec![40, 0, 3, 0]),
+                Chunk::new(Encoding::RLECompressed, vec![0; 64])
+            ],
+            checksum_salt: 0x0001_2345
+        };
+        let mut v = Vec::new();
+        sg.write(&mut v).unwrap();
+        SavedGame::read(Cursor::new(v)).unwrap()
+    }
+
+    fn bytes(sg: &SavedGame) -> Vec<u8> {
+        let mut v = Vec::new();
+        sg.write(&mut v).unwrap();
+        v
+    }
+
+    fn temp_dir(tag: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rct2read-test-chunks-{}-{}", tag, std::process::id()))
+    }
+
+    #[test]
+    fn extract_and_pack() {
+        let dir = temp_dir("round-trip");
+        let sg = scenario();
+        let paths = extract(&sg, &dir).unwrap();
+        let names: Vec<_> = paths.iter().map(|p| p.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(names, ["chunk_0.bin", "chunk_1.bin", "chunk_2.bin", "chunk_3.bin", "chunk_4.bin", "chunk_5.bin", MANIFEST]);
+        assert_eq!(fs::read_to_string(dir.join(MANIFEST)).unwrap(), "\
+# chunk file, role, encoding and object entry of packed objects
+salt 00012345
+chunk_0.bin header rle
+chunk_1.bin info rle
+chunk_2.bin object rotate 000000004D59434F41535452EFBEADDE
+chunk_3.bin chunk rle
+chunk_4.bin chunk none
+chunk_5.bin chunk rle-compressed
+");
+        assert_eq!(fs::read(dir.join("chunk_2.bin")).unwrap(), [1, 2, 3, 4]);
+        assert_eq!(fs::read(dir.join("chunk_4.bin")).unwrap(), [40, 0, 3, 0]);
+        assert_eq!(bytes(&pack(&dir).unwrap()), bytes(&sg));
+
+        // Edited chunks and encodings are packed with the original salt
+        fs::write(dir.join("chunk_4.bin"), [41, 0, 3, 0]).unwrap();
+        let manifest = fs::read_to_string(dir.join(MANIFEST)).unwrap().replace("chunk_5.bin chunk rle-compressed", "chunk_5.bin chunk none");
+        fs::write(dir.join(MANIFEST), manifest).unwrap();
+        let packed = pack(&dir).unwrap();
+        assert_eq!(packed.months(), 41);
+        assert_eq!(packed.chunks[2].encoding(), Encoding::None);
+        assert_eq!(packed.checksum_salt, sg.checksum_salt);
+        let reread = SavedGame::read(Cursor::new(bytes(&packed))).unwrap();
+        assert_eq!(reread.checksum_salt, sg.checksum_salt);
+        assert_eq!(reread.packed[0].entry.name(), "MYCOASTR");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn bad_manifests() {
+        let dir = temp_dir("bad");
+        extract(&scenario(), &dir).unwrap();
+        let manifest = fs::read_to_string(dir.join(MANIFEST)).unwrap();
+        let cases: &[(&str, &str, &str)] = &[
+            ("salt 00012345\n", "", "no checksum salt"),
+            ("salt 00012345", "salt xyz", "manifest.txt line 2: invalid salt"),
+            ("chunk_4.bin chunk none", "chunk_4.bin chunk lzma", "manifest.txt line 7: unknown encoding"),
+            ("chunk_4.bin chunk none", "chunk_4.bin", "manifest.txt line 7: expected a chunk file, role and encoding"),
+            ("chunk_4.bin chunk none", "chunk_4.bin palette none", "manifest.txt line 7: unexpected role"),
+            ("chunk_4.bin chunk none", "chunk_4.bin header none", "manifest.txt line 7: unexpected role"),
+            ("EFBEADDE", "EFBEAD", "manifest.txt line 5: invalid object entry"),
+            ("chunk_4.bin", "chunk_9.bin", "manifest.txt line 7: chunk_9.bin: "),
+            ("chunk_0.bin header rle\n", "", "no header chunk"),
+            ("chunk_1.bin info rle\n", "", "scenarios, and only scenarios, have an info chunk"),
+            ("chunk_2.bin object rotate 000000004D59434F41535452EFBEADDE\n", "", "header lists 1 packed objects but 0 are given")
+        ];
+        for (from, to, msg) in cases {
+            assert!(manifest.contains(from), "{}", from);
+            fs::write(dir.join(MANIFEST), manifest.replace(from, to)).unwrap();
+            let e = pack(&dir).err().unwrap();
+            assert!(e.to_string().starts_with(msg), "{}: {}", from, e);
+        }
+        fs::write(dir.join(MANIFEST), &manifest).unwrap();
+        fs::write(dir.join("chunk_0.bin"), [1, 0]).unwrap();
+        assert_eq!(pack(&dir).err().unwrap().to_string(), "header chunk is too short");
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(pack(&dir).err().unwrap().kind(), io::ErrorKind::NotFound);
+    }
+}
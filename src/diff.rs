@@ -0,0 +1,281 @@
+use std::fmt;
+use crate::finance::format_money as money;
+use crate::map::MAP_SIZE;
+use crate::ratings::Ratings;
+use crate::ride::Ride;
+use crate::ride_type::RideType;
+use crate::s6::SavedGame;
+
+/// Difference between two saves of a park. Money is in tenths of the currency unit.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Change {
+    Cash{old: i32, new: i32},
+    Loan{old: i32, new: i32},
+    ParkRating{old: u16, new: u16},
+    GuestsInPark{old: u16, new: u16},
+    RideAdded{index: u8, name: String, ride_type: u8},
+    RideRemoved{index: u8, name: String, ride_type: u8},
+    RideRenamed{index: u8, old: String, new: String},
+    RidePrice{index: u8, name: String, old: u16, new: u16},
+    /// Ratings are None while a ride is untested.
+    RideRatings{index: u8, name: String, old: Option<Ratings>, new: Option<Ratings>},
+    ResearchedRideType{ride_type: u8},
+    /// Ride object invented, by its identifier in the object list.
+    ResearchedRide{object: String},
+    ResearchedScenery{count: usize},
+    /// Tiles whose elements differ, with the smallest rectangle holding them all.
+    Tiles{count: usize, min: (usize, usize), max: (usize, usize)}
+}
+
+fn signed(v: i64) -> String {
+    if v > 0 { format!("+{}", v) } else { v.to_string() }
+}
+
+fn format_ratings(r: &Option<Ratings>) -> String {
+    r.map_or_else(|| "not rated".to_string(), |r| r.to_string())
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Change::*;
+        match self {
+            Cash{old, new} => write!(f, "cash: {} -> {} ({}{})", money(*old), money(*new), if new >= old { "+" } else { "" },
+                money(new.wrapping_sub(*old))),
+            Loan{old, new} => write!(f, "loan: {} -> {}", money(*old), money(*new)),
+            ParkRating{old, new} => write!(f, "park rating: {} -> {} ({})", old, new, signed(i64::from(*new) - i64::from(*old))),
+            GuestsInPark{old, new} => write!(f, "guests in park: {} -> {} ({})", old, new, signed(i64::from(*new) - i64::from(*old))),
+            RideAdded{index, name, ride_type} => write!(f, "ride added: #{} {} ({})", index, name, RideType::from(*ride_type).name()),
+            RideRemoved{index, name, ride_type} => write!(f, "ride removed: #{} {} ({})", index, name, RideType::from(*ride_type).name()),
+            RideRenamed{index, old, new} => write!(f, "ride renamed: #{} {:?} -> {:?}", index, old, new),
+            RidePrice{index, name, old, new} => write!(f, "ride repriced: #{} {} {} -> {}", index, name,
+                money(i32::from(*old)), money(i32::from(*new))),
+            RideRatings{index, name, old, new} => write!(f, "ratings changed: #{} {} {} -> {}", index, name,
+                format_ratings(old), format_ratings(new)),
+            ResearchedRideType{ride_type} => write!(f, "researched ride type: {}", RideType::from(*ride_type).name()),
+            ResearchedRide{object} => write!(f, "researched ride: {}", object),
+            ResearchedScenery{count} => write!(f, "researched scenery: {} items", count),
+            Tiles{count, min, max} => write!(f, "map: {} tiles changed within ({}, {})-({}, {})", count, min.0, min.1, max.0, max.1)
+        }
+    }
+}
+
+fn ride_ratings(r: &Ride) -> Option<Ratings> {
+    if r.is_rated() {
+        Some(Ratings::new(i32::from(r.excitement), i32::from(r.intensity), i32::from(r.nausea)))
+    } else {
+        None
+    }
+}
+
+/// Pushes a change when a value stored in both saves differs.
+fn compare<T: PartialEq>(changes: &mut Vec<Change>, old: Option<T>, new: Option<T>, f: fn(T, T) -> Change) {
+    if let (Some(o), Some(n)) = (old, new) {
+        if o != n {
+            changes.push(f(o, n));
+        }
+    }
+}
+
+fn diff_rides(changes: &mut Vec<Change>, a: &SavedGame, b: &SavedGame) {
+    let (old, new) = (a.rides(), b.rides());
+    // A slot reused by a ride of another type is a different ride
+    let same = |o: &Ride, n: &Ride| o.index == n.index && o.ride_type == n.ride_type;
+    for o in old.iter().filter(|o| !new.iter().any(|n| same(o, n))) {
+        changes.push(Change::RideRemoved{index: o.index, name: a.ride_name(o), ride_type: o.ride_type});
+    }
+    for n in &new {
+        let name = b.ride_name(n);
+        let o = match old.iter().find(|o| same(o, n)) {
+            Some(o) => o,
+            None => {
+                changes.push(Change::RideAdded{index: n.index, name, ride_type: n.ride_type});
+                continue;
+            }
+        };
+        let old_name = a.ride_name(o);
+        if old_name != name {
+            changes.push(Change::RideRenamed{index: n.index, old: old_name, new: name.clone()});
+        }
+        if o.price != n.price {
+            changes.push(Change::RidePrice{index: n.index, name: name.clone(), old: o.price, new: n.price});
+        }
+        let (ro, rn) = (ride_ratings(o), ride_ratings(n));
+        if ro != rn && !RideType::from(n.ride_type).is_shop() {
+            changes.push(Change::RideRatings{index: n.index, name, old: ro, new: rn});
+        }
+    }
+}
+
+fn diff_research(changes: &mut Vec<Change>, a: &SavedGame, b: &SavedGame) {
+    let old_types = a.researched_ride_types();
+    for t in b.researched_ride_types().into_iter().filter(|t| !old_types.contains(t)) {
+        changes.push(Change::ResearchedRideType{ride_type: t});
+    }
+    let old_entries = a.researched_ride_entries();
+    let objects = b.object_list();
+    for i in b.researched_ride_entries().into_iter().filter(|i| !old_entries.contains(i)) {
+        let object = objects.get(usize::from(i)).filter(|e| !e.is_empty()).map_or_else(|| format!("ride object {}", i), |e| e.name());
+        changes.push(Change::ResearchedRide{object});
+    }
+    let old_scenery = a.researched_scenery_items();
+    let count = b.researched_scenery_items().iter().filter(|i| !old_scenery.contains(i)).count();
+    if count > 0 {
+        changes.push(Change::ResearchedScenery{count});
+    }
+}
+
+fn diff_map(changes: &mut Vec<Change>, a: &SavedGame, b: &SavedGame) {
+    let (ma, mb) = match (a.map(), b.map()) {
+        (Some(ma), Some(mb)) => (ma, mb),
+        _ => return
+    };
+    let mut count = 0;
+    let (mut min, mut max) = ((MAP_SIZE, MAP_SIZE), (0, 0));
+    for y in 0..MAP_SIZE {
+        for x in 0..MAP_SIZE {
+            if ma.tile(x, y) != mb.tile(x, y) {
+                count += 1;
+                min = (min.0.min(x), min.1.min(y));
+                max = (max.0.max(x), max.1.max(y));
+            }
+        }
+    }
+    if count > 0 {
+        changes.push(Change::Tiles{count, min, max});
+    }
+}
+
+/// Semantic differences between two saves of the same park: money, park stats, rides,
+/// research and the map.
+pub fn diff_saves(a: &SavedGame, b: &SavedGame) -> Vec<Change> {
+    let mut changes = Vec::new();
+    compare(&mut changes, a.cash(), b.cash(), |old, new| Change::Cash{old, new});
+    compare(&mut changes, a.loan(), b.loan(), |old, new| Change::Loan{old, new});
+    compare(&mut changes, a.park_rating(), b.park_rating(), |old, new| Change::ParkRating{old, new});
+    compare(&mut changes, a.guests_in_park(), b.guests_in_park(), |old, new| Change::GuestsInPark{old, new});
+    diff_rides(&mut changes, a, b);
+    diff_research(&mut changes, a, b);
+    diff_map(&mut changes, a, b);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ride::{self, RATING_UNDEFINED};
+    use crate::s6::{CUSTOM_STRINGS, GAME_DATA_SIZE, GUESTS_IN_PARK, RESEARCHED_RIDE_ENTRIES, RESEARCHED_RIDE_TYPES,
+        RESEARCHED_SCENERY_ITEMS, RIDES};
+    use crate::sawyer::{Chunk, Encoding};
+
+    /// Saved game with one ride object and four single element tiles.
+    fn save() -> SavedGame {
+        let mut list = Vec::new();
+        list.extend_from_slice(&0x0000_8000u32.to_le_bytes());
+        list.extend_from_slice(b"WOODCARS");
+        list.extend_from_slice(&0u32.to_le_bytes());
+        list.extend_from_slice(&[0xFF; 16]);
+        let mut map = vec![0; 4 * 8];
+        map.chunks_exact_mut(8).for_each(|e| e[1] = 0x80);
+        let mut game_data = vec![0; GAME_DATA_SIZE];
+        for i in 0..ride::MAX_RIDES {
+            game_data[RIDES + i * ride::RIDE_SIZE] = 0xFF;
+        }
+        let mut sg = SavedGame{
+            header: Chunk::new(Encoding::RLE, vec![0; 0x20]),
+            info: None,
+            packed: Vec::new(),
+            chunks: vec![
+                Chunk::new(Encoding::RLE, list),
+                Chunk::new(Encoding::RLE, vec![40, 0, 0, 0]),
+                Chunk::new(Encoding::RLECompressed, map),
+                Chunk::new(Encoding::RLE, game_data)
+            ],
+            checksum_salt: 0
+        };
+        sg.set_cash(10_000).unwrap();
+        sg.set_loan(5_000).unwrap();
+        sg.set_park_rating(500).unwrap();
+        sg.write_u16(GUESTS_IN_PARK, 100).unwrap();
+        sg
+    }
+
+    fn add_ride(sg: &mut SavedGame, index: u8, ride_type: u8, name: u16, ratings: [u16; 3], price: u16) {
+        let b = sg.game_data_mut(RIDES + usize::from(index) * ride::RIDE_SIZE, ride::RIDE_SIZE).unwrap();
+        b.iter_mut().for_each(|x| *x = 0);
+        b[0x000] = ride_type;
+        b[0x04A..0x04C].copy_from_slice(&name.to_le_bytes());
+        b[0x04E] = index + 1;
+        b[0x138..0x13A].copy_from_slice(&price.to_le_bytes());
+        for (i, r) in ratings.iter().enumerate() {
+            b[0x140 + i * 2..0x142 + i * 2].copy_from_slice(&r.to_le_bytes());
+        }
+    }
+
+    fn set_name(sg: &mut SavedGame, id: u16, name: &str) {
+        let b = sg.game_data_mut(CUSTOM_STRINGS + usize::from(id % 1024) * 32, 32).unwrap();
+        b.iter_mut().for_each(|x| *x = 0);
+        b[..name.len()].copy_from_slice(name.as_bytes());
+    }
+
+    #[test]
+    fn unchanged_saves() {
+        let mut a = save();
+        add_ride(&mut a, 0, 0x34, 0x8000, [650, 520, 300], 10);
+        assert!(diff_saves(&a, &a.clone()).is_empty());
+    }
+
+    #[test]
+    fn diffs_edited_saves() {
+        let mut a = save();
+        add_ride(&mut a, 0, 0x34, 0x8000, [RATING_UNDEFINED; 3], 10);
+        add_ride(&mut a, 1, 0x1C, 0, [0; 3], 15);
+        set_name(&mut a, 0x8000, "Woody");
+        let mut b = a.clone();
+        b.set_cash(12_500).unwrap();
+        b.set_loan(0).unwrap();
+        b.set_park_rating(520).unwrap();
+        b.write_u16(GUESTS_IN_PARK, 90).unwrap();
+        add_ride(&mut b, 0, 0x34, 0x8000, [650, 520, 300], 20);
+        add_ride(&mut b, 1, 0x34, 0, [RATING_UNDEFINED; 3], 0);
+        add_ride(&mut b, 4, 0x1C, 0, [0; 3], 15);
+        set_name(&mut b, 0x8000, "Timber");
+        b.game_data_mut(RESEARCHED_RIDE_TYPES + 6, 1).unwrap()[0] = 0x10;
+        b.game_data_mut(RESEARCHED_RIDE_ENTRIES, 1).unwrap()[0] = 0x21;
+        b.game_data_mut(RESEARCHED_SCENERY_ITEMS + 1, 1).unwrap()[0] = 0x07;
+        b.chunks[2].data_mut()[2 * 8 + 2] = 14;
+        b.chunks[2].data_mut()[3 * 8 + 3] = 18;
+
+        let changes = diff_saves(&a, &b);
+        assert_eq!(changes, [
+            Change::Cash{old: 10_000, new: 12_500},
+            Change::Loan{old: 5_000, new: 0},
+            Change::ParkRating{old: 500, new: 520},
+            Change::GuestsInPark{old: 100, new: 90},
+            Change::RideRemoved{index: 1, name: "Ride 2 (type 0x1C)".to_string(), ride_type: 0x1C},
+            Change::RideRenamed{index: 0, old: "Woody".to_string(), new: "Timber".to_string()},
+            Change::RidePrice{index: 0, name: "Timber".to_string(), old: 10, new: 20},
+            Change::RideRatings{index: 0, name: "Timber".to_string(), old: None, new: Some(Ratings::new(650, 520, 300))},
+            Change::RideAdded{index: 1, name: "Ride 2 (type 0x34)".to_string(), ride_type: 0x34},
+            Change::RideAdded{index: 4, name: "Ride 5 (type 0x1C)".to_string(), ride_type: 0x1C},
+            Change::ResearchedRideType{ride_type: 0x34},
+            Change::ResearchedRide{object: "WOODCARS".to_string()},
+            Change::ResearchedRide{object: "ride object 5".to_string()},
+            Change::ResearchedScenery{count: 3},
+            Change::Tiles{count: 2, min: (2, 0), max: (3, 0)}
+        ]);
+        let lines: Vec<_> = changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(lines[0], "cash: 1000.00 -> 1250.00 (+250.00)");
+        assert_eq!(lines[3], "guests in park: 100 -> 90 (-10)");
+        assert_eq!(lines[5], "ride renamed: #0 \"Woody\" -> \"Timber\"");
+        assert_eq!(lines[7], "ratings changed: #0 Timber not rated -> 6.50/5.20/3.00");
+        assert_eq!(lines[14], "map: 2 tiles changed within (2, 0)-(3, 0)");
+
+        // Going back undoes every change, with rides removed before they are added
+        let back = diff_saves(&b, &a);
+        assert_eq!(back[0], Change::Cash{old: 12_500, new: 10_000});
+        assert_eq!(back[4], Change::RideRemoved{index: 1, name: "Ride 2 (type 0x34)".to_string(), ride_type: 0x34});
+        assert_eq!(back[5], Change::RideRemoved{index: 4, name: "Ride 5 (type 0x1C)".to_string(), ride_type: 0x1C});
+        assert!(!back.iter().any(|c| matches!(c, Change::ResearchedRideType{..} | Change::ResearchedRide{..})));
+    }
+}
@@ -0,0 +1,156 @@
+pub const EXPENDITURE_TYPES: usize = 14;
+/// Months kept in the income and expenditure table, the current one first.
+pub const EXPENDITURE_MONTHS: usize = 16;
+/// Entries of each finance graph.
+pub const GRAPH_SIZE: usize = 128;
+/// Marks graph entries which have not been recorded yet.
+pub const MONEY_UNDEFINED: i32 = i32::MIN;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExpenditureType {
+    RideConstruction,
+    RideRunningCosts,
+    LandPurchase,
+    Landscaping,
+    ParkEntranceTickets,
+    RideTickets,
+    ShopSales,
+    ShopStock,
+    FoodDrinkSales,
+    FoodDrinkStock,
+    Wages,
+    Marketing,
+    Research,
+    Interest,
+    Unknown
+}
+
+impl From<u8> for ExpenditureType {
+    fn from(n: u8) -> Self {
+        use ExpenditureType::*;
+        match n {
+            0 => RideConstruction,
+            1 => RideRunningCosts,
+            2 => LandPurchase,
+            3 => Landscaping,
+            4 => ParkEntranceTickets,
+            5 => RideTickets,
+            6 => ShopSales,
+            7 => ShopStock,
+            8 => FoodDrinkSales,
+            9 => FoodDrinkStock,
+            10 => Wages,
+            11 => Marketing,
+            12 => Research,
+            13 => Interest,
+            _ => Unknown
+        }
+    }
+}
+
+impl ExpenditureType {
+    pub fn name(self) -> &'static str {
+        use ExpenditureType::*;
+        match self {
+            RideConstruction => "Ride construction",
+            RideRunningCosts => "Ride running costs",
+            LandPurchase => "Land purchase",
+            Landscaping => "Landscaping",
+            ParkEntranceTickets => "Park entrance tickets",
+            RideTickets => "Ride tickets",
+            ShopSales => "Shop sales",
+            ShopStock => "Shop stock",
+            FoodDrinkSales => "Food/drink sales",
+            FoodDrinkStock => "Food/drink stock",
+            Wages => "Staff wages",
+            Marketing => "Marketing",
+            Research => "Research",
+            Interest => "Loan interest",
+            Unknown => "Unknown"
+        }
+    }
+}
+
+/// Money of a saved game, in tenths of the currency unit. Histories are most recent first
+/// and are empty in scenarios, which do not store them.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Finances {
+    pub cash: i32,
+    pub loan: i32,
+    pub max_loan: i32,
+    pub park_value: i32,
+    pub company_value: i32,
+    pub current_expenditure: i32,
+    pub current_profit: i32,
+    pub total_admissions: u32,
+    pub income_from_admissions: i32,
+    /// Income (positive) and spending (negative) of each month, indexed by `ExpenditureType`.
+    pub expenditure: Vec<[i32; EXPENDITURE_TYPES]>,
+    /// Cash minus loan, one entry a week.
+    pub cash_history: Vec<i32>,
+    pub weekly_profit_history: Vec<i32>,
+    pub park_value_history: Vec<i32>
+}
+
+impl Finances {
+    /// Sum of a month's income and spending.
+    pub fn month_total(&self, month: usize) -> Option<i32> {
+        self.expenditure.get(month).map(|m| m.iter().fold(0i32, |a, &v| a.wrapping_add(v)))
+    }
+}
+
+/// Formats an amount in tenths of the currency unit with two decimals, as in `1500.50`.
+pub fn format_money(v: i32) -> String {
+    format!("{:.2}", f64::from(v) / 10.0)
+}
+
+/// Reads a finance graph, leaving out entries which have not been recorded.
+pub fn read_graph(b: &[u8]) -> Vec<i32> {
+    b.chunks_exact(4)
+        .map(|c| i32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .take_while(|&v| v != MONEY_UNDEFINED)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_money() {
+        assert_eq!(format_money(0), "0.00");
+        assert_eq!(format_money(15_005), "1500.50");
+        assert_eq!(format_money(7), "0.70");
+        assert_eq!(format_money(-25), "-2.50");
+        assert_eq!(format_money(-5), "-0.50");
+        assert_eq!(format_money(i32::MAX), "214748364.70");
+        assert_eq!(format_money(i32::MIN), "-214748364.80");
+    }
+
+    #[test]
+    fn reads_graphs() {
+        let mut b = Vec::new();
+        for v in [100, -20, MONEY_UNDEFINED, 5] {
+            b.extend_from_slice(&v.to_le_bytes());
+        }
+        b.push(0);
+        assert_eq!(read_graph(&b), [100, -20]);
+        assert_eq!(read_graph(&b[..6]), [100]);
+        assert!(read_graph(&[]).is_empty());
+    }
+
+    #[test]
+    fn month_totals() {
+        let mut month = [0; EXPENDITURE_TYPES];
+        month[ExpenditureType::RideTickets as usize] = 5_000;
+        month[ExpenditureType::Wages as usize] = -1_200;
+        let f = Finances{expenditure: vec![month, [i32::MAX, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]], ..Default::default()};
+        assert_eq!(f.month_total(0), Some(3_800));
+        assert_eq!(f.month_total(1), Some(i32::MIN));
+        assert_eq!(f.month_total(2), None);
+        assert_eq!(ExpenditureType::from(10), ExpenditureType::Wages);
+        assert_eq!(ExpenditureType::from(14).name(), "Unknown");
+    }
+}
@@ -0,0 +1,489 @@
+use std::fmt;
+use std::io::{Cursor, Read};
+use crate::object::DatObject;
+use crate::rctrle;
+use crate::s6::SavedGame;
+use crate::sawyer::{ChunkReader, Encoding};
+use crate::td6::{self, TrackDesign};
+use crate::ride_type::RideType;
+use crate::util::u32_from_slice;
+
+/// Size of the decoded header chunk of saved games and scenarios.
+const S6_HEADER_SIZE: usize = 0x20;
+/// Header flag set by RCT Classic.
+const S6_CLASSIC_FLAG: u8 = 0x0F;
+/// OpenRCT2 park files start with "PARK".
+const PARK_MAGIC: &[u8; 4] = b"PARK";
+/// Decoded RCT1 saves are far larger than any track design.
+const MIN_S4_SIZE: usize = 0x1_0000;
+/// Salts of RCT1 track designs from Added Attractions and Loopy Landscapes; others use the RCT2 one.
+const TD4_AA_SALT: u32 = 0x1_A67C;
+const TD4_LL_SALT: u32 = 0x1_A650;
+/// Encrypted files are only guessed at above this size and entropy (bits per byte).
+const MIN_SEA_SIZE: usize = 0x1_0000;
+const MIN_SEA_ENTROPY: f64 = 7.9;
+
+/// Kind of file this crate can read, recognised from its contents.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileKind {
+    SavedGame,
+    Scenario,
+    TrackDesign,
+    Object,
+    Unknown
+}
+
+impl FileKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FileKind::SavedGame => "saved game (SV6)",
+            FileKind::Scenario => "scenario (SC6)",
+            FileKind::TrackDesign => "track design (TD6)",
+            FileKind::Object => "object (DAT)",
+            FileKind::Unknown => "unknown"
+        }
+    }
+}
+
+/// Every file format that can be told apart, including ones this crate cannot read.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileType {
+    Sv6,
+    Sc6,
+    Td6,
+    Td4,
+    Sv4,
+    Sc4,
+    /// RCT1 saved game or scenario from Added Attractions or Loopy Landscapes.
+    Sv7,
+    Sc7,
+    Dat,
+    /// OpenRCT2 park or scenario.
+    Park,
+    /// RCT Classic encrypted saved game.
+    Sea,
+    Unknown
+}
+
+impl FileType {
+    pub fn name(self) -> &'static str {
+        match self {
+            FileType::Sv6 => "saved game (SV6)",
+            FileType::Sc6 => "scenario (SC6)",
+            FileType::Td6 => "track design (TD6)",
+            FileType::Td4 => "RCT1 track design (TD4)",
+            FileType::Sv4 => "RCT1 saved game (SV4)",
+            FileType::Sc4 => "RCT1 scenario (SC4)",
+            FileType::Sv7 => "RCT1 expansion saved game (SV7)",
+            FileType::Sc7 => "RCT1 expansion scenario (SC7)",
+            FileType::Dat => "object (DAT)",
+            FileType::Park => "OpenRCT2 park (PARK)",
+            FileType::Sea => "RCT Classic saved game (SEA)",
+            FileType::Unknown => "unknown"
+        }
+    }
+
+    /// Usual file extension, without the dot.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            FileType::Sv6 => Some("sv6"),
+            FileType::Sc6 => Some("sc6"),
+            FileType::Td6 => Some("td6"),
+            FileType::Td4 => Some("td4"),
+            FileType::Sv4 => Some("sv4"),
+            FileType::Sc4 => Some("sc4"),
+            FileType::Sv7 => Some("sv7"),
+            FileType::Sc7 => Some("sc7"),
+            FileType::Dat => Some("dat"),
+            FileType::Park => Some("park"),
+            FileType::Sea => Some("sea"),
+            FileType::Unknown => None
+        }
+    }
+
+    /// What the crate can read the file as, if anything.
+    pub fn kind(self) -> FileKind {
+        match self {
+            FileType::Sv6 => FileKind::SavedGame,
+            FileType::Sc6 => FileKind::Scenario,
+            FileType::Td6 => FileKind::TrackDesign,
+            FileType::Dat => FileKind::Object,
+            _ => FileKind::Unknown
+        }
+    }
+}
+
+/// Game or expansion a file was made with.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Game {
+    Rct1,
+    AddedAttractions,
+    LoopyLandscapes,
+    Rct2,
+    WackyWorlds,
+    TimeTwister,
+    Classic,
+    OpenRct2
+}
+
+impl Game {
+    pub fn name(self) -> &'static str {
+        match self {
+            Game::Rct1 => "RCT1",
+            Game::AddedAttractions => "RCT1: Added Attractions",
+            Game::LoopyLandscapes => "RCT1: Loopy Landscapes",
+            Game::Rct2 => "RCT2",
+            Game::WackyWorlds => "RCT2: Wacky Worlds",
+            Game::TimeTwister => "RCT2: Time Twister",
+            Game::Classic => "RCT Classic",
+            Game::OpenRct2 => "OpenRCT2"
+        }
+    }
+
+    /// Game named by the source nibble of an object entry; custom objects have none.
+    pub fn from_object_source(source: u8) -> Option<Self> {
+        match source {
+            1 => Some(Game::WackyWorlds),
+            2 => Some(Game::TimeTwister),
+            3 => Some(Game::OpenRct2),
+            4 => Some(Game::Rct1),
+            5 => Some(Game::AddedAttractions),
+            6 => Some(Game::LoopyLandscapes),
+            8 => Some(Game::Rct2),
+            _ => None
+        }
+    }
+}
+
+/// How sure detection is about a format.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Confidence {
+    /// Guessed from the look of the data.
+    Low,
+    /// The structure matches but a checksum or version does not.
+    Medium,
+    /// Magic number or checksum confirmed and the contents read.
+    High
+}
+
+impl Confidence {
+    pub fn name(self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high"
+        }
+    }
+}
+
+/// Result of `detect_format`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Format {
+    pub file_type: FileType,
+    pub confidence: Confidence,
+    pub game: Option<Game>,
+    /// Version number stored in the file, where it has one.
+    pub version: Option<u32>
+}
+
+impl Format {
+    pub const UNKNOWN: Format = Format{file_type: FileType::Unknown, confidence: Confidence::Low, game: None, version: None};
+
+    fn new(file_type: FileType, confidence: Confidence, game: Option<Game>, version: Option<u32>) -> Self {
+        Format{file_type, confidence, game, version}
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file_type.name())?;
+        if let Some(g) = self.game {
+            write!(f, ", {}", g.name())?;
+        }
+        if let Some(v) = self.version {
+            write!(f, ", version {}", v)?;
+        }
+        write!(f, " ({} confidence)", self.confidence.name())
+    }
+}
+
+fn is_chunk_start(b: &[u8], at: usize) -> bool {
+    b.len() >= at + 5 && Encoding::from(b[at]) != Encoding::Unknown
+}
+
+/// OpenRCT2 parks start with a magic number, then the version written and the oldest version able to read it.
+fn detect_park(b: &[u8]) -> Option<Format> {
+    if b.len() < 16 || &b[..4] != PARK_MAGIC {
+        return None;
+    }
+    Some(Format::new(FileType::Park, Confidence::High, Some(Game::OpenRct2), Some(u32_from_slice(b, 4))))
+}
+
+/// Objects are a 16 byte entry followed by a single chunk filling the rest of the file.
+fn detect_object(b: &[u8]) -> Option<Format> {
+    if !is_chunk_start(b, 16) || u32_from_slice(b, 17) as usize != b.len() - 21 {
+        return None;
+    }
+    let confidence = match DatObject::read(&mut Cursor::new(b)) {
+        Ok(o) if o.is_valid() => Confidence::High,
+        Ok(_) => Confidence::Medium,
+        Err(_) => Confidence::Low
+    };
+    let source = ((u32_from_slice(b, 0) & 0xF0) >> 4) as u8;
+    Some(Format::new(FileType::Dat, confidence, Game::from_object_source(source), None))
+}
+
+/// Saves start with a chunk holding the 32 byte header: type (0 saved game, 1 scenario),
+/// RCT Classic flag, packed object count and version.
+fn detect_s6(b: &[u8]) -> Option<Format> {
+    if !is_chunk_start(b, 0) || u32_from_slice(b, 1) as usize > b.len() - 5 {
+        return None;
+    }
+    let header = ChunkReader::new(Cursor::new(b)).read_chunk().ok()?;
+    let h = header.data();
+    if h.len() != S6_HEADER_SIZE {
+        return None;
+    }
+    let file_type = match h[0] {
+        0 => FileType::Sv6,
+        1 => FileType::Sc6,
+        _ => return None
+    };
+    let game = if h[1] == S6_CLASSIC_FLAG { Game::Classic } else { Game::Rct2 };
+    let confidence = if SavedGame::read(Cursor::new(b)).is_ok() { Confidence::High } else { Confidence::Medium };
+    Some(Format::new(file_type, confidence, Some(game), Some(u32_from_slice(h, 4))))
+}
+
+/// Track designs and RCT1 saves are RLE encoded as a whole, followed by a rotating checksum.
+fn decode_whole_file(b: &[u8]) -> Option<Vec<u8>> {
+    if b.len() < 8 {
+        return None;
+    }
+    let mut v = Vec::new();
+    rctrle::Reader::new(&b[..b.len()-4]).read_to_end(&mut v).ok()?;
+    Some(v)
+}
+
+/// RCT1 saves sign themselves with the game version, added to the checksum for saved games
+/// and subtracted for scenarios. Saves from the expansions (`SV7`, `SC7`) share the layout and
+/// differ only in that version.
+fn detect_s4(b: &[u8]) -> Option<Format> {
+    let diff = u32_from_slice(b, b.len() - 4).wrapping_sub(td6::rotating_checksum(&b[..b.len()-4])) as i32;
+    let version = diff.unsigned_abs();
+    let game = match version {
+        108_000..=109_999 => Game::Rct1,
+        110_000..=119_999 => Game::AddedAttractions,
+        // Some custom scenarios leave the version out
+        120_000..=129_999 | 0 => Game::LoopyLandscapes,
+        _ => return None
+    };
+    let file_type = match (game, diff > 0) {
+        (Game::Rct1, true) => FileType::Sv4,
+        (Game::Rct1, false) => FileType::Sc4,
+        (_, true) => FileType::Sv7,
+        (_, false) => FileType::Sc7
+    };
+    Some(Format::new(file_type, Confidence::High, Some(game), Some(version)))
+}
+
+/// Track designs keep their version in bits 2-3 of byte 7: 0 and 1 for RCT1 (TD4), 2 for RCT2 (TD6).
+fn detect_track(b: &[u8], decoded: &[u8]) -> Option<Format> {
+    if decoded.len() < 8 {
+        return None;
+    }
+    let salt = td6::rotating_checksum(&b[..b.len()-4]).wrapping_sub(u32_from_slice(b, b.len() - 4));
+    match (decoded[7] >> 2) & 3 {
+        2 => {
+            let td = TrackDesign::from_data(decoded).ok()?;
+            let confidence = if salt == td6::CHECKSUM_SALT {
+                Confidence::High
+            } else if RideType::from(td.ride_type) != RideType::Unknown {
+                Confidence::Medium
+            } else {
+                return None;
+            };
+            Some(Format::new(FileType::Td6, confidence, Some(Game::Rct2), Some(2)))
+        },
+        // Without a known checksum there is too little structure to tell a TD4 from noise
+        v @ (0 | 1) => {
+            let game = match salt {
+                TD4_LL_SALT => Game::LoopyLandscapes,
+                TD4_AA_SALT | td6::CHECKSUM_SALT if v == 1 => Game::AddedAttractions,
+                TD4_AA_SALT | td6::CHECKSUM_SALT => Game::Rct1,
+                _ => return None
+            };
+            Some(Format::new(FileType::Td4, Confidence::High, Some(game), Some(u32::from(v))))
+        },
+        _ => None
+    }
+}
+
+/// Bits of information per byte.
+fn entropy(b: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &x in b {
+        counts[x as usize] += 1;
+    }
+    let n = b.len() as f64;
+    counts.iter().filter(|&&c| c > 0).map(|&c| {
+        let p = c as f64 / n;
+        -p * p.log2()
+    }).sum()
+}
+
+/// RCT Classic encrypts its saves, so they can only be guessed at from data that looks random.
+fn detect_sea(b: &[u8]) -> Option<Format> {
+    if b.len() >= MIN_SEA_SIZE && entropy(b) >= MIN_SEA_ENTROPY {
+        Some(Format::new(FileType::Sea, Confidence::Low, Some(Game::Classic), None))
+    } else {
+        None
+    }
+}
+
+/// Works out what a file is from its headers and checksums, without relying on its extension.
+/// RCT1 saves are `SV4`/`SC4` or, when made with an expansion, `SV7`/`SC7`.
+pub fn detect_format(b: &[u8]) -> Format {
+    if let Some(f) = detect_park(b).or_else(|| detect_object(b)).or_else(|| detect_s6(b)) {
+        return f;
+    }
+    if let Some(decoded) = decode_whole_file(b) {
+        let f = if decoded.len() >= MIN_S4_SIZE { detect_s4(b) } else { detect_track(b, &decoded) };
+        if let Some(f) = f {
+            return f;
+        }
+    }
+    detect_sea(b).unwrap_or(Format::UNKNOWN)
+}
+
+/// Kind of file as far as reading it goes; formats the crate cannot read are `Unknown`.
+pub fn detect_kind(b: &[u8]) -> FileKind {
+    detect_format(b).file_type.kind()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::ObjectEntry;
+    use crate::sawyer::{Chunk, ChunkWriter};
+
+    /// Whole-file RLE data signed with `checksum(rotating checksum)`.
+    fn signed(data: &[u8], checksum: impl Fn(u32) -> u32) -> Vec<u8> {
+        let mut v = rctrle::encode(data);
+        let c = checksum(td6::rotating_checksum(&v));
+        v.extend_from_slice(&c.to_le_bytes());
+        v
+    }
+
+    fn s6_file(kind: u8, classic: bool) -> Vec<u8> {
+        let mut header = vec![0u8; S6_HEADER_SIZE];
+        header[0] = kind;
+        if classic {
+            header[1] = S6_CLASSIC_FLAG;
+        }
+        header[4..8].copy_from_slice(&120_001u32.to_le_bytes());
+        let mut v = Vec::new();
+        let mut w = ChunkWriter::new(&mut v);
+        w.write_chunk(&Chunk::new(Encoding::RLE, header)).unwrap();
+        w.write_chunk(&Chunk::new(Encoding::RLE, vec![0xFF; 32])).unwrap();
+        v.extend_from_slice(&[0; 4]);
+        v
+    }
+
+    fn td_data(version: u8) -> Vec<u8> {
+        let mut v = vec![0u8; 0xA3];
+        v[0] = 0x34;
+        v[7] = version << 2;
+        v.extend_from_slice(&[0x02, 0, 0xFF, 0xFF, 0xFF]);
+        v
+    }
+
+    #[test]
+    fn parks() {
+        let mut b = b"PARK".to_vec();
+        b.extend_from_slice(&[10, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(detect_format(&b), Format::new(FileType::Park, Confidence::High, Some(Game::OpenRct2), Some(10)));
+    }
+
+    #[test]
+    fn objects() {
+        let mut o = DatObject{entry: ObjectEntry{flags: 0x80, name: *b"TOILETS ", checksum: 0}, encoding: Encoding::RLE, data: vec![3; 40]};
+        let file = |o: &DatObject| {
+            let mut v = Vec::new();
+            o.write(&mut v).unwrap();
+            v
+        };
+        assert_eq!(detect_format(&file(&o)), Format::new(FileType::Dat, Confidence::Medium, Some(Game::Rct2), None));
+        o.entry.checksum = o.calculate_checksum();
+        assert_eq!(detect_format(&file(&o)).confidence, Confidence::High);
+        assert_eq!(detect_kind(&file(&o)), FileKind::Object);
+    }
+
+    #[test]
+    fn saves() {
+        assert_eq!(detect_format(&s6_file(0, false)), Format::new(FileType::Sv6, Confidence::High, Some(Game::Rct2), Some(120_001)));
+        assert_eq!(detect_format(&s6_file(1, false)).file_type, FileType::Sc6);
+        assert_eq!(detect_format(&s6_file(0, true)).game, Some(Game::Classic));
+        assert_eq!(detect_format(&s6_file(2, false)), Format::UNKNOWN);
+    }
+
+    #[test]
+    fn track_designs() {
+        let f = detect_format(&signed(&td_data(2), |c| c.wrapping_sub(td6::CHECKSUM_SALT)));
+        assert_eq!(f, Format::new(FileType::Td6, Confidence::High, Some(Game::Rct2), Some(2)));
+        assert_eq!(detect_format(&signed(&td_data(2), |c| c)).confidence, Confidence::Medium);
+        let f = detect_format(&signed(&td_data(1), |c| c.wrapping_sub(TD4_LL_SALT)));
+        assert_eq!((f.file_type, f.game), (FileType::Td4, Some(Game::LoopyLandscapes)));
+        assert_eq!(detect_format(&signed(&td_data(0), |c| c.wrapping_sub(TD4_AA_SALT))).game, Some(Game::Rct1));
+        assert_eq!(detect_format(&signed(&td_data(0), |c| c)), Format::UNKNOWN);
+    }
+
+    #[test]
+    fn rct1_saves() {
+        let data = vec![7u8; MIN_S4_SIZE];
+        let save = |version: u32| detect_format(&signed(&data, |c| c.wrapping_add(version)));
+        let scenario = |version: u32| detect_format(&signed(&data, |c| c.wrapping_sub(version)));
+        assert_eq!(save(108_001), Format::new(FileType::Sv4, Confidence::High, Some(Game::Rct1), Some(108_001)));
+        assert_eq!(scenario(108_001).file_type, FileType::Sc4);
+        assert_eq!((save(110_001).file_type, save(110_001).game), (FileType::Sv7, Some(Game::AddedAttractions)));
+        assert_eq!((scenario(120_001).file_type, scenario(120_001).game), (FileType::Sc7, Some(Game::LoopyLandscapes)));
+        assert_eq!(scenario(0).file_type, FileType::Sc7);
+        assert_eq!(save(200_000), Format::UNKNOWN);
+    }
+
+    #[test]
+    fn encrypted_saves() {
+        let mut x = 1u32;
+        let noise: Vec<u8> = (0..MIN_SEA_SIZE).map(|_| {
+            x = x.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (x >> 24) as u8
+        }).collect();
+        assert_eq!(detect_format(&noise).file_type, FileType::Sea);
+        assert_eq!(detect_format(&noise[..MIN_SEA_SIZE - 1]), Format::UNKNOWN);
+    }
+
+    #[test]
+    fn short_and_truncated_input() {
+        assert_eq!(detect_format(&[]), Format::UNKNOWN);
+        assert_eq!(detect_format(&[0, 0, 0, 0, 0]), Format::UNKNOWN);
+        let s6 = s6_file(0, false);
+        let td = signed(&td_data(2), |c| c.wrapping_sub(td6::CHECKSUM_SALT));
+        for f in [&s6, &td] {
+            for n in 0..f.len() {
+                detect_format(&f[..n]);
+            }
+        }
+        assert_eq!(detect_format(&s6[..s6.len() - 3]).confidence, Confidence::Medium);
+    }
+
+    #[test]
+    fn display() {
+        let f = Format::new(FileType::Sv7, Confidence::High, Some(Game::LoopyLandscapes), Some(120_001));
+        assert_eq!(f.to_string(), "RCT1 expansion saved game (SV7), RCT1: Loopy Landscapes, version 120001 (high confidence)");
+        assert_eq!(FileType::Sc7.extension(), Some("sc7"));
+    }
+}
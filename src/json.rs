@@ -0,0 +1,374 @@
+use std::io::{self, Cursor};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::format::{FileKind, Format};
+use crate::object::{DatObject, ObjectEntry};
+use crate::repository::ObjectRepository;
+use crate::s6::{SavedGame, MAX_ENTRANCE_FEE, MAX_PARK_RATING, MAX_RIDE_PRICE};
+use crate::sawyer::Encoding;
+use crate::td6::TrackDesign;
+use crate::util;
+
+/// Serializes a value, adding a `name` field to objects.
+fn named<T: Serialize>(v: &T, name: String) -> Value {
+    let mut v = serde_json::to_value(v).unwrap_or(Value::Null);
+    if let Value::Object(m) = &mut v {
+        m.insert("name".to_string(), Value::String(name));
+    }
+    v
+}
+
+/// Object entry with its identifier and type spelled out; `missing` is only set when
+/// the objects were looked up.
+pub fn object_entry(e: &ObjectEntry, missing: Option<bool>) -> Value {
+    let mut v = json!({
+        "name": e.name(),
+        "type": e.object_type(),
+        "flags": e.flags,
+        "checksum": e.checksum,
+        "custom": e.is_custom()
+    });
+    if let Some(m) = missing {
+        v["missing"] = Value::Bool(m);
+    }
+    v
+}
+
+pub fn format(f: &Format) -> Value {
+    json!({
+        "type": f.file_type,
+        "name": f.file_type.name(),
+        "confidence": f.confidence,
+        "game": f.game,
+        "version": f.version
+    })
+}
+
+pub fn track_design(td: &TrackDesign) -> Value {
+    serde_json::to_value(td).unwrap_or(Value::Null)
+}
+
+fn hex(b: &[u8]) -> Value {
+    util::hex_bytes::serialize(b, serde_json::value::Serializer).unwrap_or(Value::Null)
+}
+
+/// Object header, whether its checksum holds, the decoded ride object if it is one and the
+/// decoded chunk.
+pub fn object(o: &DatObject) -> Value {
+    let ride = o.ride_object().and_then(Result::ok);
+    json!({
+        "entry": object_entry(&o.entry, None),
+        "encoding": o.encoding,
+        "valid": o.is_valid(),
+        "name": ride.as_ref().map(|r| r.name.english()),
+        "ride": ride,
+        "data": hex(&o.data)
+    })
+}
+
+/// Everything read from a saved game or scenario, with names resolved, and the whole file as
+/// hex so the document can be converted back.
+pub fn saved_game(sg: &SavedGame, repo: Option<&ObjectRepository>) -> Value {
+    let refs = sg.objects();
+    let missing = repo.map(|r| r.missing(&refs));
+    let is_missing = |e: &ObjectEntry| missing.as_ref().map(|m| m.iter().any(|x| x.matches(e)));
+    let mut file = Vec::new();
+    let file = match sg.write(&mut file) {
+        Ok(()) => hex(&file),
+        Err(_) => Value::Null
+    };
+    json!({
+        "scenario": sg.is_scenario(),
+        "months": sg.months(),
+        "objects": refs.iter().map(|e| object_entry(e, is_missing(e))).collect::<Vec<_>>(),
+        "packed": sg.packed.iter().map(|o| object_entry(&o.entry, None)).collect::<Vec<_>>(),
+        "park": {
+            "flags": sg.park_flags(),
+            "entrance_fee": sg.entrance_fee(),
+            "rating": sg.park_rating(),
+            "guests_in_park": sg.guests_in_park(),
+            "initial_cash": sg.initial_cash()
+        },
+        "finances": sg.finances(),
+        "rides": sg.rides().iter().map(|r| named(r, sg.ride_name(r))).collect::<Vec<_>>(),
+        "guests": sg.guests().iter().map(|g| named(g, sg.guest_name(g))).collect::<Vec<_>>(),
+        "staff": sg.staff().iter().map(|s| named(s, sg.staff_name(s))).collect::<Vec<_>>(),
+        "file": file
+    })
+}
+
+/// A whole file as JSON, `{"kind": ..., "data": ...}`, as printed by `dump --format json`.
+/// Track designs are stored field by field and objects as their entry and decoded chunk. Saves
+/// are read back from their `file`, then the park's flags, entrance fee and rating, the cash and
+/// loans and the ride prices are applied on top; the other fields only describe the save.
+#[derive(Clone)]
+pub enum Document {
+    TrackDesign(TrackDesign),
+    SavedGame(SavedGame),
+    Object(DatObject)
+}
+
+#[derive(Deserialize)]
+struct RawDocument {
+    kind: FileKind,
+    data: Value
+}
+
+#[derive(Deserialize)]
+struct EntryFields {
+    #[serde(with = "crate::util::name_bytes")]
+    name: [u8; 8],
+    flags: u32,
+    checksum: u32
+}
+
+#[derive(Deserialize)]
+struct ObjectFields {
+    entry: EntryFields,
+    encoding: Encoding,
+    #[serde(with = "crate::util::hex_bytes")]
+    data: Vec<u8>
+}
+
+#[derive(Default, Deserialize)]
+struct ParkFields {
+    flags: Option<u32>,
+    entrance_fee: Option<u16>,
+    rating: Option<u16>
+}
+
+#[derive(Deserialize)]
+struct FinanceFields {
+    cash: i32,
+    loan: i32,
+    max_loan: i32
+}
+
+#[derive(Deserialize)]
+struct RideFields {
+    index: u8,
+    price: u16
+}
+
+/// Parts of a save document which are written back; money is in tenths of the currency unit.
+#[derive(Deserialize)]
+struct SaveFields {
+    #[serde(with = "crate::util::hex_bytes")]
+    file: Vec<u8>,
+    #[serde(default)]
+    park: ParkFields,
+    finances: Option<FinanceFields>,
+    /// Rides left out keep their price.
+    #[serde(default)]
+    rides: Vec<RideFields>
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// The element, entrance and scenery lists end at an 0xFF byte, so edited values must not start with one.
+fn check_track_design(td: &TrackDesign) -> io::Result<()> {
+    if let Some(i) = td.elements.iter().position(|e| e.kind == 0xFF) {
+        return Err(invalid(format!("track element {} has kind 0xFF, which ends the element list", i)));
+    }
+    if let Some(i) = td.entrances.iter().position(|e| e.z == -1) {
+        return Err(invalid(format!("entrance {} has z -1, which ends the entrance list", i)));
+    }
+    if let Some(i) = td.scenery.iter().position(|s| s.entry.flags & 0xFF == 0xFF) {
+        return Err(invalid(format!("scenery {} has object flags ending in 0xFF, which ends the scenery list", i)));
+    }
+    Ok(())
+}
+
+/// Reads the save stored in a document and applies the edited fields to it.
+fn read_saved_game(f: SaveFields) -> io::Result<SavedGame> {
+    let mut sg = SavedGame::read(Cursor::new(f.file))?;
+    let no_park = || invalid("the save has no park data to edit".to_string());
+    if let Some(v) = f.park.flags {
+        sg.set_park_flags(v).ok_or_else(no_park)?;
+    }
+    if let Some(v) = f.park.entrance_fee {
+        if v > MAX_ENTRANCE_FEE {
+            return Err(invalid(format!("entrance fee {} is above {}", v, MAX_ENTRANCE_FEE)));
+        }
+        sg.set_entrance_fee(v).ok_or_else(no_park)?;
+    }
+    if let Some(v) = f.park.rating {
+        if v > MAX_PARK_RATING {
+            return Err(invalid(format!("park rating {} is above {}", v, MAX_PARK_RATING)));
+        }
+        sg.set_park_rating(v).ok_or_else(no_park)?;
+    }
+    if let Some(m) = f.finances {
+        sg.set_cash(m.cash).and_then(|_| sg.set_loan(m.loan)).and_then(|_| sg.set_max_loan(m.max_loan)).ok_or_else(no_park)?;
+    }
+    let rides = sg.rides();
+    for r in &f.rides {
+        if !rides.iter().any(|x| x.index == r.index) {
+            return Err(invalid(format!("there is no ride {}", r.index)));
+        }
+        if r.price > MAX_RIDE_PRICE {
+            return Err(invalid(format!("ride {} price {} is above {}", r.index, r.price, MAX_RIDE_PRICE)));
+        }
+        sg.set_ride_price(r.index, r.price).ok_or_else(no_park)?;
+    }
+    Ok(sg)
+}
+
+impl Document {
+    /// Parses a document, checking that it can be written as a valid file.
+    pub fn from_json(s: &str) -> io::Result<Self> {
+        let doc = serde_json::from_str::<RawDocument>(s).map_err(|e| invalid(e.to_string()))?;
+        let fields = |e: serde_json::Error| invalid(format!("{}: {}", doc.kind.name(), e));
+        match doc.kind {
+            FileKind::TrackDesign => {
+                let td = TrackDesign::deserialize(&doc.data).map_err(fields)?;
+                check_track_design(&td)?;
+                Ok(Document::TrackDesign(td))
+            },
+            FileKind::SavedGame | FileKind::Scenario => {
+                let sg = read_saved_game(SaveFields::deserialize(&doc.data).map_err(fields)?)?;
+                if sg.is_scenario() != (doc.kind == FileKind::Scenario) {
+                    return Err(invalid(format!("the file stored in a {} document is not one", doc.kind.name())));
+                }
+                Ok(Document::SavedGame(sg))
+            },
+            FileKind::Object => {
+                let o = ObjectFields::deserialize(&doc.data).map_err(fields)?;
+                let entry = ObjectEntry{flags: o.entry.flags, name: o.entry.name, checksum: o.entry.checksum};
+                Ok(Document::Object(DatObject{entry, encoding: o.encoding, data: o.data}))
+            },
+            FileKind::Unknown => Err(invalid("documents of unknown files cannot be converted".to_string()))
+        }
+    }
+
+    /// The document, with missing objects marked when a repository is given.
+    pub fn to_value(&self, repo: Option<&ObjectRepository>) -> Value {
+        let (kind, data) = match self {
+            Document::TrackDesign(td) => (FileKind::TrackDesign, track_design(td)),
+            Document::SavedGame(sg) if sg.is_scenario() => (FileKind::Scenario, saved_game(sg, repo)),
+            Document::SavedGame(sg) => (FileKind::SavedGame, saved_game(sg, repo)),
+            Document::Object(o) => (FileKind::Object, object(o))
+        };
+        json!({"kind": kind, "data": data})
+    }
+
+    /// Pretty printed, so documents diff well under version control.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.to_value(None)).unwrap_or_default()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ride;
+    use crate::s6::{GAME_DATA_SIZE, RIDES};
+    use crate::sawyer::Chunk;
+
+    /// Saved game with one wooden coaster in ride slot 3, priced at 2.50.
+    fn save() -> SavedGame {
+        let mut game_data = vec![0u8; GAME_DATA_SIZE];
+        for i in 0..ride::MAX_RIDES {
+            game_data[RIDES + i * ride::RIDE_SIZE] = 0xFF;
+        }
+        let r = RIDES + 3 * ride::RIDE_SIZE;
+        game_data[r] = 0x34;
+        game_data[r + 0x138] = 25;
+        let chunks = vec![
+            Chunk::new(Encoding::RLE, vec![0xFF; 16]),
+            Chunk::new(Encoding::RLE, vec![0; 4]),
+            Chunk::new(Encoding::RLE, Vec::new()),
+            Chunk::new(Encoding::RLE, game_data)
+        ];
+        SavedGame{header: Chunk::new(Encoding::RLE, vec![0; 0x20]), info: None, packed: Vec::new(), chunks, checksum_salt: 0}
+    }
+
+    fn track_design() -> TrackDesign {
+        let mut v = vec![0u8; 0xA3];
+        v[0] = 0x34;
+        v[7] = 2 << 2;
+        v.extend_from_slice(&[0x02, 0, 0x00, 0x80, 0xFF, 0, 2, 0x20, 0, 0, 0, 0xFF, 0xFF, 1, 2]);
+        TrackDesign::from_data(&v).unwrap()
+    }
+
+    /// A save document with `edit` applied to its data.
+    fn edited(edit: impl FnOnce(&mut Value)) -> io::Result<Document> {
+        let mut v = Document::SavedGame(save()).to_value(None);
+        edit(&mut v["data"]);
+        Document::from_json(&v.to_string())
+    }
+
+    fn saved_game(d: Document) -> SavedGame {
+        match d {
+            Document::SavedGame(sg) => sg,
+            _ => panic!("not a saved game document")
+        }
+    }
+
+    #[test]
+    fn track_design_round_trip() {
+        let td = track_design();
+        let json = Document::TrackDesign(td.clone()).to_json();
+        match Document::from_json(&json).unwrap() {
+            Document::TrackDesign(r) => assert_eq!(r.to_data(), td.to_data()),
+            _ => panic!("not a track design document")
+        }
+        let mut v: Value = serde_json::from_str(&json).unwrap();
+        v["data"]["elements"][1]["kind"] = json!(0xFF);
+        let e = Document::from_json(&v.to_string()).err().unwrap();
+        assert_eq!(e.to_string(), "track element 1 has kind 0xFF, which ends the element list");
+    }
+
+    #[test]
+    fn object_round_trip() {
+        let o = DatObject{entry: ObjectEntry{flags: 0x80, name: *b"TOILETS ", checksum: 9}, encoding: Encoding::Rotate, data: vec![1, 2, 3]};
+        let v = Document::Object(o.clone()).to_value(None);
+        assert_eq!(v["data"]["entry"]["name"], "TOILETS");
+        assert_eq!(v["data"]["valid"], false);
+        match Document::from_json(&v.to_string()).unwrap() {
+            Document::Object(r) => assert_eq!((r.entry, r.encoding, r.data), (o.entry, o.encoding, o.data)),
+            _ => panic!("not an object document")
+        }
+    }
+
+    #[test]
+    fn applies_save_edits() {
+        let sg = saved_game(edited(|d| {
+            d["park"]["entrance_fee"] = json!(150);
+            d["park"]["rating"] = json!(800);
+            d["finances"]["cash"] = json!(-2500);
+            d["rides"][0]["price"] = json!(40);
+        }).unwrap());
+        assert_eq!((sg.entrance_fee(), sg.park_rating(), sg.cash()), (Some(150), Some(800), Some(-2500)));
+        assert_eq!(sg.rides()[0].price, 40);
+        // Unedited documents give back the same file
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+        save().write(&mut a).unwrap();
+        saved_game(edited(|_| ()).unwrap()).write(&mut b).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn rejects_out_of_range_edits() {
+        let err = |edit: fn(&mut Value)| edited(edit).err().unwrap().to_string();
+        assert_eq!(err(|d| d["park"]["entrance_fee"] = json!(2001)), "entrance fee 2001 is above 2000");
+        assert_eq!(err(|d| d["park"]["rating"] = json!(1000)), "park rating 1000 is above 999");
+        assert_eq!(err(|d| d["rides"][0]["price"] = json!(201)), "ride 3 price 201 is above 200");
+        assert_eq!(err(|d| d["rides"][0]["index"] = json!(4)), "there is no ride 4");
+        assert!(err(|d| d["park"]["entrance_fee"] = json!(-1)).starts_with("saved game (SV6): "));
+    }
+
+    #[test]
+    fn rejects_mismatched_kinds() {
+        let mut v = Document::SavedGame(save()).to_value(None);
+        v["kind"] = json!("Scenario");
+        let e = Document::from_json(&v.to_string()).err().unwrap();
+        assert_eq!(e.to_string(), "the file stored in a scenario (SC6) document is not one");
+        assert!(Document::from_json(r#"{"kind": "Unknown", "data": null}"#).is_err());
+        assert!(Document::from_json("[").is_err());
+    }
+}
@@ -0,0 +1,574 @@
+use std::io::{self, Read, Write};
+use crate::finance::format_money;
+use crate::object::ObjectEntry;
+use crate::peep::{MAX_SPRITES, SPRITE_SIZE};
+use crate::rct::decrypt_money;
+use crate::ride::{MAX_RIDES, RIDE_SIZE};
+use crate::rctrle;
+use crate::s6::{self, SavedGame};
+use crate::strings::{self, Language};
+use crate::td6::{self, TrackDesign};
+use crate::util::{u16_from_slice, u32_from_slice};
+use FieldType::*;
+
+/// How the bytes of a field are decoded.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    /// u32 shown in hex.
+    Flags,
+    /// i32 in tenths of the currency unit.
+    Money,
+    Money16,
+    /// Money scrambled by `encrypt_money`.
+    EncryptedMoney,
+    /// Zero-terminated string.
+    Text,
+    Object,
+    Bytes
+}
+
+impl FieldType {
+    /// Bytes of one value; 0 when it depends on the field.
+    const fn size(self) -> usize {
+        match self {
+            U8 | I8 => 1,
+            U16 | I16 | Money16 => 2,
+            U32 | I32 | Flags | Money | EncryptedMoney => 4,
+            Object => 16,
+            Text | Bytes => 0
+        }
+    }
+
+    /// Decoded value of the bytes of one element.
+    fn describe(self, b: &[u8]) -> Option<String> {
+        Some(match self {
+            U8 => b[0].to_string(),
+            I8 => (b[0] as i8).to_string(),
+            U16 => u16_from_slice(b, 0).to_string(),
+            I16 => (u16_from_slice(b, 0) as i16).to_string(),
+            U32 => u32_from_slice(b, 0).to_string(),
+            I32 => (u32_from_slice(b, 0) as i32).to_string(),
+            Flags => format!("0x{:08X}", u32_from_slice(b, 0)),
+            Money => format_money(u32_from_slice(b, 0) as i32),
+            Money16 => format_money(i32::from(u16_from_slice(b, 0) as i16)),
+            EncryptedMoney => format_money(decrypt_money(u32_from_slice(b, 0)) as i32),
+            Text => {
+                let ln = b.iter().position(|&c| c == 0).unwrap_or(b.len());
+                format!("{:?}", strings::decode(&b[..ln], Language::EnglishUK))
+            },
+            Object => {
+                let e = ObjectEntry::from_slice(b);
+                if e.is_empty() { "empty".to_string() } else { format!("{:?} ({:?})", e.name(), e.object_type()) }
+            },
+            Bytes => return None
+        })
+    }
+}
+
+/// Named range of a chunk: `count` elements of `size` bytes each.
+#[derive(Copy, Clone, Debug)]
+pub struct Field {
+    pub offset: usize,
+    pub size: usize,
+    pub count: usize,
+    pub name: &'static str,
+    pub field_type: FieldType
+}
+
+impl Field {
+    pub const fn new(offset: usize, name: &'static str, field_type: FieldType, size: usize, count: usize) -> Self {
+        Field{offset, size, count, name, field_type}
+    }
+
+    pub fn len(&self) -> usize {
+        self.size * self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn end(&self) -> usize {
+        self.offset + self.len()
+    }
+}
+
+const fn f(offset: usize, name: &'static str, field_type: FieldType) -> Field {
+    Field::new(offset, name, field_type, field_type.size(), 1)
+}
+
+const fn bytes(offset: usize, name: &'static str, size: usize) -> Field {
+    Field::new(offset, name, FieldType::Bytes, size, 1)
+}
+
+const fn text(offset: usize, name: &'static str, size: usize) -> Field {
+    Field::new(offset, name, FieldType::Text, size, 1)
+}
+
+const fn array(offset: usize, name: &'static str, field_type: FieldType, count: usize) -> Field {
+    Field::new(offset, name, field_type, field_type.size(), count)
+}
+
+pub const S6_HEADER: &[Field] = &[
+    f(0x00, "type", U8),
+    f(0x01, "classic_flag", U8),
+    f(0x02, "num_packed_objects", U16),
+    f(0x04, "version", U32),
+    f(0x08, "magic_number", U32),
+    bytes(0x0C, "pad_0C", 0x14)
+];
+
+/// Scenario details chunk of SC6 files.
+pub const S6_INFO: &[Field] = &[
+    f(0x00, "editor_step", U8),
+    f(0x01, "category", U8),
+    f(0x02, "objective_type", U8),
+    f(0x03, "objective_arg_1", U8),
+    f(0x04, "objective_arg_2", I32),
+    f(0x08, "objective_arg_3", I16),
+    bytes(0x0A, "pad_0A", 0x3E),
+    text(0x48, "name", 64),
+    text(0x88, "details", 256),
+    f(0x188, "entry", Object)
+];
+
+pub const S6_DATE: &[Field] = &[
+    f(0x00, "elapsed_months", U16),
+    f(0x02, "current_day", U16),
+    f(0x04, "scenario_ticks", U32),
+    f(0x08, "scenario_srand_0", U32),
+    f(0x0C, "scenario_srand_1", U32)
+];
+
+/// Game data, by offset from its start. Scenarios store it split over several chunks.
+pub const S6_GAME: &[Field] = &[
+    f(0x00_0000, "next_free_tile_element", U32),
+    Field::new(s6::SPRITES, "sprites", Bytes, SPRITE_SIZE, MAX_SPRITES),
+    array(0x27_1004, "sprite_lists_head", U16, 6),
+    array(0x27_1010, "sprite_lists_count", U16, 6),
+    f(0x27_101C, "park_name", U16),
+    bytes(0x27_101E, "pad_27101E", 2),
+    f(0x27_1020, "park_name_args", U32),
+    f(s6::INITIAL_CASH, "initial_cash", Money),
+    f(s6::CURRENT_LOAN, "current_loan", Money),
+    f(s6::PARK_FLAGS, "park_flags", Flags),
+    f(s6::PARK_ENTRANCE_FEE, "park_entrance_fee", Money16),
+    f(0x27_1032, "rct1_park_entrance_x", U16),
+    f(0x27_1034, "rct1_park_entrance_y", U16),
+    bytes(0x27_1036, "pad_271036", 2),
+    f(0x27_1038, "rct1_park_entrance_z", U8),
+    bytes(0x27_1039, "pad_271039", 1),
+    Field::new(0x27_103A, "peep_spawns", Bytes, 6, 2),
+    f(0x27_1046, "guest_count_change_modifier", U8),
+    f(0x27_1047, "current_research_level", U8),
+    bytes(0x27_1048, "pad_271048", 4),
+    array(s6::RESEARCHED_RIDE_TYPES, "researched_ride_types", Flags, 8),
+    array(s6::RESEARCHED_RIDE_ENTRIES, "researched_ride_entries", Flags, 8),
+    array(0x27_108C, "researched_track_types_a", Flags, 128),
+    array(0x27_128C, "researched_track_types_b", Flags, 128),
+    f(s6::GUESTS_IN_PARK, "guests_in_park", U16),
+    f(0x27_148E, "guests_heading_for_park", U16),
+    array(s6::EXPENDITURE_TABLE, "expenditure_table", Money, 16 * 14),
+    f(0x27_1810, "last_guests_in_park", U16),
+    bytes(0x27_1812, "pad_271812", 3),
+    f(0x27_1815, "handyman_colour", U8),
+    f(0x27_1816, "mechanic_colour", U8),
+    f(0x27_1817, "security_colour", U8),
+    array(s6::RESEARCHED_SCENERY_ITEMS, "researched_scenery_items", Flags, 56),
+    f(s6::PARK_RATING, "park_rating", U16),
+    array(0x27_18FA, "park_rating_history", U8, 32),
+    array(0x27_191A, "guests_in_park_history", U8, 32),
+    f(0x27_193A, "active_research_types", U8),
+    f(0x27_193B, "research_progress_stage", U8),
+    f(0x27_193C, "last_researched_item_subject", U32),
+    bytes(0x27_1940, "pad_271940", 1000),
+    f(0x27_1D28, "next_research_item", U32),
+    f(0x27_1D2C, "research_progress", U16),
+    f(0x27_1D2E, "next_research_category", U8),
+    f(0x27_1D2F, "next_research_expected_day", U8),
+    f(0x27_1D30, "next_research_expected_month", U8),
+    f(0x27_1D31, "guest_initial_happiness", U8),
+    f(0x27_1D32, "park_size", U16),
+    f(0x27_1D34, "guest_generation_probability", U16),
+    f(0x27_1D36, "total_ride_value_for_money", U16),
+    f(s6::MAXIMUM_LOAN, "maximum_loan", Money),
+    f(0x27_1D3C, "guest_initial_cash", Money16),
+    f(0x27_1D3E, "guest_initial_hunger", U8),
+    f(0x27_1D3F, "guest_initial_thirst", U8),
+    f(0x27_1D40, "objective_type", U8),
+    f(0x27_1D41, "objective_year", U8),
+    bytes(0x27_1D42, "pad_271D42", 2),
+    f(0x27_1D44, "objective_currency", Money),
+    f(0x27_1D48, "objective_guests", U16),
+    array(0x27_1D4A, "campaign_weeks_left", U8, 20),
+    array(0x27_1D5E, "campaign_ride_index", U8, 22),
+    array(s6::CASH_HISTORY, "cash_history", Money, 128),
+    f(s6::CURRENT_EXPENDITURE, "current_expenditure", Money),
+    f(s6::CURRENT_PROFIT, "current_profit", Money),
+    f(0x27_1F7C, "weekly_profit_average_dividend", U32),
+    f(0x27_1F80, "weekly_profit_average_divisor", U16),
+    bytes(0x27_1F82, "pad_271F82", 2),
+    array(s6::WEEKLY_PROFIT_HISTORY, "weekly_profit_history", Money, 128),
+    f(s6::PARK_VALUE, "park_value", Money),
+    array(s6::PARK_VALUE_HISTORY, "park_value_history", Money, 128),
+    f(0x27_2388, "completed_company_value", Money),
+    f(s6::TOTAL_ADMISSIONS, "total_admissions", U32),
+    f(s6::INCOME_FROM_ADMISSIONS, "income_from_admissions", Money),
+    f(s6::COMPANY_VALUE, "company_value", Money),
+    array(0x27_2398, "peep_warning_throttle", U8, 16),
+    Field::new(0x27_23A8, "awards", Bytes, 4, 4),
+    f(s6::CASH, "cash", EncryptedMoney),
+    Field::new(s6::CUSTOM_STRINGS, "custom_strings", Text, 32, 1024),
+    Field::new(s6::RIDES, "rides", Bytes, RIDE_SIZE, MAX_RIDES)
+];
+
+/// Fields of decoded TD6 data. The lists after the fixed part depend on the design.
+pub fn td6(td: &TrackDesign) -> Vec<Field> {
+    let mut fields = vec![
+        f(0x00, "ride_type", U8),
+        f(0x01, "vehicle_type", U8),
+        f(0x02, "flags", Flags),
+        f(0x06, "ride_mode", U8),
+        f(0x07, "version_and_colour_scheme", U8),
+        Field::new(0x08, "vehicle_colours", Bytes, 2, 32),
+        f(0x48, "pad_48", U8),
+        f(0x49, "entrance_style", U8),
+        f(0x4A, "total_air_time", U8),
+        f(0x4B, "depart_flags", U8),
+        f(0x4C, "number_of_trains", U8),
+        f(0x4D, "cars_per_train", U8),
+        f(0x4E, "min_waiting_time", U8),
+        f(0x4F, "max_waiting_time", U8),
+        f(0x50, "operation_setting", U8),
+        f(0x51, "max_speed", I8),
+        f(0x52, "average_speed", I8),
+        f(0x53, "ride_length", U16),
+        f(0x55, "max_positive_vertical_g", U8),
+        f(0x56, "max_negative_vertical_g", I8),
+        f(0x57, "max_lateral_g", U8),
+        f(0x58, "inversions", U8),
+        f(0x59, "drops", U8),
+        f(0x5A, "highest_drop_height", U8),
+        f(0x5B, "excitement", U8),
+        f(0x5C, "intensity", U8),
+        f(0x5D, "nausea", U8),
+        f(0x5E, "upkeep_cost", I16),
+        bytes(0x60, "track_spine_colour", 4),
+        bytes(0x64, "track_rail_colour", 4),
+        bytes(0x68, "track_support_colour", 4),
+        f(0x6C, "flags2", Flags),
+        f(0x70, "vehicle_object", Object),
+        f(0x80, "space_required_x", U8),
+        f(0x81, "space_required_y", U8),
+        bytes(0x82, "vehicle_additional_colour", 32),
+        f(0xA2, "lift_hill_speed_num_circuits", U8)
+    ];
+    let mut at = 0xA3;
+    let mut list = |name, size, count| {
+        fields.push(Field::new(at, name, Bytes, size, count));
+        at += size * count;
+    };
+    if td.is_maze() {
+        list("maze", 4, td.maze.len());
+        list("maze_end", 4, 1);
+    } else {
+        list("elements", 2, td.elements.len());
+        list("elements_end", 1, 1);
+        list("entrances", 6, td.entrances.len());
+        list("entrances_end", 1, 1);
+    }
+    list("scenery", 22, td.scenery.len());
+    list("scenery_end", 1, 1);
+    fields
+}
+
+/// Row of an annotated dump: up to 16 bytes with what they hold.
+struct Row<'a> {
+    /// Rows are only collapsed within the same field or unknown range.
+    group: usize,
+    offset: usize,
+    bytes: &'a [u8],
+    label: String,
+    value: Option<String>,
+    known: bool
+}
+
+fn write_row<W: Write>(w: &mut W, r: &Row, colour: bool) -> io::Result<()> {
+    let hex = r.bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+    let (start, end) = if colour && !r.known { ("\x1b[33m", "\x1b[0m") } else { ("", "") };
+    write!(w, "{}{:08X}  {:<47}  {}", start, r.offset, hex, r.label)?;
+    if let Some(v) = &r.value {
+        write!(w, " = {}", v)?;
+    }
+    writeln!(w, "{}", end)
+}
+
+/// Splits a field's bytes (clipped to what the chunk holds) into rows.
+fn field_rows<'a>(fd: &Field, b: &'a [u8], offset: usize, group: usize, rows: &mut Vec<Row<'a>>) {
+    let first = (offset - fd.offset) / fd.size.max(1);
+    for (n, e) in b.chunks(fd.size.max(1)).enumerate() {
+        let i = first + n;
+        let name = if fd.count > 1 { format!("{}[{}]", fd.name, i) } else { fd.name.to_string() };
+        let whole = e.len() == fd.size;
+        let small = fd.field_type.size() > 0 && fd.size <= 16;
+        for (k, r) in e.chunks(16).enumerate() {
+            let value = if k == 0 && whole && (small || fd.field_type == Text) { fd.field_type.describe(e) } else { None };
+            let label = if k == 0 { name.clone() } else { format!("{} +0x{:X}", name, k * 16) };
+            rows.push(Row{group, offset: offset + n * fd.size + k * 16, bytes: r, label, value, known: true});
+        }
+    }
+}
+
+/// Writes a chunk as hex, 16 bytes a row, naming the fields it holds. `base` is the layout
+/// offset of the chunk's first byte. Bytes outside every field are labelled `unknown` (and
+/// coloured when `colour` is set); runs of identical rows are collapsed into a `*` row.
+pub fn annotate<W: Write>(w: &mut W, data: &[u8], base: usize, fields: &[Field], colour: bool) -> io::Result<()> {
+    let mut sorted = fields.iter().filter(|fd| !fd.is_empty()).collect::<Vec<_>>();
+    sorted.sort_by_key(|fd| fd.offset);
+    let end = base + data.len();
+    let mut rows = Vec::new();
+    let mut pos = base;
+    let mut next = sorted.iter().peekable();
+    while pos < end {
+        while next.peek().is_some_and(|fd| fd.end() <= pos) {
+            next.next();
+        }
+        match next.peek() {
+            Some(fd) if fd.offset <= pos => {
+                let stop = fd.end().min(end);
+                let size = fd.size.max(1);
+                // Scenarios split fields across chunks, so one may start mid-element
+                let into = (pos - fd.offset) % size;
+                if into > 0 {
+                    let part = (size - into).min(stop - pos);
+                    for (k, r) in data[pos - base..pos + part - base].chunks(16).enumerate() {
+                        let label = format!("{}[{}] +0x{:X}", fd.name, (pos - fd.offset) / size, into + k * 16);
+                        rows.push(Row{group: pos, offset: pos + k * 16, bytes: r, label, value: None, known: true});
+                    }
+                    pos += part;
+                }
+                field_rows(fd, &data[pos - base..stop - base], pos, pos, &mut rows);
+                pos = stop;
+            },
+            other => {
+                let stop = other.map_or(end, |fd| fd.offset.min(end));
+                for (k, r) in data[pos - base..stop - base].chunks(16).enumerate() {
+                    rows.push(Row{group: pos, offset: pos + k * 16, bytes: r, label: "unknown".to_string(), value: None, known: false});
+                }
+                pos = stop;
+            }
+        }
+    }
+    let mut collapsed = false;
+    for (i, r) in rows.iter().enumerate() {
+        let same = i > 0 && {
+            let p = &rows[i - 1];
+            p.group == r.group && p.bytes == r.bytes && rows.get(i + 1).is_some_and(|n| n.group == r.group)
+        };
+        if same {
+            if !collapsed {
+                writeln!(w, "*")?;
+                collapsed = true;
+            }
+            continue;
+        }
+        collapsed = false;
+        write_row(w, r, colour)?;
+    }
+    Ok(())
+}
+
+/// Annotated dump of a TD6 file's decoded data.
+pub fn annotate_track_design<W: Write>(w: &mut W, file: &[u8], colour: bool) -> io::Result<()> {
+    if file.len() < 4 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "file too short"));
+    }
+    let mut v = Vec::new();
+    rctrle::Reader::new(&file[..file.len()-4]).read_to_end(&mut v)?;
+    let td = TrackDesign::from_data(&v)?;
+    let valid = if td6::validate_checksum(file) { "valid" } else { "invalid" };
+    writeln!(w, "== decoded data, 0x{:X} bytes; checksum 0x{:08X} ({})", v.len(), u32_from_slice(file, file.len()-4), valid)?;
+    annotate(w, &v, 0, &td6(&td), colour)
+}
+
+/// Annotated dump of every chunk of a saved game or scenario. Packed objects are only listed.
+pub fn annotate_saved_game<W: Write>(w: &mut W, sg: &SavedGame, colour: bool) -> io::Result<()> {
+    writeln!(w, "== header, 0x{:X} bytes, {:?}", sg.header.data().len(), sg.header.encoding())?;
+    annotate(w, sg.header.data(), 0, S6_HEADER, colour)?;
+    if let Some(info) = &sg.info {
+        writeln!(w, "== scenario info, 0x{:X} bytes, {:?}", info.data().len(), info.encoding())?;
+        annotate(w, info.data(), 0, S6_INFO, colour)?;
+    }
+    for o in &sg.packed {
+        writeln!(w, "== packed object {} ({:?}), 0x{:X} bytes, {:?}", o.entry.name(), o.entry.object_type(), o.data.len(), o.encoding)?;
+    }
+    for (i, c) in sg.chunks.iter().enumerate() {
+        let b = c.data();
+        let (name, base, fields) = match i {
+            0 => ("object list", 0, vec![Field::new(0, "objects", Object, 16, b.len() / 16)]),
+            1 => ("date", 0, S6_DATE.to_vec()),
+            2 => ("map", 0, vec![Field::new(0, "tile_elements", Bytes, 8, b.len() / 8)]),
+            _ => match sg.game_data_offset(i) {
+                Some(base) => ("game data", base, S6_GAME.to_vec()),
+                None => ("unexpected chunk", 0, Vec::new())
+            }
+        };
+        writeln!(w, "== chunk {}: {}, 0x{:X} bytes from offset 0x{:06X}, {:?}", i, name, b.len(), base, c.encoding())?;
+        annotate(w, b, base, &fields, colour)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sawyer::{Chunk, Encoding};
+
+    const FIELDS: [Field; 4] = [
+        f(0x02, "count", U16),
+        array(0x04, "values", I8, 2),
+        Field::new(0x10, "blocks", Bytes, 0x20, 2),
+        text(0x60, "name", 8)
+    ];
+
+    fn data() -> Vec<u8> {
+        let mut data = vec![0u8; 0x70];
+        data[0] = 0xAB;
+        data[2..4].copy_from_slice(&300u16.to_le_bytes());
+        data[4] = 0xFE;
+        data[0x60..0x64].copy_from_slice(b"Park");
+        data
+    }
+
+    fn annotated(data: &[u8], base: usize, fields: &[Field], colour: bool) -> String {
+        let mut v = Vec::new();
+        annotate(&mut v, data, base, fields, colour).unwrap();
+        String::from_utf8(v).unwrap()
+    }
+
+    fn lines(out: &[u8]) -> Vec<String> {
+        String::from_utf8(out.to_vec()).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn layouts_are_in_order() {
+        for (fields, end) in [(S6_HEADER, 0x20), (S6_INFO, 0x198), (S6_DATE, 0x10), (S6_GAME, s6::GAME_DATA_SIZE)] {
+            for w in fields.windows(2) {
+                assert!(w[0].end() <= w[1].offset, "{} overlaps {}", w[0].name, w[1].name);
+            }
+            assert!(fields.last().unwrap().end() <= end);
+        }
+        assert_eq!(S6_HEADER.last().unwrap().end(), 0x20);
+        assert_eq!(S6_INFO.last().unwrap().end(), 0x198);
+    }
+
+    #[test]
+    fn annotates_fields() {
+        assert_eq!(annotated(&data(), 0, &FIELDS, false), "\
+00000000  AB 00                                            unknown
+00000002  2C 01                                            count = 300
+00000004  FE                                               values[0] = -2
+00000005  00                                               values[1] = 0
+00000006  00 00 00 00 00 00 00 00 00 00                    unknown
+00000010  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  blocks[0]
+*
+00000040  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  blocks[1] +0x10
+00000050  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  unknown
+00000060  50 61 72 6B 00 00 00 00                          name = \"Park\"
+00000068  00 00 00 00 00 00 00 00                          unknown
+");
+    }
+
+    #[test]
+    fn fields_split_across_chunks() {
+        // A chunk starting mid-element labels the offset into it, and clipped fields get no value
+        assert_eq!(annotated(&data()[0x18..0x40], 0x18, &FIELDS, false), "\
+00000018  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  blocks[0] +0x8
+00000028  00 00 00 00 00 00 00 00                          blocks[0] +0x18
+00000030  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  blocks[1]
+");
+        assert_eq!(annotated(&data()[0x48..0x62], 0x48, &FIELDS, true), "\
+00000048  00 00 00 00 00 00 00 00                          blocks[1] +0x18
+\x1b[33m00000050  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  unknown\x1b[0m
+00000060  50 61                                            name
+");
+    }
+
+    #[test]
+    fn annotates_track_designs() {
+        let mut v = vec![0u8; 0xA3];
+        v[0x00] = 0x34;
+        v[0x07] = 2 << 2;
+        v[0x70..0x74].copy_from_slice(&0x80u32.to_le_bytes());
+        v[0x74..0x7C].copy_from_slice(b"WOODCARS");
+        v.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0xFF, 0xFF]);
+        let td = TrackDesign::from_data(&v).unwrap();
+        assert_eq!(td6(&td).last().unwrap().end(), v.len());
+        let mut file = Vec::new();
+        td.write(&mut file).unwrap();
+
+        let mut out = Vec::new();
+        annotate_track_design(&mut out, &file, false).unwrap();
+        let lines = lines(&out);
+        assert!(lines[0].starts_with("== decoded data, 0xB0 bytes; checksum 0x"), "{}", lines[0]);
+        assert!(lines[0].ends_with(" (valid)"));
+        assert_eq!(lines[1], "00000000  34                                               ride_type = 52");
+        assert!(lines.contains(&"00000070  80 00 00 00 57 4F 4F 44 43 41 52 53 00 00 00 00  vehicle_object = \"WOODCARS\" (Ride)".to_string()));
+        assert_eq!(lines[lines.len() - 6..], [
+            "000000A3  02 00                                            elements[0]",
+            "000000A5  00 00                                            elements[1]",
+            "000000A7  FF                                               elements_end",
+            "000000A8  00 00 20 00 00 00                                entrances",
+            "000000AE  FF                                               entrances_end",
+            "000000AF  FF                                               scenery_end"
+        ]);
+        assert!(annotate_track_design(&mut Vec::new(), &file[..3], false).is_err());
+    }
+
+    #[test]
+    fn annotates_scenario_chunks() {
+        let mut header = vec![0u8; 0x20];
+        header[0] = 1;
+        let mut chunks = vec![
+            Chunk::new(Encoding::RLE, vec![0xFF; 16]),
+            Chunk::new(Encoding::RLE, vec![40, 0, 3, 0]),
+            Chunk::new(Encoding::RLECompressed, vec![0; 8])
+        ];
+        for i in 0..8 {
+            chunks.push(Chunk::new(Encoding::RLE, vec![i as u8; 8]));
+        }
+        let sg = SavedGame{header: Chunk::new(Encoding::RLE, header), info: Some(Chunk::new(Encoding::RLE, vec![0; 0x198])),
+            packed: Vec::new(), chunks, checksum_salt: 0};
+        let mut out = Vec::new();
+        annotate_saved_game(&mut out, &sg, false).unwrap();
+        let lines = lines(&out);
+        let section = |title: &str| {
+            let i = lines.iter().position(|l| l.starts_with(title)).unwrap_or_else(|| panic!("no {}", title));
+            lines[i..].iter().skip(1).take_while(|l| !l.starts_with("==")).cloned().collect::<Vec<_>>()
+        };
+        assert_eq!(lines[0], "== header, 0x20 bytes, RLE");
+        assert_eq!(section("== chunk 0: object list, 0x10 bytes from offset 0x000000, RLE"),
+            ["00000000  FF FF FF FF FF FF FF FF FF FF FF FF FF FF FF FF  objects = empty"]);
+        assert_eq!(section("== chunk 1: date, 0x4 bytes from offset 0x000000, RLE"), [
+            "00000000  28 00                                            elapsed_months = 40",
+            "00000002  03 00                                            current_day = 3"
+        ]);
+        assert_eq!(section("== chunk 3: game data, 0x8 bytes from offset 0x000000, RLE"), [
+            "00000000  00 00 00 00                                      next_free_tile_element = 0",
+            "00000004  00 00 00 00                                      sprites[0]"
+        ]);
+        // Later chunks are annotated at their offset into the game data
+        assert_eq!(section("== chunk 6: game data, 0x8 bytes from offset 0x2718F8, RLE")[0],
+            "002718F8  03 03                                            park_rating = 771");
+        assert_eq!(section("== chunk 10: game data, 0x8 bytes from offset 0x272388, RLE"), [
+            "00272388  07 07 07 07                                      completed_company_value = 11790106.30",
+            "0027238C  07 07 07 07                                      total_admissions = 117901063"
+        ]);
+    }
+}
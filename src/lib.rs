@@ -0,0 +1,29 @@
+pub mod batch;
+pub mod chunks;
+pub mod diff;
+pub mod finance;
+pub mod format;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod layout;
+pub mod map;
+pub mod mesh;
+pub mod object;
+pub mod peep;
+pub mod physics;
+pub mod ratings;
+pub mod rct;
+pub mod rctrle;
+pub mod repository;
+pub mod ride;
+pub mod ride_type;
+pub mod s6;
+pub mod sawyer;
+pub mod strings;
+pub mod svg;
+pub mod td6;
+pub mod track;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod util;
+pub mod validate;
@@ -1,35 +1,1003 @@
-mod rctrle;
-mod rct;
-mod sawyer;
-mod s6;
-mod util;
-
 use std::fs::File;
+use std::io::{self, BufWriter, Cursor, IsTerminal, Write};
 use std::path::Path;
+use std::process;
+use rct2read::{chunks, diff, layout, object, physics, ratings, rct, svg, track, validate};
+use rct2read::finance::format_money as money;
+use rct2read::format::{self, FileKind, FileType};
+use rct2read::object::DatObject;
+use rct2read::ratings::{Ratings, RideStats};
+use rct2read::mesh::Mesh;
+use rct2read::repository::ObjectRepository;
+use rct2read::ride_type::{RideMode, RideType};
+use rct2read::s6::{self, SavedGame};
+use rct2read::sawyer::{Chunk, Encoding};
+use rct2read::td6::{self, TrackDesign};
+
+/// Why a command failed, which decides the exit code.
+enum Error {
+    /// Wrong arguments; the command's usage is printed and the exit code is 2.
+    Usage,
+    /// The command could not do its job; the exit code is 1.
+    Failed(String)
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Failed(e.to_string())
+    }
+}
+
+type CmdResult = Result<(), Error>;
+type Command = fn(&[String]) -> CmdResult;
+
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+/// Subcommands with their arguments.
+const COMMANDS: &[(&str, Command, &str)] = &[
+    ("detect", detect, "detect <file>... [--format text|json]"),
+    ("batch", batch, "batch <dir> [--threads N]"),
+    ("info", info, "info <file> [objdata]"),
+    ("dump", dump, "dump <file> [objdata] [--format text|json | --annotated]"),
+    ("rides", rides, "rides <save> [--csv]"),
+    ("guests", guests, "guests <save> [--csv]"),
+    ("objects", objects, "objects <file> [objdata]"),
+    ("map", map, "map <save>"),
+    ("tui", tui, "tui <save>"),
+    ("diff", diff, "diff <old> <new>"),
+    ("edit", edit, "edit <save> <out> [--cash N] [--loan N] [--max-loan N] [--entrance-fee N] [--park-rating N] [--flags HEX]"),
+    ("convert", convert, "convert <file|doc.json> <out|doc.json> [--encoding none|rle|rle-compressed|rotate]"),
+    ("validate", validate, "validate <track.td6>..."),
+    ("extract", extract, "extract <file> <outdir> [objdata]"),
+    ("pack", pack, "pack <save> <objdata> <out>"),
+    ("chunks", chunks, "chunks (extract <save> <outdir> | pack <dir> <out>)"),
+    ("prices", prices, "prices <save> (<out> | --dry-run)"),
+    ("ratings", ratings, "ratings <file>"),
+    ("simulate", simulate, "simulate <track.td6> [--elements]"),
+    ("export", export, "export <track.td6> <out.obj|out.gltf>"),
+    ("svg", svg, "svg <track.td6> <plan.svg> <elevation.svg>")
+];
+
+fn print_usage() {
+    eprintln!("Usage: rct2read <command> [args]\n\nCommands:");
+    for (_, _, usage) in COMMANDS {
+        eprintln!("  {}", usage);
+    }
+}
 
 fn main() {
-    let farg = match std::env::args().nth(1) {
-        Some(n) => n,
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let name = match args.first() {
+        Some(n) => n.as_str(),
+        None => {
+            print_usage();
+            process::exit(EXIT_USAGE);
+        }
+    };
+    if matches!(name, "help" | "--help" | "-h") {
+        print_usage();
+        return;
+    }
+    let (_, run, usage) = match COMMANDS.iter().find(|c| c.0 == name) {
+        Some(c) => c,
         None => {
-            println!("No file provided");
-            return;
+            eprintln!("Unknown command: {}", name);
+            print_usage();
+            process::exit(EXIT_USAGE);
         }
     };
-    let p = Path::new(farg.as_str());
-    let f = File::open(p).unwrap();
-    if let Some(x) = p.extension() {
-        match x.to_str() {
-            Some("td6") => {
-                rct::read_td6_file(&f);
+    match run(&args[1..]) {
+        Ok(()) => (),
+        Err(Error::Usage) => {
+            eprintln!("Usage: rct2read {}", usage);
+            process::exit(EXIT_USAGE);
+        },
+        Err(Error::Failed(msg)) => {
+            eprintln!("error: {}", msg);
+            process::exit(EXIT_FAILURE);
+        }
+    }
+}
+
+/// JSON output, one document per line.
+#[cfg(feature = "serde")]
+mod json {
+    use std::io::{self, Cursor};
+    use std::path::{Path, PathBuf};
+    use serde_json::{json, Value};
+    use rct2read::batch::{self, Summary};
+    use rct2read::format::{FileKind, Format};
+    use rct2read::json::{self, Document};
+    use rct2read::object::DatObject;
+    use rct2read::repository::ObjectRepository;
+    use rct2read::s6::SavedGame;
+    use rct2read::td6::TrackDesign;
+    use super::{CmdResult, Contents, Error};
+
+    fn print(v: &Value) -> CmdResult {
+        println!("{}", serde_json::to_string(v).map_err(|e| Error::Failed(e.to_string()))?);
+        Ok(())
+    }
+
+    pub fn detect(path: &str, f: &Format) -> CmdResult {
+        print(&json!({"file": path, "format": json::format(f)}))
+    }
+
+    /// Record of a file in a batch: its format and summary, or why it could not be read.
+    pub fn batch_record(path: &Path, r: Result<(Format, Summary), String>) -> Value {
+        let file = path.display().to_string();
+        match r {
+            Ok((f, summary)) => json!({"file": file, "format": json::format(&f), "summary": summary}),
+            Err(e) => json!({"file": file, "error": e})
+        }
+    }
+
+    /// Summarizes the files on a pool of threads, printing a record for each as it is done, after
+    /// a record for each directory or entry which could not be listed. Returns the number of files
+    /// which could not be read.
+    pub fn batch(paths: &[PathBuf], unlisted: &[(PathBuf, io::Error)], threads: usize) -> Result<usize, Error> {
+        for (path, e) in unlisted {
+            print(&batch_record(path, Err(e.to_string())))?;
+        }
+        let mut errors = 0;
+        batch::run(paths, threads, batch::summarize_file, |path, r| {
+            errors += usize::from(r.is_err());
+            // A closed stdout ends the output but not the batch
+            let _ = print(&batch_record(path, r));
+        });
+        Ok(errors)
+    }
+
+    pub fn read_document(path: &str) -> Result<Contents, Error> {
+        let s = std::fs::read_to_string(path).map_err(|e| Error::Failed(format!("{}: {}", path, e)))?;
+        Ok(match Document::from_json(&s).map_err(|e| Error::Failed(format!("{}: {}", path, e)))? {
+            Document::TrackDesign(td) => Contents::TrackDesign(td),
+            Document::SavedGame(sg) => Contents::SavedGame(sg),
+            Document::Object(o) => Contents::Object(o)
+        })
+    }
+
+    pub fn write_document(contents: Contents, path: &str) -> CmdResult {
+        let doc = match contents {
+            Contents::TrackDesign(td) => Document::TrackDesign(td),
+            Contents::SavedGame(sg) => Document::SavedGame(sg),
+            Contents::Object(o) => Document::Object(o)
+        };
+        std::fs::write(path, doc.to_json() + "\n").map_err(|e| Error::Failed(format!("{}: {}", path, e)))?;
+        Ok(())
+    }
+
+    pub fn dump(kind: FileKind, b: &[u8], repo: Option<&ObjectRepository>) -> CmdResult {
+        let doc = match kind {
+            FileKind::TrackDesign => Document::TrackDesign(TrackDesign::read(b)?),
+            FileKind::SavedGame | FileKind::Scenario => Document::SavedGame(SavedGame::read(Cursor::new(b))?),
+            FileKind::Object => Document::Object(DatObject::read(&mut Cursor::new(b))?),
+            FileKind::Unknown => return print(&json!({"kind": kind, "data": Value::Null}))
+        };
+        print(&doc.to_value(repo))
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+mod json {
+    use rct2read::format::{FileKind, Format};
+    use rct2read::repository::ObjectRepository;
+    use super::{CmdResult, Contents, Error};
+
+    fn unsupported() -> CmdResult {
+        Err(Error::Failed("JSON output needs rct2read built with the serde feature".to_string()))
+    }
+
+    pub fn detect(_: &str, _: &Format) -> CmdResult {
+        unsupported()
+    }
+
+    pub fn dump(_: FileKind, _: &[u8], _: Option<&ObjectRepository>) -> CmdResult {
+        unsupported()
+    }
+
+    pub fn read_document(_: &str) -> Result<Contents, Error> {
+        Err(Error::Failed("JSON documents need rct2read built with the serde feature".to_string()))
+    }
+
+    pub fn write_document(_: Contents, _: &str) -> CmdResult {
+        unsupported()
+    }
+}
+
+fn open_repository(d: &str) -> Result<ObjectRepository, Error> {
+    Ok(match ObjectRepository::default_cache_path(d) {
+        Some(cache) => ObjectRepository::scan_with_cache(d, cache)?,
+        None => ObjectRepository::scan(d)?
+    })
+}
+
+/// Reads a file and works out its kind from the contents.
+fn load(path: &str) -> Result<(FileKind, Vec<u8>), Error> {
+    let b = std::fs::read(path).map_err(|e| Error::Failed(format!("{}: {}", path, e)))?;
+    let f = format::detect_format(&b);
+    match f.file_type.kind() {
+        FileKind::Unknown if f.file_type == FileType::Unknown => Err(Error::Failed(format!("{}: unrecognised file format", path))),
+        FileKind::Unknown => Err(Error::Failed(format!("{}: {} files are not supported", path, f.file_type.name()))),
+        k => Ok((k, b))
+    }
+}
+
+fn wrong_kind(path: &str, kind: FileKind, expected: &str) -> Error {
+    Error::Failed(format!("{} is a {}, not a {}", path, kind.name(), expected))
+}
+
+fn read_save(path: &str) -> Result<SavedGame, Error> {
+    match load(path)? {
+        (FileKind::SavedGame, b) | (FileKind::Scenario, b) => Ok(SavedGame::read(Cursor::new(b))?),
+        (k, _) => Err(wrong_kind(path, k, "saved game or scenario"))
+    }
+}
+
+fn read_track(path: &str) -> Result<TrackDesign, Error> {
+    match load(path)? {
+        (FileKind::TrackDesign, b) => Ok(TrackDesign::read(&b[..])?),
+        (k, _) => Err(wrong_kind(path, k, "track design"))
+    }
+}
+
+fn create(path: &str) -> Result<BufWriter<File>, Error> {
+    File::create(path).map(BufWriter::new).map_err(|e| Error::Failed(format!("{}: {}", path, e)))
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json
+}
+
+/// Takes `--format text|json` out of the arguments.
+fn output_format(args: &[String]) -> Result<(Vec<String>, OutputFormat), Error> {
+    let mut rest = Vec::new();
+    let mut fmt = OutputFormat::Text;
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        if a == "--format" {
+            fmt = match it.next().map(String::as_str) {
+                Some("text") => OutputFormat::Text,
+                Some("json") => OutputFormat::Json,
+                _ => return Err(Error::Usage)
+            };
+        } else {
+            rest.push(a.clone());
+        }
+    }
+    Ok((rest, fmt))
+}
+
+/// `detect <file>... [--format F]` names the format of each file, whether or not it can be read.
+fn detect(args: &[String]) -> CmdResult {
+    let (paths, fmt) = output_format(args)?;
+    if paths.is_empty() {
+        return Err(Error::Usage);
+    }
+    for path in &paths {
+        let b = std::fs::read(path).map_err(|e| Error::Failed(format!("{}: {}", path, e)))?;
+        let f = format::detect_format(&b);
+        match fmt {
+            OutputFormat::Text => println!("{}: {}", path, f),
+            OutputFormat::Json => json::detect(path, &f)?
+        }
+    }
+    Ok(())
+}
+
+/// `batch <dir> [--threads N]` summarizes every file under a directory as JSON lines, whatever its
+/// extension. Files which cannot be read, and directories which cannot be listed, get a record with
+/// the error and make the command fail once the rest are done.
+#[cfg(feature = "serde")]
+fn batch(args: &[String]) -> CmdResult {
+    let mut dir = None;
+    let mut threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        match a.as_str() {
+            "--threads" => threads = it.next().and_then(|n| n.parse().ok()).filter(|&n| n > 0).ok_or(Error::Usage)?,
+            _ if dir.is_none() => dir = Some(a),
+            _ => return Err(Error::Usage)
+        }
+    }
+    let dir = dir.ok_or(Error::Usage)?;
+    let (paths, unlisted) = rct2read::batch::find_files(Path::new(dir));
+    let errors = json::batch(&paths, &unlisted, threads)?;
+    match (errors, unlisted.len()) {
+        (0, 0) => {
+            eprintln!("{} files", paths.len());
+            Ok(())
+        },
+        (e, 0) => Err(Error::Failed(format!("{} of {} files could not be read", e, paths.len()))),
+        (e, u) => Err(Error::Failed(format!("{} of {} files could not be read, {} directories or entries could not be listed",
+            e, paths.len(), u)))
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn batch(_: &[String]) -> CmdResult {
+    Err(Error::Failed("batch needs rct2read built with the serde feature".to_string()))
+}
+
+/// `info <file> [objdata]` prints a short summary of a save, scenario, track design or object.
+fn info(args: &[String]) -> CmdResult {
+    let path = args.first().ok_or(Error::Usage)?;
+    let repo = args.get(1).map(|d| open_repository(d)).transpose()?;
+    let (kind, b) = load(path)?;
+    println!("File: {}", kind.name());
+    match kind {
+        FileKind::TrackDesign => {
+            let td = TrackDesign::read(&b[..])?;
+            let rt = RideType::from(td.ride_type);
+            println!("Ride type: {} (0x{:02X}); mode: {}", rt.name(), td.ride_type, RideMode::from(td.ride_mode).name());
+            println!("Vehicle: {}", td.vehicle_object.name());
+            if td.is_maze() {
+                println!("Maze tiles: {}", td.maze.len());
+            } else {
+                println!("Elements: {}", td.elements.len());
+                if let Some(bb) = track::bounding_box(&track::walk(&td.elements)) {
+                    let (w, l) = bb.tiles();
+                    println!("Size: {}x{}; height {}", w, l, (bb.max.2 - bb.min.2) / 8);
+                }
+            }
+            println!("Ratings: {}", ratings::stored_td6(&td));
+            println!("Checksum: {}", if td6::validate_checksum(&b) { "valid" } else { "invalid" });
+            if let Some(r) = &repo {
+                print_missing(r, &td.object_refs(), &[]);
+            }
+        },
+        FileKind::SavedGame | FileKind::Scenario => {
+            let sg = SavedGame::read(Cursor::new(&b))?;
+            let months = sg.months();
+            println!("Date: month {} of year {}", months % 8 + 1, months / 8 + 1);
+            if let Some(c) = sg.cash() {
+                println!("Cash: {}", money(c));
+            }
+            if let (Some(l), Some(m)) = (sg.loan(), sg.max_loan()) {
+                println!("Loan: {} (max {})", money(l), money(m));
+            }
+            if let Some(r) = sg.park_rating() {
+                println!("Park rating: {}", r);
+            }
+            if let Some(g) = sg.guests_in_park() {
+                println!("Guests in park: {}", g);
+            }
+            println!("Rides: {}", sg.rides().len());
+            println!("Objects: {} ({} packed)", sg.objects().len(), sg.packed.len());
+            if let Some(r) = &repo {
+                print_missing(r, &sg.objects(), &sg.packed);
+            }
+        },
+        FileKind::Object => {
+            let obj = DatObject::read(&mut Cursor::new(&b))?;
+            println!("Object: {} ({:?})", obj.entry.name(), obj.entry.object_type());
+            println!("Checksum: {}", if obj.is_valid() { "valid" } else { "invalid" });
+            if let Some(ro) = obj.ride_object() {
+                println!("Name: {}", ro?.name.english());
+            }
+        },
+        FileKind::Unknown => ()
+    }
+    Ok(())
+}
+
+fn print_missing(repo: &ObjectRepository, refs: &[object::ObjectEntry], packed: &[DatObject]) {
+    for e in repo.missing(refs).into_iter().filter(|e| !packed.iter().any(|o| o.entry.matches(e))) {
+        println!("Missing: {} ({:?})", e.name(), e.object_type());
+    }
+}
+
+/// `dump <file> [objdata] [--format F | --annotated]` prints everything read from a file, as text
+/// or JSON, or as hex annotated with the layout of saves and track designs.
+fn dump(args: &[String]) -> CmdResult {
+    let (args, fmt) = output_format(args)?;
+    let annotated = args.iter().any(|a| a == "--annotated");
+    let args = args.into_iter().filter(|a| a != "--annotated").collect::<Vec<_>>();
+    let path = args.first().ok_or(Error::Usage)?;
+    let repo = args.get(1).map(|d| open_repository(d)).transpose()?;
+    let (kind, b) = load(path)?;
+    if annotated {
+        if fmt == OutputFormat::Json {
+            return Err(Error::Usage);
+        }
+        let out = io::stdout();
+        let colour = out.is_terminal();
+        let mut w = BufWriter::new(out.lock());
+        match kind {
+            FileKind::TrackDesign => layout::annotate_track_design(&mut w, &b, colour)?,
+            FileKind::SavedGame | FileKind::Scenario => layout::annotate_saved_game(&mut w, &SavedGame::read(Cursor::new(&b))?, colour)?,
+            _ => return Err(Error::Failed(format!("{}: annotated dumps are only available for saves and track designs", path)))
+        }
+        return Ok(w.flush()?);
+    }
+    if fmt == OutputFormat::Json {
+        return json::dump(kind, &b, repo.as_ref());
+    }
+    let f = File::open(path)?;
+    match kind {
+        FileKind::TrackDesign => rct::read_td6_file(&f, repo.as_ref())?,
+        FileKind::SavedGame | FileKind::Scenario => rct::read_sv6_file(&f, repo.as_ref())?,
+        FileKind::Object => rct::read_dat_file(&f)?,
+        FileKind::Unknown => ()
+    }
+    Ok(())
+}
+
+/// Quotes a CSV field if it holds a separator, quote or line break.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Appends a line of comma separated fields.
+fn push_csv_row<S: AsRef<str>>(out: &mut String, fields: &[S]) {
+    out.push_str(&fields.iter().map(|f| csv_field(f.as_ref())).collect::<Vec<_>>().join(","));
+    out.push('\n');
+}
+
+/// Takes a `--csv` flag out of the arguments.
+fn csv_flag(args: &[String]) -> (Vec<&String>, bool) {
+    let rest = args.iter().filter(|a| *a != "--csv").collect::<Vec<_>>();
+    let csv = rest.len() != args.len();
+    (rest, csv)
+}
+
+fn rating_value(v: u16) -> String {
+    format!("{:.2}", f64::from(v) / 100.0)
+}
+
+/// Rides of a park as CSV, with a header line.
+fn rides_csv(sg: &SavedGame) -> String {
+    let mut out = String::new();
+    push_csv_row(&mut out, &["name", "type", "excitement", "intensity", "nausea", "age", "price", "suggested_price", "profit", "reliability"]);
+    let months = sg.months();
+    for r in sg.rides() {
+        let rt = RideType::from(r.ride_type);
+        let rated = r.is_rated() && !rt.is_shop();
+        let (exc, int, nau, suggested) = if rated {
+            let p = rct::calculate_price(sg.price_multipliers(&r), r.excitement, r.intensity, r.nausea, r.age(months));
+            (rating_value(r.excitement), rating_value(r.intensity), rating_value(r.nausea), format!("{:.2}", p / 10.0))
+        } else {
+            Default::default()
+        };
+        push_csv_row(&mut out, &[
+            sg.ride_name(&r), rt.name().to_string(), exc, int, nau, r.age(months).to_string(),
+            money(i32::from(r.price)), suggested, money(r.profit),
+            r.reliability_percent().to_string()
+        ]);
+    }
+    out
+}
+
+/// `rides <save> [--csv]` lists the rides of a park.
+fn rides(args: &[String]) -> CmdResult {
+    let (args, csv) = csv_flag(args);
+    let sg = read_save(args.first().ok_or(Error::Usage)?)?;
+    if csv {
+        print!("{}", rides_csv(&sg));
+        return Ok(());
+    }
+    println!("{:>3}  {:<32} {:<28} {:<8} {:>17} {:>7}", "#", "Name", "Type", "Status", "Ratings", "Price");
+    for r in sg.rides() {
+        let rt = RideType::from(r.ride_type);
+        let rated = if r.is_rated() && !rt.is_shop() {
+            Ratings::new(i32::from(r.excitement), i32::from(r.intensity), i32::from(r.nausea)).to_string()
+        } else {
+            "-".to_string()
+        };
+        println!("{:>3}  {:<32} {:<28} {:<8} {:>17} {:>7}", r.index, sg.ride_name(&r), rt.name(), r.status_name(), rated, money(i32::from(r.price)));
+    }
+    Ok(())
+}
+
+/// Guests in a park as CSV, with a header line.
+fn guests_csv(sg: &SavedGame) -> String {
+    let rides = sg.rides();
+    let ride_name = |i: u8| rides.iter().find(|r| r.index == i).map(|r| sg.ride_name(r)).unwrap_or_default();
+    let mut out = String::new();
+    push_csv_row(&mut out, &["id", "name", "state", "in_park", "happiness", "energy", "hunger", "thirst", "nausea", "toilet",
+        "cash", "spent", "rides", "favourite_ride"]);
+    for g in sg.guests() {
+        push_csv_row(&mut out, &[
+            g.id.to_string(), sg.guest_name(&g), g.state.name().to_string(), (!g.outside_park).to_string(),
+            g.happiness.to_string(), g.energy.to_string(), g.hunger.to_string(), g.thirst.to_string(),
+            g.nausea.to_string(), g.toilet.to_string(), money(g.cash_in_pocket),
+            money(g.cash_spent), g.rides_been_on.to_string(), ride_name(g.favourite_ride)
+        ]);
+    }
+    out
+}
+
+/// `guests <save> [--csv]` lists the guests in a park.
+fn guests(args: &[String]) -> CmdResult {
+    let (args, csv) = csv_flag(args);
+    let sg = read_save(args.first().ok_or(Error::Usage)?)?;
+    if csv {
+        print!("{}", guests_csv(&sg));
+        return Ok(());
+    }
+    println!("{:>6}  {:<24} {:<16} {:>9} {:>8} {:>5}", "Id", "Name", "State", "Happiness", "Cash", "Rides");
+    for g in sg.guests() {
+        println!("{:>6}  {:<24} {:<16} {:>8}% {:>8} {:>5}", g.id, sg.guest_name(&g), g.state.name(), g.happiness_percent(),
+            money(g.cash_in_pocket), g.rides_been_on);
+    }
+    Ok(())
+}
+
+/// `objects <file> [objdata]` lists the objects a save or track design uses.
+fn objects(args: &[String]) -> CmdResult {
+    let path = args.first().ok_or(Error::Usage)?;
+    let repo = args.get(1).map(|d| open_repository(d)).transpose()?;
+    let (refs, packed) = match load(path)? {
+        (FileKind::TrackDesign, b) => (TrackDesign::read(&b[..])?.object_refs(), Vec::new()),
+        (FileKind::SavedGame, b) | (FileKind::Scenario, b) => {
+            let sg = SavedGame::read(Cursor::new(b))?;
+            (sg.objects(), sg.packed)
+        },
+        (k, _) => return Err(wrong_kind(path, k, "saved game, scenario or track design"))
+    };
+    let missing = repo.as_ref().map(|r| r.missing(&refs)).unwrap_or_default();
+    for e in &refs {
+        let state = if packed.iter().any(|o| o.entry.matches(e)) {
+            "packed"
+        } else if missing.iter().any(|m| m.matches(e)) {
+            "missing"
+        } else if e.is_custom() {
+            "custom"
+        } else {
+            ""
+        };
+        println!("{:<8} {:<14} {}", e.name(), format!("{:?}", e.object_type()), state);
+    }
+    Ok(())
+}
+
+/// `map <save>` draws the park as text.
+fn map(args: &[String]) -> CmdResult {
+    let path = args.first().ok_or(Error::Usage)?;
+    let m = read_save(path)?.map().ok_or_else(|| Error::Failed(format!("{} has no map", path)))?;
+    print!("{}", m.ascii());
+    println!("\nE entrance  # ride  + path  * scenery  | wall  ~ water  . park land");
+    Ok(())
+}
+
+/// `tui <save>` browses a park interactively in the terminal.
+#[cfg(feature = "tui")]
+fn tui(args: &[String]) -> CmdResult {
+    let path = args.first().ok_or(Error::Usage)?;
+    let sg = read_save(path)?;
+    rct2read::tui::run(&sg, format!(" rct2read: {} ", path))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "tui"))]
+fn tui(_: &[String]) -> CmdResult {
+    Err(Error::Failed("the park inspector needs rct2read built with the tui feature".to_string()))
+}
+
+/// `diff <old> <new>` lists what changed between two saves of a park.
+fn diff(args: &[String]) -> CmdResult {
+    let (a, b) = match args {
+        [a, b] => (read_save(a)?, read_save(b)?),
+        _ => return Err(Error::Usage)
+    };
+    let changes = diff::diff_saves(&a, &b);
+    if changes.is_empty() {
+        println!("no differences");
+    }
+    for c in &changes {
+        println!("{}", c);
+    }
+    Ok(())
+}
+
+/// Parses a money amount in currency units (e.g. `1500.50`) into the game's tenths.
+fn parse_money(s: &str) -> Option<i32> {
+    s.parse::<f64>().ok().map(|v| (v * 10.0).round() as i32)
+}
+
+/// `edit <save> <out> [--cash N] [--loan N] [--max-loan N] [--entrance-fee N] [--park-rating N] [--flags HEX]`
+fn edit(args: &[String]) -> CmdResult {
+    if args.len() < 2 || !args.len().is_multiple_of(2) {
+        return Err(Error::Usage);
+    }
+    let mut sg = read_save(&args[0])?;
+    for kv in args[2..].chunks(2) {
+        let (k, v) = (kv[0].as_str(), kv[1].as_str());
+        let done = match k {
+            "--cash" => parse_money(v).and_then(|m| sg.set_cash(m)),
+            "--loan" => parse_money(v).and_then(|m| sg.set_loan(m)),
+            "--max-loan" => parse_money(v).and_then(|m| sg.set_max_loan(m)),
+            "--entrance-fee" => {
+                let fee = parse_money(v).filter(|&m| (0..=i32::from(s6::MAX_ENTRANCE_FEE)).contains(&m)).map(|m| m as u16);
+                sg.set_entrance_fee(fee.ok_or(Error::Usage)?)
+            },
+            "--park-rating" => {
+                let rating = v.parse::<u16>().ok().filter(|&r| r <= s6::MAX_PARK_RATING);
+                sg.set_park_rating(rating.ok_or(Error::Usage)?)
             },
-            Some("sv6") => {
-                rct::read_sv6_file(&f);
+            "--flags" => u32::from_str_radix(v.trim_start_matches("0x"), 16).ok().and_then(|f| sg.set_park_flags(f)),
+            _ => return Err(Error::Usage)
+        };
+        if done.is_none() {
+            return Err(Error::Failed(format!("cannot set {} to {}", k, v)));
+        }
+    }
+    println!("Cash: {}", money(sg.cash().unwrap_or(0)));
+    println!("Loan: {} (max {})", money(sg.loan().unwrap_or(0)), money(sg.max_loan().unwrap_or(0)));
+    println!("Entrance fee: {}", money(i32::from(sg.entrance_fee().unwrap_or(0))));
+    println!("Park rating: {}", sg.park_rating().unwrap_or(0));
+    println!("Park flags: {:08X}", sg.park_flags().unwrap_or(0));
+    sg.write(create(&args[1])?)?;
+    Ok(())
+}
+
+/// File contents `convert` can write.
+enum Contents {
+    TrackDesign(TrackDesign),
+    SavedGame(SavedGame),
+    Object(DatObject)
+}
+
+fn has_extension(path: &str, ext: &str) -> bool {
+    Path::new(path).extension().is_some_and(|x| x.eq_ignore_ascii_case(ext))
+}
+
+/// `convert <file> <out> [--encoding E]` rewrites a file, optionally changing how its chunks are
+/// encoded. Track designs and objects written to a `.bin` file are stored decoded; any file can be
+/// written to, and read back from, a `.json` document.
+fn convert(args: &[String]) -> CmdResult {
+    let encoding = match args.len() {
+        2 => None,
+        4 if args[2] == "--encoding" => Some(Encoding::from_name(&args[3]).ok_or(Error::Usage)?),
+        _ => return Err(Error::Usage)
+    };
+    let mut contents = if has_extension(&args[0], "json") {
+        json::read_document(&args[0])?
+    } else {
+        match load(&args[0])? {
+            (FileKind::TrackDesign, b) => Contents::TrackDesign(TrackDesign::read(&b[..])?),
+            (FileKind::SavedGame, b) | (FileKind::Scenario, b) => Contents::SavedGame(SavedGame::read(Cursor::new(b))?),
+            (FileKind::Object, b) => Contents::Object(DatObject::read(&mut Cursor::new(b))?),
+            (FileKind::Unknown, _) => return Ok(())
+        }
+    };
+    if let Some(enc) = encoding {
+        match &mut contents {
+            Contents::TrackDesign(_) => return Err(Error::Failed("track designs are always RLE encoded".to_string())),
+            Contents::SavedGame(sg) => for c in &mut sg.chunks {
+                *c = Chunk::new(enc, std::mem::take(c.data_mut()));
             },
-            _ => {
-                println!("Unsupported extension");
+            Contents::Object(obj) => obj.encoding = enc
+        }
+    }
+    if has_extension(&args[1], "json") {
+        return json::write_document(contents, &args[1]);
+    }
+    let raw = has_extension(&args[1], "bin");
+    match contents {
+        Contents::TrackDesign(td) if raw => std::fs::write(&args[1], td.to_data())?,
+        Contents::TrackDesign(td) => td.write(create(&args[1])?)?,
+        Contents::SavedGame(_) if raw => return Err(Error::Failed("saves hold several chunks and cannot be written decoded".to_string())),
+        Contents::SavedGame(sg) => sg.write(create(&args[1])?)?,
+        Contents::Object(obj) if raw => std::fs::write(&args[1], &obj.data)?,
+        Contents::Object(obj) => obj.write(create(&args[1])?)?
+    }
+    Ok(())
+}
+
+/// `validate <track.td6>...` reports layout problems, failing if any design has errors.
+fn validate(args: &[String]) -> CmdResult {
+    if args.is_empty() {
+        return Err(Error::Usage);
+    }
+    let mut failed = 0;
+    for a in args {
+        let td = read_track(a)?;
+        let diags = validate::validate_track(&td);
+        if diags.is_empty() {
+            println!("{}: ok", a);
+        }
+        for d in &diags {
+            println!("{}: {}", a, d);
+        }
+        if validate::has_errors(&diags) {
+            failed += 1;
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        n => Err(Error::Failed(format!("{} of {} designs have errors", n, args.len())))
+    }
+}
+
+/// `extract <file> <outdir> [objdata]` writes objects packed in a save, or used by a track, as DAT files.
+fn extract(args: &[String]) -> CmdResult {
+    if args.len() < 2 {
+        return Err(Error::Usage);
+    }
+    let objects = match load(&args[0])? {
+        (FileKind::TrackDesign, b) => {
+            // Tracks only refer to objects, so they have to come from the object directory
+            let repo = match args.get(2) {
+                Some(d) => open_repository(d)?,
+                None => return Err(Error::Failed("tracks do not contain objects, provide an object directory".to_string()))
+            };
+            let refs = TrackDesign::read(&b[..])?.object_refs();
+            for e in repo.missing(&refs) {
+                println!("Missing: {}", e.name());
+            }
+            repo.load_all(&refs)?
+        },
+        (FileKind::SavedGame, b) | (FileKind::Scenario, b) => SavedGame::read(Cursor::new(b))?.packed,
+        (k, _) => return Err(wrong_kind(&args[0], k, "saved game, scenario or track design"))
+    };
+    for p in object::extract_objects(&objects, &args[1])? {
+        println!("{}", p.display());
+    }
+    Ok(())
+}
+
+/// `chunks extract <save> <outdir>` writes the decoded chunks of a save to files, and
+/// `chunks pack <dir> <out>` puts them back together.
+fn chunks(args: &[String]) -> CmdResult {
+    match args {
+        [cmd, path, dir] if cmd == "extract" => {
+            for p in chunks::extract(&read_save(path)?, dir)? {
+                println!("{}", p.display());
+            }
+        },
+        [cmd, dir, out] if cmd == "pack" => {
+            let sg = chunks::pack(dir).map_err(|e| Error::Failed(format!("{}: {}", dir, e)))?;
+            let mut w = create(out)?;
+            sg.write(&mut w)?;
+            w.flush()?;
+        },
+        _ => return Err(Error::Usage)
+    }
+    Ok(())
+}
+
+/// `pack <save> <objdata> <out>` embeds the custom objects a save uses, so it can be shared on its own.
+fn pack(args: &[String]) -> CmdResult {
+    if args.len() < 3 {
+        return Err(Error::Usage);
+    }
+    let mut sg = read_save(&args[0])?;
+    let before = sg.packed.len();
+    let repo = open_repository(&args[1])?;
+    for e in sg.pack_objects(&repo)? {
+        println!("Missing: {}", e.name());
+    }
+    for o in &sg.packed[before..] {
+        println!("Packed: {}", o.entry.name());
+    }
+    sg.write(create(&args[2])?)?;
+    Ok(())
+}
+
+/// `prices <save> (<out> | --dry-run)` sets every ride to its suggested ticket price.
+fn prices(args: &[String]) -> CmdResult {
+    if args.len() < 2 {
+        return Err(Error::Usage);
+    }
+    let mut sg = read_save(&args[0])?;
+    if !sg.ride_prices_unlocked() {
+        return Err(Error::Failed("ride prices are locked in this park (no money or paid entry)".to_string()));
+    }
+    let rides = sg.rides();
+    for c in sg.optimise_prices() {
+        let name = rides.iter().find(|r| r.index == c.ride).map(|r| sg.ride_name(r)).unwrap_or_default();
+        println!("{}: {} -> {}", name, money(i32::from(c.old)), money(i32::from(c.new)));
+    }
+    if args[1] != "--dry-run" {
+        sg.write(create(&args[1])?)?;
+    }
+    Ok(())
+}
+
+fn print_ratings(name: &str, stored: Option<Ratings>, computed: Ratings) {
+    match stored {
+        Some(s) => println!("{}: computed {}, stored {}{}", name, computed, s,
+            if ratings::looks_corrupted(s, computed) { " (corrupted?)" } else { "" }),
+        None => println!("{}: computed {}, not tested", name, computed)
+    }
+}
+
+/// `ratings <file>` recalculates excitement, intensity and nausea from ride stats.
+fn ratings(args: &[String]) -> CmdResult {
+    let path = args.first().ok_or(Error::Usage)?;
+    match load(path)? {
+        (FileKind::TrackDesign, b) => {
+            let td = TrackDesign::read(&b[..])?;
+            match ratings::calculate(&RideStats::from_track_design(&td)) {
+                Some(r) => print_ratings("Track", Some(ratings::stored_td6(&td)), r),
+                None => println!("Ride type 0x{:02X} is not rated from its track", td.ride_type)
+            }
+        },
+        (FileKind::SavedGame, b) | (FileKind::Scenario, b) => {
+            let sg = SavedGame::read(Cursor::new(b))?;
+            for r in sg.rides() {
+                if let Some(c) = ratings::calculate(&RideStats::from_ride(&r)) {
+                    print_ratings(&sg.ride_name(&r), ratings::stored(&r), c);
+                }
             }
+        },
+        (k, _) => return Err(wrong_kind(path, k, "saved game, scenario or track design"))
+    }
+    Ok(())
+}
+
+/// `simulate <track.td6> [--elements]` runs a train around the layout and compares the stats with the stored ones.
+fn simulate(args: &[String]) -> CmdResult {
+    let td = read_track(args.first().ok_or(Error::Usage)?)?;
+    let sim = physics::simulate(&td);
+    if args.iter().any(|a| a == "--elements") {
+        println!("{:>5} {:<28} {:>6} {:>6} {:>6} {:>6} {:>6} {:>6}", "#", "element", "entry", "exit", "vert+", "vert-", "lat", "air");
+        for e in &sim.elements {
+            println!("{:>5} {:<28} {:>6.1} {:>6.1} {:>6.2} {:>6.2} {:>6.2} {:>6.2}", e.index, rct::segment_name(e.kind),
+                e.entry_speed, e.exit_speed, e.max_vertical_g, e.min_vertical_g, e.max_lateral_g, e.air_time);
         }
     }
-    
+    match sim.stalled_at {
+        Some(i) => println!("Train stalls on element {} ({})", i, rct::segment_name(td.elements[i].kind)),
+        None => println!("Train completes the circuit in {:.1}s", sim.duration)
+    }
+    let stats = sim.stats(&td);
+    println!("{:<22} {:>8} {:>10}", "", "stored", "simulated");
+    println!("{:<22} {:>8} {:>10}", "Max speed", td.max_speed, stats.max_speed >> 16);
+    println!("{:<22} {:>8} {:>10}", "Average speed", td.average_speed, stats.average_speed >> 16);
+    println!("{:<22} {:>8} {:>10}", "Length", td.ride_length, stats.length);
+    println!("{:<22} {:>8.2} {:>10.2}", "Max positive vert. G", f32::from(td.max_positive_vertical_g) * 0.32, sim.max_positive_vertical_g);
+    println!("{:<22} {:>8.2} {:>10.2}", "Max negative vert. G", f32::from(td.max_negative_vertical_g) * 0.32, sim.max_negative_vertical_g);
+    println!("{:<22} {:>8.2} {:>10.2}", "Max lateral G", f32::from(td.max_lateral_g) * 0.32, sim.max_lateral_g);
+    println!("{:<22} {:>8} {:>10}", "Air time", td.total_air_time, sim.td6_air_time());
+    println!("{:<22} {:>8} {:>10}", "Inversions", td.inversions & 0x1F, stats.inversions);
+    println!("{:<22} {:>8} {:>10}", "Drops", td.drops & 0x3F, stats.drops);
+    println!("{:<22} {:>8} {:>10}", "Highest drop", td.highest_drop_height, stats.highest_drop_height);
+    if let Some(r) = ratings::calculate(&stats) {
+        print_ratings("Ratings", Some(ratings::stored_td6(&td)), r);
+    }
+    Ok(())
+}
+
+/// `export <track.td6> <out.obj|out.gltf>` writes the track layout as a 3D mesh.
+fn export(args: &[String]) -> CmdResult {
+    if args.len() < 2 {
+        return Err(Error::Usage);
+    }
+    let td = read_track(&args[0])?;
+    let mesh = Mesh::from_track(&td.elements);
+    let out = Path::new(&args[1]);
+    if out.extension().is_some_and(|x| x.eq_ignore_ascii_case("obj")) {
+        let mtl = out.with_extension("mtl");
+        mesh.write_mtl(BufWriter::new(File::create(&mtl)?))?;
+        let name = mtl.file_name().unwrap().to_string_lossy();
+        mesh.write_obj(create(&args[1])?, &name)?;
+    } else {
+        mesh.write_gltf(create(&args[1])?)?;
+    }
+    println!("Wrote {} vertices", mesh.positions.len());
+    Ok(())
 }
 
+/// `svg <track.td6> <plan.svg> <elevation.svg>` draws the layout from above and from the side.
+fn svg(args: &[String]) -> CmdResult {
+    if args.len() < 3 {
+        return Err(Error::Usage);
+    }
+    let td = read_track(&args[0])?;
+    std::fs::write(&args[1], svg::plan(&td.elements))?;
+    std::fs::write(&args[2], svg::elevation(&td.elements))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rct2read::{peep, ride};
+    use rct2read::sawyer::{self, ChunkWriter};
+
+    /// Saved game with no rides or guests, 40 months into the park.
+    fn save() -> SavedGame {
+        let mut game_data = vec![0; s6::GAME_DATA_SIZE];
+        for i in 0..ride::MAX_RIDES {
+            game_data[s6::RIDES + i * ride::RIDE_SIZE] = 0xFF;
+        }
+        let mut v = Vec::new();
+        {
+            let mut cw = ChunkWriter::new(&mut v);
+            cw.write_chunk(&Chunk::new(Encoding::RLE, vec![0; 0x20])).unwrap();
+            cw.write_chunk(&Chunk::new(Encoding::RLE, vec![0xFF; 16])).unwrap();
+            cw.write_chunk(&Chunk::new(Encoding::RLE, vec![40, 0, 0, 0])).unwrap();
+            cw.write_chunk(&Chunk::new(Encoding::RLECompressed, vec![0; 64])).unwrap();
+            cw.write_chunk(&Chunk::new(Encoding::RLE, game_data)).unwrap();
+        }
+        let checksum = sawyer::calculate_checksum(&v);
+        v.extend_from_slice(&checksum.to_le_bytes());
+        SavedGame::read(Cursor::new(v)).unwrap()
+    }
+
+    /// Stores `name` as user string `id`.
+    fn set_name(sg: &mut SavedGame, id: u16, name: &str) {
+        let b = sg.game_data_mut(s6::CUSTOM_STRINGS + usize::from(id % 1024) * 32, 32).unwrap();
+        b[..name.len()].copy_from_slice(name.as_bytes());
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        assert_eq!(csv_field("Log Flume"), "Log Flume");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("Twist, Shout"), "\"Twist, Shout\"");
+        assert_eq!(csv_field("The \"Big\" One"), "\"The \"\"Big\"\" One\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+        let mut out = String::new();
+        push_csv_row(&mut out, &["a", "b,c", "\""]);
+        assert_eq!(out, "a,\"b,c\",\"\"\"\"\n");
+    }
+
+    #[test]
+    fn rides_as_csv() {
+        let mut sg = save();
+        assert_eq!(rides_csv(&sg), "name,type,excitement,intensity,nausea,age,price,suggested_price,profit,reliability\n");
+        let b = sg.game_data_mut(s6::RIDES + 2 * ride::RIDE_SIZE, ride::RIDE_SIZE).unwrap();
+        b.iter_mut().for_each(|x| *x = 0);
+        b[0x000] = 0x34;
+        b[0x04A..0x04C].copy_from_slice(&0x8001u16.to_le_bytes());
+        b[0x138..0x13A].copy_from_slice(&60u16.to_le_bytes());
+        b[0x140..0x146].copy_from_slice(&[0x8A, 0x02, 0x08, 0x02, 0x2C, 0x01]);
+        b[0x1B4..0x1B8].copy_from_slice(&(-250i32).to_le_bytes());
+        b[0x196..0x198].copy_from_slice(&0x5A00u16.to_le_bytes());
+        let b = sg.game_data_mut(s6::RIDES + 3 * ride::RIDE_SIZE, ride::RIDE_SIZE).unwrap();
+        b.iter_mut().for_each(|x| *x = 0);
+        b[0x000] = 0x1C;
+        b[0x140..0x142].copy_from_slice(&0xFFFFu16.to_le_bytes());
+        set_name(&mut sg, 0x8001, "Woody, \"The\" Coaster");
+        let csv = rides_csv(&sg);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "\"Woody, \"\"The\"\" Coaster\",Wooden Roller Coaster,6.50,5.20,3.00,40,6.00,7.70,-25.00,90");
+        // Shops have no ratings or suggested price
+        assert_eq!(lines[2], "Ride 0 (type 0x1C),Food Stall,,,,40,0.00,,0.00,0");
+    }
+
+    #[test]
+    fn guests_as_csv() {
+        let mut sg = save();
+        assert_eq!(guests_csv(&sg).lines().count(), 1);
+        let b = sg.game_data_mut(s6::SPRITES + 5 * peep::SPRITE_SIZE, peep::SPRITE_SIZE).unwrap();
+        b[0x00] = 1;
+        b[0x22..0x24].copy_from_slice(&0x8002u16.to_le_bytes());
+        b[0x2F] = 3;
+        b[0x3A] = 200;
+        b[0x9C..0xA0].copy_from_slice(&17u32.to_le_bytes());
+        b[0xA0..0xA4].copy_from_slice(&455u32.to_le_bytes());
+        set_name(&mut sg, 0x8002, "Smith, J");
+        let csv = guests_csv(&sg);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines, ["id,name,state,in_park,happiness,energy,hunger,thirst,nausea,toilet,cash,spent,rides,favourite_ride",
+            "17,\"Smith, J\",Falling,true,200,0,0,0,0,0,45.50,0.00,3,"]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn batch_records() {
+        let r = json::batch_record(Path::new("archive/broken.sv6"), Err("failed to fill whole buffer".to_string()));
+        assert_eq!(r.to_string(), r#"{"error":"failed to fill whole buffer","file":"archive/broken.sv6"}"#);
+        let mut b = Vec::new();
+        save().write(&mut b).unwrap();
+        let r = json::batch_record(Path::new("park.sv6"), rct2read::batch::summarize(&b).map_err(|e| e.to_string()));
+        assert_eq!(r["file"], "park.sv6");
+        assert!(r.get("error").is_none());
+        assert_eq!(r["summary"]["kind"], "SavedGame");
+        assert_eq!(r["summary"]["months"], 40);
+        assert_eq!(r["summary"]["rides"], 0);
+    }
+}
@@ -0,0 +1,184 @@
+/// Tiles along each side of the map data, whatever the park's size.
+pub const MAP_SIZE: usize = 256;
+pub const TILE_ELEMENT_SIZE: usize = 8;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ElementType {
+    Surface,
+    Path,
+    Track,
+    SmallScenery,
+    Entrance,
+    Wall,
+    LargeScenery,
+    Banner,
+    Unknown
+}
+
+impl From<u8> for ElementType {
+    fn from(n: u8) -> Self {
+        use ElementType::*;
+        match n {
+            0 => Surface,
+            1 => Path,
+            2 => Track,
+            3 => SmallScenery,
+            4 => Entrance,
+            5 => Wall,
+            6 => LargeScenery,
+            7 => Banner,
+            _ => Unknown
+        }
+    }
+}
+
+/// One of the stacked elements making up a map tile.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileElement {
+    pub kind: ElementType,
+    pub direction: u8,
+    pub flags: u8,
+    pub base_height: u8,
+    pub clearance_height: u8,
+    /// Type specific properties.
+    pub data: [u8; 4]
+}
+
+impl TileElement {
+    pub const FLAG_LAST_FOR_TILE: u8 = 1 << 7;
+    /// Surface ownership flags (in the last data byte).
+    pub const OWNERSHIP_CONSTRUCTION_RIGHTS: u8 = 1 << 4;
+    pub const OWNERSHIP_OWNED: u8 = 1 << 5;
+
+    pub fn from_slice(b: &[u8]) -> Self {
+        TileElement{
+            kind: ElementType::from((b[0] >> 2) & 0x0F),
+            direction: b[0] & 3,
+            flags: b[1],
+            base_height: b[2],
+            clearance_height: b[3],
+            data: [b[4], b[5], b[6], b[7]]
+        }
+    }
+
+    pub fn is_last_for_tile(&self) -> bool {
+        self.flags & Self::FLAG_LAST_FOR_TILE != 0
+    }
+
+    /// Water level of a surface, in the same units as heights.
+    pub fn water_height(&self) -> u8 {
+        (self.data[1] & 0x1F) * 2
+    }
+
+    /// Whether a surface is under water.
+    pub fn is_underwater(&self) -> bool {
+        self.kind == ElementType::Surface && self.water_height() > self.base_height
+    }
+
+    /// Whether the park owns a surface or the rights to build above it.
+    pub fn is_owned(&self) -> bool {
+        self.kind == ElementType::Surface && self.data[3] & (Self::OWNERSHIP_OWNED | Self::OWNERSHIP_CONSTRUCTION_RIGHTS) != 0
+    }
+
+    /// Ride a track element or ride entrance belongs to.
+    pub fn ride_index(&self) -> Option<u8> {
+        match self.kind {
+            ElementType::Track => Some(self.data[3]),
+            ElementType::Entrance if self.data[0] != 2 => Some(self.data[3]),
+            _ => None
+        }
+    }
+}
+
+/// Map tiles, decoded from the tile element chunk of a saved game.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Map {
+    tiles: Vec<Vec<TileElement>>
+}
+
+impl Map {
+    /// Splits the element list into tiles, stored row by row.
+    pub fn from_data(b: &[u8]) -> Self {
+        let mut tiles = Vec::with_capacity(MAP_SIZE * MAP_SIZE);
+        let mut elements = b.chunks_exact(TILE_ELEMENT_SIZE).map(TileElement::from_slice);
+        while tiles.len() < MAP_SIZE * MAP_SIZE {
+            let mut tile = Vec::new();
+            for e in elements.by_ref() {
+                tile.push(e);
+                if e.is_last_for_tile() {
+                    break;
+                }
+            }
+            if tile.is_empty() {
+                break;
+            }
+            tiles.push(tile);
+        }
+        tiles.resize(MAP_SIZE * MAP_SIZE, Vec::new());
+        Map{tiles}
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> &[TileElement] {
+        &self.tiles[y * MAP_SIZE + x]
+    }
+
+    /// Whether a tile holds anything other than unowned land.
+    fn is_used(&self, x: usize, y: usize) -> bool {
+        self.tile(x, y).iter().any(|e| e.kind != ElementType::Surface || e.is_owned())
+    }
+
+    /// Smallest rectangle (min x, min y, max x, max y) holding the park and everything built.
+    pub fn used_area(&self) -> Option<(usize, usize, usize, usize)> {
+        let mut area: Option<(usize, usize, usize, usize)> = None;
+        for y in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                if self.is_used(x, y) {
+                    area = Some(match area {
+                        Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                        None => (x, y, x, y)
+                    });
+                }
+            }
+        }
+        area
+    }
+
+    /// Character summarising a tile on text maps.
+    pub fn tile_char(&self, x: usize, y: usize) -> char {
+        let tile = self.tile(x, y);
+        let has = |k| tile.iter().any(|e| e.kind == k);
+        if has(ElementType::Entrance) {
+            'E'
+        } else if has(ElementType::Track) {
+            '#'
+        } else if has(ElementType::Path) {
+            '+'
+        } else if has(ElementType::SmallScenery) || has(ElementType::LargeScenery) {
+            '*'
+        } else if has(ElementType::Wall) || has(ElementType::Banner) {
+            '|'
+        } else if tile.iter().any(|e| e.is_underwater()) {
+            '~'
+        } else if tile.iter().any(|e| e.is_owned()) {
+            '.'
+        } else {
+            ' '
+        }
+    }
+
+    /// Text map of the used area, one line per row of tiles.
+    pub fn ascii(&self) -> String {
+        let mut s = String::new();
+        if let Some((x0, y0, x1, y1)) = self.used_area() {
+            for y in y0..=y1 {
+                let line = (x0..=x1).map(|x| self.tile_char(x, y)).collect::<String>();
+                s.push_str(line.trim_end());
+                s.push('\n');
+            }
+        }
+        s
+    }
+}
@@ -0,0 +1,346 @@
+use std::f32::consts::PI;
+use std::io::{self, Write};
+use crate::td6::TrackElement;
+use crate::track::{self, PlacedElement, TILE_SIZE};
+
+/// Part of the track a mesh section belongs to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Material {
+    Track,
+    Lift,
+    Station
+}
+
+impl Material {
+    pub const ALL: [Material; 3] = [Material::Track, Material::Lift, Material::Station];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Material::Track => "track",
+            Material::Lift => "chain_lift",
+            Material::Station => "station"
+        }
+    }
+
+    fn colour(self) -> [f32; 3] {
+        match self {
+            Material::Track => [0.8, 0.2, 0.1],
+            Material::Lift => [0.3, 0.3, 0.3],
+            Material::Station => [0.9, 0.8, 0.3]
+        }
+    }
+}
+
+/// Point of the track centreline, in tiles (z up).
+#[derive(Copy, Clone, Debug)]
+pub struct CentrelinePoint {
+    pub position: [f32; 3],
+    pub material: Material,
+    /// Index of the element in the track's element list.
+    pub element: usize
+}
+
+type Vec3 = [f32; 3];
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn normalize(a: Vec3) -> Vec3 {
+    let l = (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt();
+    if l > 0.0 { [a[0] / l, a[1] / l, a[2] / l] } else { a }
+}
+
+/// Unit heading of a direction (0-7) in the horizontal plane.
+fn heading(direction: u8) -> [f32; 2] {
+    let (x, y) = if direction & 4 != 0 {
+        track::rotate(-1, -1, direction)
+    } else {
+        track::rotate(-1, 0, direction)
+    };
+    let l = ((x * x + y * y) as f32).sqrt();
+    [x as f32 / l, y as f32 / l]
+}
+
+/// Point where track enters the tile at (x, y), heading in `direction`.
+fn entry(x: i32, y: i32, direction: u8) -> [f32; 2] {
+    let h = heading(direction);
+    let half = TILE_SIZE as f32 / 2.0;
+    // Diagonal track enters at a tile corner
+    let r = if direction & 4 != 0 { half * 2f32.sqrt() } else { half };
+    [(x as f32 + half - h[0] * r) / TILE_SIZE as f32, (y as f32 + half - h[1] * r) / TILE_SIZE as f32]
+}
+
+/// Samples the centreline of laid out track, as a Hermite curve between element ends.
+pub fn centreline(elements: &[TrackElement], placed: &[PlacedElement]) -> Vec<CentrelinePoint> {
+    let mut line = Vec::new();
+    let scale = TILE_SIZE as f32;
+    for p in placed {
+        let g = match track::geometry(p.kind) {
+            Some(g) => g,
+            None => continue
+        };
+        let material = if g.is_station() {
+            Material::Station
+        } else if elements[p.index].has_chain_lift() {
+            Material::Lift
+        } else {
+            Material::Track
+        };
+        let p0 = entry(p.x, p.y, p.direction);
+        let p1 = entry(p.end.0, p.end.1, p.end_direction);
+        let (h0, h1) = (heading(p.direction), heading(p.end_direction));
+        let dist = ((p1[0] - p0[0]).powi(2) + (p1[1] - p0[1]).powi(2)).sqrt();
+        let (z0, z1) = (p.z as f32 / scale, p.end.2 as f32 / scale);
+        let base = (p.z - i32::from(g.start_height)) as f32 / scale;
+        let top = base + f32::from(g.blocks.iter().map(|b| b.z).max().unwrap_or(0)) / scale;
+        let bump = (top - z0.max(z1)).max(0.0);
+        let n = (usize::from(g.length) / 8).max(2);
+        for i in 0..n {
+            let t = i as f32 / n as f32;
+            let (t2, t3) = (t * t, t * t * t);
+            let (a, b, c, d) = (2.0 * t3 - 3.0 * t2 + 1.0, t3 - 2.0 * t2 + t, -2.0 * t3 + 3.0 * t2, t3 - t2);
+            let x = a * p0[0] + b * h0[0] * dist + c * p1[0] + d * h1[0] * dist;
+            let y = a * p0[1] + b * h0[1] * dist + c * p1[1] + d * h1[1] * dist;
+            let z = z0 + (z1 - z0) * t + bump * (PI * t).sin();
+            line.push(CentrelinePoint{position: [x, y, z], material, element: p.index});
+        }
+    }
+    if let (Some(p), Some(last)) = (placed.last(), line.last().copied()) {
+        let e = entry(p.end.0, p.end.1, p.end_direction);
+        line.push(CentrelinePoint{position: [e[0], e[1], p.end.2 as f32 / scale], material: last.material, element: last.element});
+    }
+    line
+}
+
+/// Triangle mesh with one index list per material.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    /// Vertex positions in tiles, y up.
+    pub positions: Vec<Vec3>,
+    pub triangles: Vec<(Material, Vec<u32>)>
+}
+
+impl Mesh {
+    /// Sweeps a rectangular profile along a centreline.
+    pub fn sweep(line: &[CentrelinePoint], width: f32, thickness: f32) -> Self {
+        let mut mesh = Mesh{
+            positions: Vec::new(),
+            triangles: Material::ALL.iter().map(|&m| (m, Vec::new())).collect()
+        };
+        if line.len() < 2 {
+            return mesh;
+        }
+        let profile = [(-width / 2.0, 0.0), (width / 2.0, 0.0), (width / 2.0, -thickness), (-width / 2.0, -thickness)];
+        for i in 0..line.len() {
+            let prev = line[i.saturating_sub(1)].position;
+            let next = line[(i + 1).min(line.len() - 1)].position;
+            let tangent = normalize(sub(next, prev));
+            let reference = if tangent[2].abs() > 0.99 { [1.0, 0.0, 0.0] } else { [0.0, 0.0, 1.0] };
+            let side = normalize(cross(tangent, reference));
+            let up = cross(side, tangent);
+            let c = line[i].position;
+            for &(s, u) in &profile {
+                let v = [c[0] + side[0] * s + up[0] * u, c[1] + side[1] * s + up[1] * u, c[2] + side[2] * s + up[2] * u];
+                // Convert from z up to y up, turning about the x axis so the layout is not mirrored
+                mesh.positions.push([v[0], v[2], -v[1]]);
+            }
+        }
+        for (i, p) in line[..line.len() - 1].iter().enumerate() {
+            let tris = &mut mesh.triangles.iter_mut().find(|t| t.0 == p.material).unwrap().1;
+            let (a, b) = (i as u32 * 4, (i as u32 + 1) * 4);
+            for k in 0..4 {
+                let k1 = (k + 1) % 4;
+                // Counter-clockwise seen from outside
+                tris.extend_from_slice(&[a + k, b + k1, b + k, a + k, a + k1, b + k1]);
+            }
+        }
+        mesh
+    }
+
+    /// Builds the mesh of a track layout.
+    pub fn from_track(elements: &[TrackElement]) -> Self {
+        let placed = track::walk(elements);
+        Self::sweep(&centreline(elements, &placed), 0.4, 0.1)
+    }
+
+    pub fn write_mtl<W: Write>(&self, mut w: W) -> io::Result<()> {
+        for m in &Material::ALL {
+            let [r, g, b] = m.colour();
+            writeln!(w, "newmtl {}\nKd {} {} {}\n", m.name(), r, g, b)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a Wavefront OBJ file referring to materials in `mtl`.
+    pub fn write_obj<W: Write>(&self, mut w: W, mtl: &str) -> io::Result<()> {
+        writeln!(w, "mtllib {}", mtl)?;
+        for p in &self.positions {
+            writeln!(w, "v {} {} {}", p[0], p[1], p[2])?;
+        }
+        for (m, idx) in &self.triangles {
+            if idx.is_empty() {
+                continue;
+            }
+            writeln!(w, "usemtl {}", m.name())?;
+            for t in idx.chunks(3) {
+                writeln!(w, "f {} {} {}", t[0] + 1, t[1] + 1, t[2] + 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a glTF 2.0 file with the buffer embedded as a data URI. Fails on an empty mesh,
+    /// whose position bounds glTF cannot express.
+    pub fn write_gltf<W: Write>(&self, mut w: W) -> io::Result<()> {
+        if self.positions.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the mesh has no vertices"));
+        }
+        let mut buf = Vec::new();
+        let (mut min, mut max) = ([f32::MAX; 3], [f32::MIN; 3]);
+        for p in &self.positions {
+            for k in 0..3 {
+                buf.extend_from_slice(&p[k].to_le_bytes());
+                min[k] = min[k].min(p[k]);
+                max[k] = max[k].max(p[k]);
+            }
+        }
+        let mut views = vec![format!(r#"{{"buffer":0,"byteOffset":0,"byteLength":{},"target":34962}}"#, buf.len())];
+        let mut accessors = vec![format!(
+            r#"{{"bufferView":0,"componentType":5126,"count":{},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+            self.positions.len(), min[0], min[1], min[2], max[0], max[1], max[2]
+        )];
+        let mut primitives = Vec::new();
+        for (i, (_, idx)) in self.triangles.iter().enumerate() {
+            if idx.is_empty() {
+                continue;
+            }
+            let offset = buf.len();
+            for &v in idx {
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            views.push(format!(r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":34963}}"#, offset, idx.len() * 4));
+            accessors.push(format!(r#"{{"bufferView":{},"componentType":5125,"count":{},"type":"SCALAR"}}"#, views.len() - 1, idx.len()));
+            primitives.push(format!(r#"{{"attributes":{{"POSITION":0}},"indices":{},"material":{}}}"#, accessors.len() - 1, i));
+        }
+        let materials = Material::ALL.iter().map(|m| {
+            let [r, g, b] = m.colour();
+            format!(r#"{{"name":"{}","pbrMetallicRoughness":{{"baseColorFactor":[{},{},{},1.0],"metallicFactor":0.0}}}}"#, m.name(), r, g, b)
+        }).collect::<Vec<_>>();
+        write!(w, r#"{{"asset":{{"version":"2.0","generator":"rct2read"}},"scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"#)?;
+        write!(w, r#""meshes":[{{"primitives":[{}]}}],"materials":[{}],"#, primitives.join(","), materials.join(","))?;
+        write!(w, r#""accessors":[{}],"bufferViews":[{}],"#, accessors.join(","), views.join(","))?;
+        writeln!(w, r#""buffers":[{{"byteLength":{},"uri":"data:application/octet-stream;base64,{}"}}]}}"#, buf.len(), base64(&buf))
+    }
+}
+
+fn base64(b: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity(b.len().div_ceil(3) * 4);
+    for c in b.chunks(3) {
+        let n = u32::from(c[0]) << 16 | u32::from(*c.get(1).unwrap_or(&0)) << 8 | u32::from(*c.get(2).unwrap_or(&0));
+        for i in 0..4 {
+            if i <= c.len() {
+                s.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize]));
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> Vec<TrackElement> {
+        vec![
+            TrackElement{kind: 0x02, flags: 0},
+            TrackElement{kind: 0x06, flags: TrackElement::FLAG_CHAIN_LIFT},
+            TrackElement{kind: 0x09, flags: 0},
+            TrackElement{kind: 0x00, flags: 0}
+        ]
+    }
+
+    fn indices(m: &Mesh, material: Material) -> usize {
+        m.triangles.iter().find(|t| t.0 == material).map_or(0, |t| t.1.len())
+    }
+
+    #[test]
+    fn centreline_follows_elements() {
+        let els = layout();
+        let line = centreline(&els, &track::walk(&els));
+        let first = line.first().unwrap();
+        let last = line.last().unwrap();
+        assert_eq!((first.position, first.material), ([1.0, 0.5, 0.0], Material::Station));
+        assert_eq!(last.position, [-3.0, 0.5, 0.5]);
+        assert_eq!(last.element, 3);
+        assert!(line.iter().any(|p| p.material == Material::Lift && p.element == 1));
+    }
+
+    #[test]
+    fn sweeps_a_box_section() {
+        let line = [
+            CentrelinePoint{position: [0.0, 0.0, 0.0], material: Material::Track, element: 0},
+            CentrelinePoint{position: [-1.0, 0.0, 0.0], material: Material::Track, element: 0}
+        ];
+        let m = Mesh::sweep(&line, 0.4, 0.1);
+        assert_eq!(m.positions.len(), 8);
+        assert_eq!((indices(&m, Material::Track), indices(&m, Material::Lift)), (24, 0));
+        assert_eq!(m.positions[0], [0.0, 0.0, 0.2]);
+        assert!(Mesh::sweep(&line[..1], 0.4, 0.1).positions.is_empty());
+    }
+
+    #[test]
+    fn writes_obj() {
+        let m = Mesh::from_track(&layout());
+        let mut v = Vec::new();
+        m.write_obj(&mut v, "track.mtl").unwrap();
+        let obj = String::from_utf8(v).unwrap();
+        let lines: Vec<_> = obj.lines().collect();
+        assert_eq!(lines[0], "mtllib track.mtl");
+        assert_eq!(lines.iter().filter(|l| l.starts_with("v ")).count(), m.positions.len());
+        let total: usize = m.triangles.iter().map(|t| t.1.len()).sum();
+        let faces: Vec<_> = lines.iter().filter(|l| l.starts_with("f ")).collect();
+        assert_eq!(faces.len(), total / 3);
+        for f in faces {
+            assert!(f[2..].split(' ').all(|i| (1..=m.positions.len()).contains(&i.parse().unwrap())));
+        }
+        let used: Vec<_> = lines.iter().filter_map(|l| l.strip_prefix("usemtl ")).collect();
+        assert_eq!(used, ["track", "chain_lift", "station"]);
+        let mut mtl = Vec::new();
+        m.write_mtl(&mut mtl).unwrap();
+        assert_eq!(String::from_utf8(mtl).unwrap().matches("newmtl ").count(), 3);
+    }
+
+    #[test]
+    fn writes_gltf() {
+        let m = Mesh::from_track(&layout()[..1]);
+        let mut v = Vec::new();
+        m.write_gltf(&mut v).unwrap();
+        let gltf = String::from_utf8(v).unwrap();
+        let vertices = m.positions.len();
+        let idx = indices(&m, Material::Station);
+        assert!(gltf.contains(&format!(r#""count":{},"type":"VEC3""#, vertices)));
+        assert!(gltf.contains(&format!(r#""count":{},"type":"SCALAR""#, idx)));
+        assert!(gltf.contains(r#""indices":1,"material":2"#));
+        let len = vertices * 12 + idx * 4;
+        assert!(gltf.contains(&format!(r#""buffers":[{{"byteLength":{},"#, len)));
+        let data = gltf.split("base64,").nth(1).unwrap().split('"').next().unwrap();
+        assert_eq!(data.len(), len.div_ceil(3) * 4);
+        assert!(Mesh::default().write_gltf(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b""), "");
+    }
+}
@@ -0,0 +1,617 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use crate::sawyer::{Chunk, ChunkReader, ChunkWriter, Encoding};
+use crate::strings::{self, Language};
+use crate::util::{self, DSer, u16_from_slice};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ObjectType {
+    Ride,
+    SmallScenery,
+    LargeScenery,
+    Walls,
+    Banners,
+    Paths,
+    PathBits,
+    SceneryGroup,
+    ParkEntrance,
+    Water,
+    ScenarioText,
+    Unknown
+}
+
+impl From<u8> for ObjectType {
+    fn from(n: u8) -> Self {
+        use ObjectType::*;
+        match n {
+            0 => Ride,
+            1 => SmallScenery,
+            2 => LargeScenery,
+            3 => Walls,
+            4 => Banners,
+            5 => Paths,
+            6 => PathBits,
+            7 => SceneryGroup,
+            8 => ParkEntrance,
+            9 => Water,
+            10 => ScenarioText,
+            _ => Unknown
+        }
+    }
+}
+
+/// 16-byte object header used by DAT files and object lists in saves and tracks.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectEntry {
+    pub flags: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::util::name_bytes"))]
+    pub name: [u8; 8],
+    pub checksum: u32
+}
+
+impl util::DeSerializable for ObjectEntry {
+    fn from_dser<S>(&mut self, ds: &mut S) -> io::Result<()> where S: DSer {
+        self.flags = ds.read_u32()?;
+        let name = ds.read_bytes(8)?;
+        self.name.copy_from_slice(&name);
+        self.checksum = ds.read_u32()?;
+        Ok(())
+    }
+}
+
+impl ObjectEntry {
+    pub fn from_slice(b: &[u8]) -> Self {
+        let mut name = [0u8; 8];
+        name.copy_from_slice(&b[4..12]);
+        ObjectEntry{flags: util::u32_from_slice(b, 0), name, checksum: util::u32_from_slice(b, 12)}
+    }
+    pub fn object_type(&self) -> ObjectType {
+        ObjectType::from((self.flags & 0x0F) as u8)
+    }
+    /// Game the object originates from (upper nibble of the first flags byte).
+    pub fn source(&self) -> u8 {
+        ((self.flags & 0xF0) >> 4) as u8
+    }
+    /// Objects not shipped with any game have no source set.
+    pub fn is_custom(&self) -> bool {
+        self.source() == 0
+    }
+    /// Identifier with the trailing space padding removed.
+    pub fn name(&self) -> String {
+        self.name.iter().map(|&c| c as char).collect::<String>().trim_end().to_string()
+    }
+    /// Unused slots in object lists are filled with 0xFF.
+    pub fn is_empty(&self) -> bool {
+        self.flags == 0xFFFF_FFFF
+    }
+    /// Compares entries the way the game does: objects shipped with a game
+    /// only need the same type and identifier, custom ones must match exactly.
+    pub fn matches(&self, o: &ObjectEntry) -> bool {
+        if self.source() != 0 || o.source() != 0 {
+            self.object_type() == o.object_type() && self.name == o.name
+        } else {
+            self == o
+        }
+    }
+}
+
+/// Object file contents: the header and its decoded chunk.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DatObject {
+    pub entry: ObjectEntry,
+    pub encoding: Encoding,
+    #[cfg_attr(feature = "serde", serde(with = "crate::util::hex_bytes"))]
+    pub data: Vec<u8>
+}
+
+impl DatObject {
+    pub fn read<S: Read + Seek>(s: &mut S) -> io::Result<Self> {
+        let entry = s.read_dser::<ObjectEntry>()?;
+        let chunk = ChunkReader::new(&mut *s).read_chunk()?;
+        Ok(DatObject{entry, encoding: chunk.encoding(), data: chunk.into_data()})
+    }
+
+    /// Writes the object as a DAT file, encoding the chunk with its original encoding.
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(&self.entry.flags.to_le_bytes())?;
+        w.write_all(&self.entry.name)?;
+        w.write_all(&self.entry.checksum.to_le_bytes())?;
+        ChunkWriter::new(w).write_chunk(&Chunk::new(self.encoding, self.data.clone()))
+    }
+
+    /// File name the game uses for the object, e.g. `RCT1TRN.DAT`.
+    pub fn file_name(&self) -> String {
+        let name = self.entry.name().chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+            .collect::<String>();
+        format!("{}.DAT", name)
+    }
+
+    pub fn calculate_checksum(&self) -> u32 {
+        let mut c = (0xF369_A75Bu32 ^ (self.entry.flags & 0xFF)).rotate_left(11);
+        for &b in self.entry.name.iter().chain(self.data.iter()) {
+            c = (c ^ u32::from(b)).rotate_left(11);
+        }
+        c
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.calculate_checksum() == self.entry.checksum
+    }
+
+    pub fn ride_object(&self) -> Option<io::Result<RideObject>> {
+        match self.entry.object_type() {
+            ObjectType::Ride => Some(RideObject::from_data(&self.data)),
+            _ => None
+        }
+    }
+}
+
+/// Writes each object to `dir` as a standalone DAT file, returning the paths written.
+pub fn extract_objects<P: AsRef<Path>>(objects: &[DatObject], dir: P) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(&dir)?;
+    let mut paths = Vec::with_capacity(objects.len());
+    for o in objects {
+        let p = dir.as_ref().join(o.file_name());
+        let mut w = BufWriter::new(File::create(&p)?);
+        o.write(&mut w)?;
+        w.flush()?;
+        paths.push(p);
+    }
+    Ok(paths)
+}
+
+/// Object strings, stored as (language id, raw bytes) pairs in the game's encoding.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StringTable {
+    entries: Vec<(u8, Vec<u8>)>
+}
+
+impl StringTable {
+    pub fn read<S: DSer>(ds: &mut S) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        loop {
+            let lang = ds.read_u8()?;
+            if lang == 0xFF {
+                break;
+            }
+            let mut s = Vec::new();
+            loop {
+                let c = ds.read_u8()?;
+                if c == 0 {
+                    break;
+                }
+                s.push(c);
+            }
+            entries.push((lang, s));
+        }
+        Ok(StringTable{entries})
+    }
+    pub fn entries(&self) -> &[(u8, Vec<u8>)] {
+        &self.entries
+    }
+    pub fn get(&self, lang: Language) -> Option<String> {
+        self.entries.iter()
+            .find(|e| Language::from(e.0) == lang)
+            .map(|(_, s)| strings::decode(s, lang))
+    }
+    /// Every translation, keyed by language.
+    pub fn translations(&self) -> BTreeMap<Language, String> {
+        self.entries.iter()
+            .map(|(l, s)| (Language::from(*l), s))
+            .filter(|(l, _)| *l != Language::Unknown)
+            .map(|(l, s)| (l, strings::decode(s, l)))
+            .collect()
+    }
+    /// String in the given language, falling back to English (UK, then US) or the first one available.
+    pub fn localized(&self, lang: Language) -> String {
+        self.get(lang)
+            .or_else(|| self.get(Language::EnglishUK))
+            .or_else(|| self.get(Language::EnglishUS))
+            .or_else(|| self.entries.first().map(|(l, s)| strings::decode(s, Language::from(*l))))
+            .unwrap_or_default()
+    }
+    pub fn english(&self) -> String {
+        self.localized(Language::EnglishUK)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageEntry {
+    pub offset: u32,
+    pub width: i16,
+    pub height: i16,
+    pub x_offset: i16,
+    pub y_offset: i16,
+    pub flags: u16,
+    pub zoomed_offset: u16
+}
+
+impl util::DeSerializable for ImageEntry {
+    fn from_dser<S>(&mut self, ds: &mut S) -> io::Result<()> where S: DSer {
+        self.offset = ds.read_u32()?;
+        self.width = ds.read_u16()? as i16;
+        self.height = ds.read_u16()? as i16;
+        self.x_offset = ds.read_u16()? as i16;
+        self.y_offset = ds.read_u16()? as i16;
+        self.flags = ds.read_u16()?;
+        self.zoomed_offset = ds.read_u16()?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageTable {
+    pub entries: Vec<ImageEntry>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::util::hex_bytes"))]
+    pub data: Vec<u8>
+}
+
+impl ImageTable {
+    pub fn read<S: DSer>(ds: &mut S) -> io::Result<Self> {
+        let count = ds.read_u32()?;
+        let size = ds.read_u32()?;
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            entries.push(ds.read_dser::<ImageEntry>()?);
+        }
+        let data = ds.read_bytes(size as usize)?;
+        Ok(ImageTable{entries, data})
+    }
+
+    /// Palette-indexed pixels (row by row, 0 is transparent) of an image.
+    pub fn pixels(&self, idx: usize) -> Option<Vec<u8>> {
+        let e = self.entries.get(idx)?;
+        let (w, h) = (e.width.max(0) as usize, e.height.max(0) as usize);
+        let src = self.data.get(e.offset as usize..)?;
+        let mut px = vec![0u8; w * h];
+        if e.flags & 4 != 0 {
+            for y in 0..h {
+                let mut i = u16_from_slice(src.get(y*2..y*2+2)?, 0) as usize;
+                loop {
+                    let ln = *src.get(i)?;
+                    let x = *src.get(i+1)? as usize;
+                    let n = (ln & 0x7F) as usize;
+                    i += 2;
+                    px.get_mut(y*w+x..y*w+x+n)?.copy_from_slice(src.get(i..i+n)?);
+                    i += n;
+                    if ln & 0x80 != 0 {
+                        break;
+                    }
+                }
+            }
+        } else {
+            px.copy_from_slice(src.get(..w*h)?);
+        }
+        Some(px)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VehicleEntry {
+    pub rotation_frame_mask: u16,
+    pub spacing: u32,
+    pub car_mass: u16,
+    pub tab_height: i8,
+    pub num_seats: u8,
+    pub sprite_flags: u16,
+    pub sprite_width: u8,
+    pub sprite_height_negative: u8,
+    pub sprite_height_positive: u8,
+    pub animation: u8,
+    pub flags: u32,
+    pub base_num_frames: u16,
+    pub no_seating_rows: u8,
+    pub spinning_inertia: u8,
+    pub spinning_friction: u8,
+    pub friction_sound_id: u8,
+    pub log_flume_reverser_vehicle_type: u8,
+    pub sound_range: u8,
+    pub double_sound_frequency: u8,
+    pub powered_acceleration: u8,
+    pub powered_max_speed: u8,
+    pub car_visual: u8,
+    pub effect_visual: u8,
+    pub draw_order: u8,
+    pub num_vertical_frames_override: u8
+}
+
+impl util::DeSerializable for VehicleEntry {
+    fn from_dser<S>(&mut self, ds: &mut S) -> io::Result<()> where S: DSer {
+        self.rotation_frame_mask = ds.read_u16()?;
+        ds.read_bytes(2)?;
+        self.spacing = ds.read_u32()?;
+        self.car_mass = ds.read_u16()?;
+        self.tab_height = ds.read_i8()?;
+        self.num_seats = ds.read_u8()?;
+        self.sprite_flags = ds.read_u16()?;
+        self.sprite_width = ds.read_u8()?;
+        self.sprite_height_negative = ds.read_u8()?;
+        self.sprite_height_positive = ds.read_u8()?;
+        self.animation = ds.read_u8()?;
+        self.flags = ds.read_u32()?;
+        self.base_num_frames = ds.read_u16()?;
+        // Sprite image ids are assigned on load
+        ds.read_bytes(15 * 4)?;
+        self.no_seating_rows = ds.read_u8()?;
+        self.spinning_inertia = ds.read_u8()?;
+        self.spinning_friction = ds.read_u8()?;
+        self.friction_sound_id = ds.read_u8()?;
+        self.log_flume_reverser_vehicle_type = ds.read_u8()?;
+        self.sound_range = ds.read_u8()?;
+        self.double_sound_frequency = ds.read_u8()?;
+        self.powered_acceleration = ds.read_u8()?;
+        self.powered_max_speed = ds.read_u8()?;
+        self.car_visual = ds.read_u8()?;
+        self.effect_visual = ds.read_u8()?;
+        self.draw_order = ds.read_u8()?;
+        self.num_vertical_frames_override = ds.read_u8()?;
+        ds.read_bytes(4)?;
+        Ok(())
+    }
+}
+
+impl VehicleEntry {
+    pub const FLAG_LOADING_WAYPOINTS: u32 = 1 << 26;
+
+    /// Seat count without the "seats in pairs" bit.
+    pub fn seats(&self) -> u8 {
+        self.num_seats & 0x7F
+    }
+}
+
+/// Decoded ride object (RIDE type DAT).
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RideObject {
+    pub flags: u32,
+    pub ride_type: [u8; 3],
+    pub min_cars_in_train: u8,
+    pub max_cars_in_train: u8,
+    pub cars_per_flat_ride: u8,
+    pub zero_cars: u8,
+    pub tab_vehicle: u8,
+    pub default_vehicle: u8,
+    pub front_vehicle: u8,
+    pub second_vehicle: u8,
+    pub rear_vehicle: u8,
+    pub third_vehicle: u8,
+    pub vehicles: [VehicleEntry; 4],
+    pub excitement_multiplier: i8,
+    pub intensity_multiplier: i8,
+    pub nausea_multiplier: i8,
+    pub max_height: u8,
+    pub enabled_track_pieces: u64,
+    pub category: [u8; 2],
+    pub shop_item: [u8; 2],
+    pub name: StringTable,
+    pub description: StringTable,
+    pub capacity: StringTable,
+    pub preset_colours: Vec<[u8; 3]>,
+    pub peep_loading_positions: [Vec<u8>; 4],
+    pub images: ImageTable
+}
+
+impl RideObject {
+    pub const FLAG_NO_INVERSIONS: u32 = 1 << 1;
+    pub const FLAG_NO_BANKED_TRACK: u32 = 1 << 2;
+    pub const FLAG_COVERED_RIDE: u32 = 1 << 10;
+    pub const FLAG_SEPARATE_RIDE: u32 = 1 << 13;
+    pub const FLAG_CANNOT_BREAK_DOWN: u32 = 1 << 14;
+
+    pub fn from_data(b: &[u8]) -> io::Result<Self> {
+        let mut ds = Cursor::new(b);
+        // Name, description and image offset are assigned on load
+        ds.read_bytes(8)?;
+        let flags = ds.read_u32()?;
+        let mut ride_type = [0u8; 3];
+        ride_type.copy_from_slice(&ds.read_bytes(3)?);
+        let v = ds.read_bytes(11)?;
+        let vehicles = [
+            ds.read_dser::<VehicleEntry>()?,
+            ds.read_dser::<VehicleEntry>()?,
+            ds.read_dser::<VehicleEntry>()?,
+            ds.read_dser::<VehicleEntry>()?
+        ];
+        ds.read_bytes(4)?;
+        let excitement_multiplier = ds.read_i8()?;
+        let intensity_multiplier = ds.read_i8()?;
+        let nausea_multiplier = ds.read_i8()?;
+        let max_height = ds.read_u8()?;
+        let enabled_track_pieces = ds.read_u64()?;
+        let category = [ds.read_u8()?, ds.read_u8()?];
+        let shop_item = [ds.read_u8()?, ds.read_u8()?];
+
+        let name = StringTable::read(&mut ds)?;
+        let description = StringTable::read(&mut ds)?;
+        let capacity = StringTable::read(&mut ds)?;
+
+        // 255 means a different colour for each train, from the full list of 32
+        let colours = match ds.read_u8()? {
+            255 => 32,
+            n => n
+        };
+        let mut preset_colours = Vec::with_capacity(colours as usize);
+        for _ in 0..colours {
+            let c = ds.read_bytes(3)?;
+            preset_colours.push([c[0], c[1], c[2]]);
+        }
+
+        let mut peep_loading_positions: [Vec<u8>; 4] = Default::default();
+        for p in peep_loading_positions.iter_mut() {
+            let n = match ds.read_u8()? {
+                255 => ds.read_u16()?,
+                n => u16::from(n)
+            };
+            *p = ds.read_bytes(n as usize)?;
+        }
+
+        let images = ImageTable::read(&mut ds)?;
+
+        Ok(RideObject{
+            flags, ride_type,
+            min_cars_in_train: v[0],
+            max_cars_in_train: v[1],
+            cars_per_flat_ride: v[2],
+            zero_cars: v[3],
+            tab_vehicle: v[4],
+            default_vehicle: v[5],
+            front_vehicle: v[6],
+            second_vehicle: v[7],
+            rear_vehicle: v[8],
+            third_vehicle: v[9],
+            vehicles,
+            excitement_multiplier, intensity_multiplier, nausea_multiplier,
+            max_height, enabled_track_pieces, category, shop_item,
+            name, description, capacity,
+            preset_colours, peep_loading_positions, images
+        })
+    }
+
+    /// First ride type this object can be built as.
+    pub fn ride_type(&self) -> u8 {
+        self.ride_type.iter().copied().find(|&t| t != 0xFF).unwrap_or(0xFF)
+    }
+
+    pub fn default_vehicle(&self) -> &VehicleEntry {
+        &self.vehicles[(self.default_vehicle & 3) as usize]
+    }
+
+    /// Index of the preview image, which follows the first used ride type slot.
+    pub fn preview_index(&self) -> usize {
+        self.ride_type.iter().position(|&t| t != 0xFF).unwrap_or(0)
+    }
+
+    pub fn preview(&self) -> Option<(ImageEntry, Vec<u8>)> {
+        let i = self.preview_index();
+        let e = *self.images.entries.get(i)?;
+        Some((e, self.images.pixels(i)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn string_table(entries: &[(u8, &[u8])]) -> Vec<u8> {
+        let mut v = Vec::new();
+        for (lang, s) in entries {
+            v.push(*lang);
+            v.extend_from_slice(s);
+            v.push(0);
+        }
+        v.push(0xFF);
+        v
+    }
+
+    fn vehicle(mass: u16, seats: u8) -> Vec<u8> {
+        let mut v = vec![0u8; 101];
+        v[8..10].copy_from_slice(&mass.to_le_bytes());
+        v[11] = seats;
+        v
+    }
+
+    /// Wooden roller coaster cars: two trains' worth of vehicles, names in three languages
+    /// and a single 2x1 preview image.
+    fn ride_object_data() -> Vec<u8> {
+        let mut b = vec![0u8; 8];
+        b.extend_from_slice(&RideObject::FLAG_NO_INVERSIONS.to_le_bytes());
+        b.extend_from_slice(&[0xFF, 0x34, 0xFF]);
+        b.extend_from_slice(&[2, 6, 0xFF, 0, 0, 1, 0xFF, 0xFF, 0xFF, 0xFF, 0]);
+        b.extend(vehicle(1200, 4 | 0x80));
+        b.extend(vehicle(900, 2));
+        b.extend(vehicle(0, 0));
+        b.extend(vehicle(0, 0));
+        b.extend_from_slice(&[0; 4]);
+        b.extend_from_slice(&[10, (-5i8) as u8, 0, 30]);
+        b.extend_from_slice(&0x0Fu64.to_le_bytes());
+        b.extend_from_slice(&[2, 0xFF, 0xFF, 0xFF]);
+        b.extend(string_table(&[(0, b"Wooden Cars"), (2, b"Voitures en bois"), (8, &[0xFF, 0x96, 0xD8])]));
+        b.extend(string_table(&[(0, b"Classic wooden cars")]));
+        b.extend(string_table(&[(0, b"4 passengers per car")]));
+        b.extend_from_slice(&[1, 10, 20, 30]);
+        b.extend_from_slice(&[2, 1, 2, 0, 0, 0]);
+        b.extend_from_slice(&1u32.to_le_bytes());
+        b.extend_from_slice(&2u32.to_le_bytes());
+        b.extend_from_slice(&[0, 0, 0, 0, 2, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        b.extend_from_slice(&[7, 8]);
+        b
+    }
+
+    #[test]
+    fn decodes_ride_objects() {
+        let o = RideObject::from_data(&ride_object_data()).unwrap();
+        assert_eq!(o.flags, RideObject::FLAG_NO_INVERSIONS);
+        assert_eq!(o.ride_type(), 0x34);
+        assert_eq!(o.preview_index(), 1);
+        assert_eq!((o.min_cars_in_train, o.max_cars_in_train), (2, 6));
+        assert_eq!(o.default_vehicle().car_mass, 900);
+        assert_eq!(o.vehicles[0].seats(), 4);
+        assert_eq!(o.vehicles[0].num_seats, 0x84);
+        assert_eq!((o.excitement_multiplier, o.intensity_multiplier, o.nausea_multiplier), (10, -5, 0));
+        assert_eq!((o.max_height, o.enabled_track_pieces), (30, 0x0F));
+        assert_eq!(o.name.english(), "Wooden Cars");
+        assert_eq!(o.name.localized(Language::French), "Voitures en bois");
+        assert_eq!(o.name.localized(Language::German), "Wooden Cars");
+        assert_eq!(o.name.translations().len(), 3);
+        assert_eq!(o.capacity.english(), "4 passengers per car");
+        assert_eq!(o.preset_colours, [[10, 20, 30]]);
+        assert_eq!(o.peep_loading_positions[0], [1, 2]);
+        assert_eq!(o.images.entries.len(), 1);
+        assert!(o.preview().is_none());
+        assert_eq!(o.images.pixels(0), Some(vec![7, 8]));
+    }
+
+    #[test]
+    fn truncated_ride_objects() {
+        let b = ride_object_data();
+        for n in [0, 30, 450, b.len() - 1] {
+            assert!(RideObject::from_data(&b[..n]).is_err(), "{} bytes", n);
+        }
+    }
+
+    #[test]
+    fn dat_objects() {
+        let mut o = DatObject{
+            entry: ObjectEntry{flags: 0x0000_8000, name: *b"WOODCARS", checksum: 0},
+            encoding: Encoding::RLE,
+            data: ride_object_data()
+        };
+        assert!(!o.is_valid());
+        o.entry.checksum = o.calculate_checksum();
+        assert!(o.is_valid());
+        assert_eq!(o.file_name(), "WOODCARS.DAT");
+        let mut v = Vec::new();
+        o.write(&mut v).unwrap();
+        let r = DatObject::read(&mut Cursor::new(&v)).unwrap();
+        assert_eq!(r.entry, o.entry);
+        assert_eq!(r.ride_object().unwrap().unwrap().name.english(), "Wooden Cars");
+    }
+
+    #[test]
+    fn extracts_objects() {
+        let dir = std::env::temp_dir().join(format!("rct2read-test-extract-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let objects = [
+            DatObject{entry: ObjectEntry{flags: 0x0000_8000, name: *b"WOODCARS", checksum: 1}, encoding: Encoding::RLE, data: ride_object_data()},
+            DatObject{entry: ObjectEntry{flags: 0x0000_0089, name: *b"M/SIGN  ", checksum: 2}, encoding: Encoding::None, data: vec![1, 2, 3]}
+        ];
+        let paths = extract_objects(&objects, &dir).unwrap();
+        assert_eq!(paths, [dir.join("WOODCARS.DAT"), dir.join("M_SIGN.DAT")]);
+        for (p, o) in paths.iter().zip(&objects) {
+            let r = DatObject::read(&mut BufReader::new(File::open(p).unwrap())).unwrap();
+            assert_eq!((r.entry, r.encoding, &r.data), (o.entry, o.encoding, &o.data));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+use crate::util::{u16_from_slice, u32_from_slice};
+
+pub const SPRITE_SIZE: usize = 0x100;
+pub const MAX_SPRITES: usize = 10000;
+
+/// First byte of sprites holding a guest or staff member.
+const SPRITE_IDENTIFIER_PEEP: u8 = 1;
+const PEEP_TYPE_STAFF: u8 = 1;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PeepState {
+    Falling,
+    One,
+    QueuingFront,
+    OnRide,
+    LeavingRide,
+    Walking,
+    Queuing,
+    EnteringRide,
+    Sitting,
+    Picked,
+    Patrolling,
+    Mowing,
+    Sweeping,
+    EnteringPark,
+    LeavingPark,
+    Answering,
+    Fixing,
+    Buying,
+    Watching,
+    EmptyingBin,
+    UsingBin,
+    Watering,
+    HeadingToInspection,
+    Inspecting,
+    Unknown
+}
+
+impl From<u8> for PeepState {
+    fn from(n: u8) -> Self {
+        use PeepState::*;
+        match n {
+            0 => Falling,
+            1 => One,
+            2 => QueuingFront,
+            3 => OnRide,
+            4 => LeavingRide,
+            5 => Walking,
+            6 => Queuing,
+            7 => EnteringRide,
+            8 => Sitting,
+            9 => Picked,
+            10 => Patrolling,
+            11 => Mowing,
+            12 => Sweeping,
+            13 => EnteringPark,
+            14 => LeavingPark,
+            15 => Answering,
+            16 => Fixing,
+            17 => Buying,
+            18 => Watching,
+            19 => EmptyingBin,
+            20 => UsingBin,
+            21 => Watering,
+            22 => HeadingToInspection,
+            23 => Inspecting,
+            _ => Unknown
+        }
+    }
+}
+
+impl PeepState {
+    pub fn name(self) -> &'static str {
+        use PeepState::*;
+        match self {
+            Falling => "Falling",
+            One | Walking | Patrolling => "Walking",
+            QueuingFront | Queuing => "Queuing",
+            OnRide => "On ride",
+            LeavingRide => "Leaving ride",
+            EnteringRide => "Entering ride",
+            Sitting => "Sitting",
+            Picked => "Picked up",
+            Mowing => "Mowing grass",
+            Sweeping => "Sweeping",
+            EnteringPark => "Entering park",
+            LeavingPark => "Leaving park",
+            Answering => "Answering call",
+            Fixing => "Fixing ride",
+            Buying => "Buying",
+            Watching => "Watching",
+            EmptyingBin => "Emptying bin",
+            UsingBin => "Using bin",
+            Watering => "Watering gardens",
+            HeadingToInspection => "Heading to inspection",
+            Inspecting => "Inspecting ride",
+            Unknown => "Unknown"
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StaffType {
+    Handyman,
+    Mechanic,
+    Security,
+    Entertainer,
+    Unknown
+}
+
+impl From<u8> for StaffType {
+    fn from(n: u8) -> Self {
+        use StaffType::*;
+        match n {
+            0 => Handyman,
+            1 => Mechanic,
+            2 => Security,
+            3 => Entertainer,
+            _ => Unknown
+        }
+    }
+}
+
+impl StaffType {
+    pub fn name(self) -> &'static str {
+        match self {
+            StaffType::Handyman => "Handyman",
+            StaffType::Mechanic => "Mechanic",
+            StaffType::Security => "Security Guard",
+            StaffType::Entertainer => "Entertainer",
+            StaffType::Unknown => "Staff"
+        }
+    }
+}
+
+/// Guest sprite of a saved game.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Guest {
+    pub sprite_index: u16,
+    pub id: u32,
+    pub name: u16,
+    /// Position in the game's coordinates (32 per tile).
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+    pub outside_park: bool,
+    pub state: PeepState,
+    /// Needs and feelings, 0-255.
+    pub energy: u8,
+    pub happiness: u8,
+    pub nausea: u8,
+    pub hunger: u8,
+    pub thirst: u8,
+    pub toilet: u8,
+    /// Preferred intensity: maximum in the upper four bits, minimum in the lower ones.
+    pub intensity: u8,
+    pub nausea_tolerance: u8,
+    pub rides_been_on: u8,
+    pub current_ride: u8,
+    pub favourite_ride: u8,
+    pub cash_in_pocket: i32,
+    pub cash_spent: i32,
+    /// Park tick at which the guest entered.
+    pub park_entry_time: i32,
+    pub paid_to_enter: i16,
+    pub paid_on_rides: i16,
+    pub paid_on_food: i16,
+    pub paid_on_souvenirs: i16,
+    /// Items carried, one bit each.
+    pub items: u32
+}
+
+impl Guest {
+    /// Happiness in percent, as shown in the game.
+    pub fn happiness_percent(&self) -> u8 {
+        (u32::from(self.happiness) * 100 / 255) as u8
+    }
+}
+
+/// Staff member sprite of a saved game.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Staff {
+    pub sprite_index: u16,
+    pub id: u32,
+    pub name: u16,
+    pub staff_type: StaffType,
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+    pub state: PeepState,
+    pub energy: u8,
+    /// Duties the staff member has been told to carry out, one bit each.
+    pub orders: u8,
+    /// Park tick at which the staff member was hired.
+    pub hire_time: i32,
+    /// Handymen: lawns mown, gardens watered, litter swept and bins emptied.
+    /// Mechanics: rides fixed and inspected.
+    pub work_done: [u16; 4]
+}
+
+#[derive(Clone, Debug)]
+pub enum Peep {
+    Guest(Guest),
+    Staff(Staff)
+}
+
+impl Peep {
+    /// Parses a sprite, returning None for sprites which are not guests or staff.
+    pub fn from_slice(b: &[u8]) -> Option<Self> {
+        if b[0x00] != SPRITE_IDENTIFIER_PEEP {
+            return None;
+        }
+        let sprite_index = u16_from_slice(b, 0x0A);
+        let id = u32_from_slice(b, 0x9C);
+        let name = u16_from_slice(b, 0x22);
+        let (x, y, z) = (u16_from_slice(b, 0x0E) as i16, u16_from_slice(b, 0x10) as i16, u16_from_slice(b, 0x12) as i16);
+        let state = PeepState::from(b[0x2B]);
+        if b[0x2E] == PEEP_TYPE_STAFF {
+            return Some(Peep::Staff(Staff{
+                sprite_index, id, name, x, y, z, state,
+                staff_type: StaffType::from(b[0x2F]),
+                energy: b[0x38],
+                orders: b[0xC6],
+                hire_time: u32_from_slice(b, 0xA8) as i32,
+                work_done: [0, 1, 2, 3].map(|i| u16_from_slice(b, 0xE4 + i * 2))
+            }));
+        }
+        Some(Peep::Guest(Guest{
+            sprite_index, id, name, x, y, z, state,
+            outside_park: b[0x2A] != 0,
+            energy: b[0x38],
+            happiness: b[0x3A],
+            nausea: b[0x3C],
+            hunger: b[0x3E],
+            thirst: b[0x3F],
+            toilet: b[0x40],
+            intensity: b[0x43],
+            nausea_tolerance: b[0x44],
+            rides_been_on: b[0x2F],
+            current_ride: b[0x68],
+            favourite_ride: b[0xF9],
+            cash_in_pocket: u32_from_slice(b, 0xA0) as i32,
+            cash_spent: u32_from_slice(b, 0xA4) as i32,
+            park_entry_time: u32_from_slice(b, 0xA8) as i32,
+            paid_to_enter: u16_from_slice(b, 0xE4) as i16,
+            paid_on_rides: u16_from_slice(b, 0xE6) as i16,
+            paid_on_food: u16_from_slice(b, 0xE8) as i16,
+            paid_on_souvenirs: u16_from_slice(b, 0xEA) as i16,
+            items: u32_from_slice(b, 0xFC)
+        }))
+    }
+}
@@ -0,0 +1,464 @@
+//! Simple train physics along a track layout.
+//!
+//! Speeds are in the game's speed units (the integer part of its 16.16 velocities), which are
+//! close to metres per second. Distances are in metres, taking a coordinate unit as 0.13 m so
+//! that the two agree.
+
+use std::f32::consts::PI;
+use crate::ratings::RideStats;
+use crate::ride_type::{RideMode, RideType};
+use crate::td6::{TrackDesign, TrackElement};
+use crate::track::{self, Bank, Slope};
+
+pub const TICKS_PER_SECOND: f32 = 40.0;
+const DT: f32 = 1.0 / TICKS_PER_SECOND;
+const GRAVITY: f32 = 9.81;
+const METRES_PER_UNIT: f32 = 0.13;
+/// Ride length the game records per coordinate unit travelled.
+const LENGTH_PER_UNIT: f32 = 13962.0 / 65536.0;
+const ROLLING_RESISTANCE: f32 = 0.01;
+/// Air drag per metre, as a fraction of the speed squared.
+const DRAG: f32 = 0.0003;
+/// Speed stations push trains out at.
+const STATION_SPEED: f32 = 3.0;
+const BOOSTER_ACCELERATION: f32 = 8.0;
+const BRAKE_DECELERATION: f32 = 15.0;
+/// Speed open block brakes slow trains to.
+const BLOCK_BRAKE_SPEED: f32 = 4.0;
+const BANK_ANGLE: f32 = PI / 4.0;
+/// Runs longer than this are treated as never finishing.
+const MAX_TICKS: u32 = 40 * 60 * 10;
+
+const BRAKES: u8 = 0x63;
+const BOOSTER: u8 = 0x64;
+const BRAKE_FOR_DROP: u8 = 0x84;
+const BLOCK_BRAKES: u8 = 0xD8;
+
+/// Ride settings that affect the run.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Settings {
+    /// Speed trains are pulled up chain lifts at.
+    pub lift_speed: f32,
+    /// Speed trains leave the station at in powered launch modes.
+    pub launch_speed: Option<f32>,
+    /// Whether element 0x64 is a booster; spinning coasters use it to toggle rotation.
+    pub boosters: bool
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings{lift_speed: 2.4, launch_speed: None, boosters: true}
+    }
+}
+
+impl Settings {
+    pub fn from_track_design(td: &TrackDesign) -> Self {
+        let launch = matches!(RideMode::from(td.ride_mode), RideMode::PoweredLaunch | RideMode::PoweredLaunchPassthrough
+            | RideMode::PoweredLaunchBlockSectioned | RideMode::LimPoweredLaunch | RideMode::ReverseInclineLaunchedShuttle);
+        Settings{
+            // The game compares velocities against lift_hill_speed * 31079
+            lift_speed: f32::from(td.lift_hill_speed()) * 31079.0 / 65536.0,
+            launch_speed: if launch { Some(f32::from(td.operation_setting) * 30000.0 / 65536.0) } else { None },
+            boosters: RideType::from(td.ride_type) != RideType::SteelWildMouse
+        }
+    }
+}
+
+/// What happened to the train on one element.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementProfile {
+    pub index: usize,
+    pub kind: u8,
+    pub entry_speed: f32,
+    pub exit_speed: f32,
+    pub max_speed: f32,
+    /// Vertical G felt in the seat; negative values lift riders out of it.
+    pub max_vertical_g: f32,
+    pub min_vertical_g: f32,
+    pub max_lateral_g: f32,
+    /// Seconds.
+    pub time: f32,
+    pub air_time: f32
+}
+
+/// Result of running a train around a layout.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Simulation {
+    pub elements: Vec<ElementProfile>,
+    /// Whether the train reached the end of the last element.
+    pub completed: bool,
+    /// Element the train came to a stop on.
+    pub stalled_at: Option<usize>,
+    pub max_speed: f32,
+    pub average_speed: f32,
+    /// Metres, as the game measures ride length.
+    pub length: f32,
+    /// Seconds.
+    pub duration: f32,
+    pub max_positive_vertical_g: f32,
+    pub max_negative_vertical_g: f32,
+    pub max_lateral_g: f32,
+    /// Seconds.
+    pub air_time: f32
+}
+
+fn pitch(s: Slope) -> f32 {
+    match s {
+        Slope::Flat => 0.0,
+        Slope::Up25 => 0.5f32.atan(),
+        Slope::Up60 => 2f32.atan(),
+        Slope::Up90 => PI / 2.0,
+        Slope::Down25 => -(0.5f32.atan()),
+        Slope::Down60 => -(2f32.atan()),
+        Slope::Down90 => -PI / 2.0
+    }
+}
+
+fn heading(direction: u8) -> f32 {
+    let (x, y) = if direction & 4 != 0 { track::rotate(-1, -1, direction) } else { track::rotate(-1, 0, direction) };
+    (y as f32).atan2(x as f32)
+}
+
+fn is_banked(b: Bank) -> bool {
+    matches!(b, Bank::Left | Bank::Right)
+}
+
+/// Loop or roll, followed as a helix around the direction of travel.
+#[derive(Copy, Clone)]
+struct Loop {
+    /// Height of a full loop, in metres.
+    height: f32,
+    /// Radius of curvature of the helix.
+    radius: f32,
+    /// Angle around the loop at the start (0 at the bottom) and the angle covered.
+    start: f32,
+    sweep: f32
+}
+
+impl Loop {
+    fn height_at(&self, angle: f32) -> f32 {
+        self.height * (1.0 - angle.cos()) / 2.0
+    }
+}
+
+/// Shape of one element as the simulation sees it.
+struct Profile {
+    length: f32,
+    /// Heights at both ends, in metres.
+    z0: f32,
+    z1: f32,
+    pitch: (f32, f32),
+    /// Horizontal curvature (1/m) and bank angle.
+    turn: f32,
+    bank: f32,
+    loop_: Option<Loop>
+}
+
+impl Profile {
+    fn new(e: &TrackElement) -> Option<Self> {
+        let g = track::geometry(e.kind)?;
+        let z0 = f32::from(g.start_height) * METRES_PER_UNIT;
+        let z1 = f32::from(g.end_height) * METRES_PER_UNIT;
+        let peak = f32::from(g.blocks.iter().map(|b| b.z).max().unwrap_or(0)) * METRES_PER_UNIT;
+        let mut turn = heading(g.end_direction) - heading(g.start_direction);
+        while turn > PI {
+            turn -= 2.0 * PI;
+        }
+        while turn < -PI {
+            turn += 2.0 * PI;
+        }
+        let bank = match (is_banked(g.start_bank), is_banked(g.end_bank)) {
+            (true, true) => BANK_ANGLE,
+            (true, false) | (false, true) => BANK_ANGLE / 2.0,
+            _ => 0.0
+        };
+        let mut length = (f32::from(g.length) * METRES_PER_UNIT).max(0.5);
+        let mut loop_ = None;
+        // Inversions are treated as helices, which covers any change of direction too
+        if g.is_inversion() && peak > 0.0 {
+            turn = 0.0;
+            let (start, sweep) = match (g.start_bank, g.end_bank) {
+                (Bank::UpsideDown, _) => (PI, PI),
+                (_, Bank::UpsideDown) => (0.0, PI),
+                _ => (0.0, 2.0 * PI)
+            };
+            // Long enough to never climb faster than vertically
+            length = length.max(peak * sweep / 2.0);
+            let r = (peak / 2.0).max(1.0);
+            let advance = (length * length - (peak * sweep / 2.0).powi(2)).max(0.0).sqrt() / sweep;
+            loop_ = Some(Loop{height: peak, radius: (r * r + advance * advance) / r, start, sweep});
+        }
+        let run = (length * length - (z1 - z0).powi(2)).max(0.0).sqrt().max(0.5);
+        Some(Profile{length, z0, z1, pitch: (pitch(g.start_slope), pitch(g.end_slope)), turn: turn.abs() / run, bank, loop_})
+    }
+
+    /// Rate of climb at a fraction of the way along.
+    fn climb(&self, t: f32) -> f32 {
+        let dz = match self.loop_ {
+            // Around the loop, with any difference from the element's end heights spread evenly
+            Some(l) => {
+                let end = l.start + l.sweep;
+                l.height / 2.0 * (l.start + l.sweep * t).sin() * l.sweep
+                    + (self.z1 - l.height_at(end)) - (self.z0 - l.height_at(l.start))
+            },
+            None => self.z1 - self.z0
+        };
+        (dz / self.length).clamp(-1.0, 1.0)
+    }
+
+    /// Vertical and lateral G at a speed, a fraction of the way along.
+    fn gforces(&self, v: f32, t: f32) -> (f32, f32) {
+        let centripetal = v * v / GRAVITY;
+        let vertical = match self.loop_ {
+            Some(l) => centripetal / l.radius + (l.start + l.sweep * t).cos(),
+            None => {
+                let p = self.pitch.0 + (self.pitch.1 - self.pitch.0) * t;
+                p.cos() + centripetal * (self.pitch.1 - self.pitch.0) / self.length
+            }
+        };
+        let lateral = if self.turn > 0.0 {
+            (centripetal * self.turn * self.bank.cos() - self.bank.sin()).abs()
+        } else {
+            0.0
+        };
+        (vertical, lateral)
+    }
+}
+
+/// Runs a single train from the first element with the given settings.
+pub fn simulate_elements(elements: &[TrackElement], settings: &Settings) -> Simulation {
+    let mut sim = Simulation{completed: true, max_negative_vertical_g: f32::MAX, ..Default::default()};
+    let mut v = settings.launch_speed.unwrap_or(STATION_SPEED);
+    let mut ticks = 0u32;
+    let mut speed_sum = 0.0;
+    for (index, e) in elements.iter().enumerate() {
+        let p = match Profile::new(e) {
+            Some(p) => p,
+            None => continue
+        };
+        let g = track::geometry(e.kind).unwrap();
+        let mut ep = ElementProfile{index, kind: e.kind, entry_speed: v, min_vertical_g: f32::MAX, ..Default::default()};
+        let target = f32::from(e.extra()) * 2.0;
+        let mut s = 0.0;
+        while s < p.length {
+            let t = s / p.length;
+            let climb = p.climb(t);
+            let mut a = -GRAVITY * climb - ROLLING_RESISTANCE * GRAVITY - DRAG * v * v;
+            match e.kind {
+                BOOSTER if settings.boosters && v < target => a += BOOSTER_ACCELERATION,
+                BRAKES if v > target => a = -BRAKE_DECELERATION,
+                BLOCK_BRAKES | BRAKE_FOR_DROP if v > BLOCK_BRAKE_SPEED => a = -BRAKE_DECELERATION,
+                _ => ()
+            }
+            v += a * DT;
+            if g.is_station() {
+                v = v.max(settings.launch_speed.unwrap_or(STATION_SPEED));
+            } else if e.has_chain_lift() && climb >= 0.0 {
+                v = v.max(settings.lift_speed);
+            } else if matches!(e.kind, BLOCK_BRAKES | BRAKE_FOR_DROP) {
+                v = v.max(BLOCK_BRAKE_SPEED.min(STATION_SPEED));
+            }
+            if v <= 0.0 {
+                sim.completed = false;
+                sim.stalled_at = Some(index);
+                break;
+            }
+            let (vertical, lateral) = p.gforces(v, t);
+            ep.max_speed = ep.max_speed.max(v);
+            ep.max_vertical_g = ep.max_vertical_g.max(vertical);
+            ep.min_vertical_g = ep.min_vertical_g.min(vertical);
+            ep.max_lateral_g = ep.max_lateral_g.max(lateral);
+            ep.time += DT;
+            if vertical < 0.0 {
+                ep.air_time += DT;
+            }
+            speed_sum += v;
+            ticks += 1;
+            if ticks > MAX_TICKS {
+                sim.completed = false;
+                sim.stalled_at = Some(index);
+                break;
+            }
+            s += v * DT;
+        }
+        if ep.min_vertical_g == f32::MAX {
+            ep.min_vertical_g = ep.max_vertical_g;
+        }
+        ep.exit_speed = v;
+        sim.max_speed = sim.max_speed.max(ep.max_speed);
+        sim.max_positive_vertical_g = sim.max_positive_vertical_g.max(ep.max_vertical_g);
+        sim.max_negative_vertical_g = sim.max_negative_vertical_g.min(ep.min_vertical_g);
+        sim.max_lateral_g = sim.max_lateral_g.max(ep.max_lateral_g);
+        sim.air_time += ep.air_time;
+        sim.length += f32::from(g.length) * LENGTH_PER_UNIT;
+        sim.elements.push(ep);
+        if !sim.completed {
+            break;
+        }
+    }
+    if sim.elements.is_empty() {
+        sim.max_negative_vertical_g = 0.0;
+    }
+    sim.duration = ticks as f32 * DT;
+    if ticks > 0 {
+        sim.average_speed = speed_sum / ticks as f32;
+    }
+    sim
+}
+
+/// Runs a single train around a design using its stored lift and launch settings.
+pub fn simulate(td: &TrackDesign) -> Simulation {
+    simulate_elements(&td.elements, &Settings::from_track_design(td))
+}
+
+impl Simulation {
+    /// Stats in the form the ratings calculator expects, with inversions and drops counted from
+    /// the layout.
+    pub fn stats(&self, td: &TrackDesign) -> RideStats {
+        let mut inversions = 0u8;
+        let mut drops = 0u8;
+        let mut highest_drop = 0;
+        let mut drop_start = None;
+        for p in track::walk(&td.elements) {
+            let g = match track::geometry(p.kind) {
+                Some(g) => g,
+                None => continue
+            };
+            if g.is_inversion() && g.start_bank != Bank::UpsideDown {
+                inversions = inversions.saturating_add(1);
+            }
+            let descending = p.end.2 < p.z;
+            match (descending, drop_start) {
+                (true, None) => {
+                    drop_start = Some(p.z);
+                    drops = drops.saturating_add(1);
+                }
+                (false, Some(_)) => drop_start = None,
+                _ => ()
+            }
+            if let Some(z) = drop_start {
+                highest_drop = highest_drop.max((z - p.end.2) / 8);
+            }
+        }
+        RideStats{
+            ride_type: td.ride_type,
+            cars_per_train: td.cars_per_train,
+            max_speed: (self.max_speed * 65536.0) as i32,
+            average_speed: (self.average_speed * 65536.0) as i32,
+            length: self.length as i32,
+            duration: self.duration as u32,
+            max_positive_vertical_g: (self.max_positive_vertical_g * 100.0) as i32,
+            max_negative_vertical_g: (self.max_negative_vertical_g * 100.0) as i32,
+            max_lateral_g: (self.max_lateral_g * 100.0) as i32,
+            inversions: inversions.min(0x1F),
+            drops: drops.min(0x3F),
+            highest_drop_height: highest_drop.clamp(0, 255) as u8,
+            ..Default::default()
+        }
+    }
+
+    /// Air time in the units stored in track designs, four ticks each.
+    pub fn td6_air_time(&self) -> u8 {
+        (self.air_time * TICKS_PER_SECOND / 4.0).ceil().min(255.0) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn el(kind: u8, flags: u8) -> TrackElement {
+        TrackElement{kind, flags}
+    }
+
+    /// Station, a lift of `lift` 25 degree pieces, then a drop of the same height.
+    fn hill(lift: usize, chain: bool) -> Vec<TrackElement> {
+        let f = if chain { TrackElement::FLAG_CHAIN_LIFT } else { 0 };
+        let mut v = vec![el(0x02, 0), el(0x06, f)];
+        v.extend(std::iter::repeat_n(el(0x04, f), lift));
+        v.extend_from_slice(&[el(0x09, f), el(0x0C, 0)]);
+        v.extend(std::iter::repeat_n(el(0x0A, 0), lift));
+        v.extend_from_slice(&[el(0x0F, 0), el(0x00, 0), el(0x00, 0)]);
+        v
+    }
+
+    #[test]
+    fn flat_track_keeps_one_g() {
+        let sim = simulate_elements(&[el(0x02, 0), el(0x00, 0), el(0x00, 0)], &Settings::default());
+        assert!(sim.completed);
+        assert_eq!(sim.stalled_at, None);
+        assert_eq!(sim.elements.len(), 3);
+        assert!((sim.max_positive_vertical_g - 1.0).abs() < 1e-3, "{:?}", sim);
+        assert!((sim.max_negative_vertical_g - 1.0).abs() < 1e-3);
+        assert_eq!(sim.max_lateral_g, 0.0);
+        assert!(sim.elements[2].exit_speed < STATION_SPEED);
+    }
+
+    #[test]
+    fn stalls_without_a_lift() {
+        let sim = simulate_elements(&hill(6, false), &Settings::default());
+        assert!(!sim.completed);
+        let at = sim.stalled_at.unwrap();
+        assert!((1..=4).contains(&at), "{}", at);
+        assert_eq!(sim.elements.last().unwrap().index, at);
+    }
+
+    #[test]
+    fn lift_and_drop() {
+        let sim = simulate_elements(&hill(6, true), &Settings::default());
+        assert!(sim.completed);
+        assert_eq!(sim.elements.len(), hill(6, true).len());
+        // 112 units of climb, or about 14.6 m, let the train reach roughly sqrt(2 g h)
+        let top = sim.elements[8].exit_speed;
+        let bottom = sim.elements[16].exit_speed;
+        assert!(top < 3.0 && bottom > 15.5 && bottom < 17.1, "{} {}", top, bottom);
+        // Pulling out of the drop pushes riders into their seats
+        assert!(sim.elements[16].max_vertical_g > 1.2);
+        assert!(sim.max_speed >= bottom);
+        assert!(sim.length > 0.0 && sim.duration > 0.0);
+    }
+
+    #[test]
+    fn brakes_slow_trains() {
+        let mut track = hill(6, true);
+        track.extend_from_slice(&[el(BRAKES, 2); 4]);
+        let sim = simulate_elements(&track, &Settings::default());
+        let brakes = &sim.elements[sim.elements.len() - 4..];
+        assert!(brakes[0].entry_speed > 15.0);
+        assert!(brakes.windows(2).all(|w| w[1].exit_speed <= w[0].exit_speed));
+        // Brake speed 2 slows trains to 4 speed units, after which they only coast
+        assert!(brakes[3].exit_speed > 3.0 && brakes[3].exit_speed <= 4.0, "{:?}", brakes[3]);
+    }
+
+    #[test]
+    fn turns_pull_sideways() {
+        let sim = simulate_elements(&[el(0x02, 0), el(0x64, 12), el(0x64, 12), el(0x2A, 0)], &Settings::default());
+        assert!(sim.completed);
+        assert!(sim.elements[3].max_lateral_g > 0.5, "{:?}", sim.elements[3]);
+    }
+
+    #[test]
+    fn stats_count_drops() {
+        let mut v = vec![0u8; 0xA3];
+        v[0] = 0x0F;
+        v[0xA2] = 5;
+        for e in hill(6, true) {
+            v.extend_from_slice(&[e.kind, e.flags]);
+        }
+        v.extend_from_slice(&[0xFF, 0xFF, 0xFF]);
+        let td = TrackDesign::from_data(&v).unwrap();
+        let sim = simulate(&td);
+        let stats = sim.stats(&td);
+        assert_eq!((stats.drops, stats.highest_drop_height, stats.inversions), (1, 14, 0));
+        assert_eq!(stats.max_speed >> 16, sim.max_speed as i32);
+    }
+
+    #[test]
+    fn air_time_units() {
+        let sim = Simulation{air_time: 0.5, ..Default::default()};
+        assert_eq!(sim.td6_air_time(), 5);
+    }
+}
@@ -0,0 +1,825 @@
+use std::fmt;
+use crate::ride::{Ride, RATING_UNDEFINED};
+use crate::ride_type::RideType;
+use crate::td6::TrackDesign;
+
+/// Excitement, intensity and nausea in hundredths (the game's `ride_rating`).
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ratings {
+    pub excitement: i32,
+    pub intensity: i32,
+    pub nausea: i32
+}
+
+impl Ratings {
+    pub fn new(excitement: i32, intensity: i32, nausea: i32) -> Self {
+        Ratings{excitement, intensity, nausea}
+    }
+
+    fn add(&mut self, e: i32, i: i32, n: i32) {
+        self.excitement += e;
+        self.intensity += i;
+        self.nausea += n;
+    }
+
+    /// Adds `sub` scaled by 16.16 multipliers.
+    fn apply(&mut self, sub: Ratings, e: i32, i: i32, n: i32) {
+        self.add(mul(sub.excitement, e), mul(sub.intensity, i), mul(sub.nausea, n));
+    }
+
+    fn divide(&mut self, e: i32, i: i32, n: i32) {
+        self.excitement /= e;
+        self.intensity /= i;
+        self.nausea /= n;
+    }
+}
+
+/// Shows the ratings as the game does, as in `6.50/5.20/3.00`.
+impl fmt::Display for Ratings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.2}/{:.2}/{:.2}", f64::from(self.excitement) / 100.0, f64::from(self.intensity) / 100.0, f64::from(self.nausea) / 100.0)
+    }
+}
+
+fn mul(v: i32, m: i32) -> i32 {
+    ((i64::from(v) * i64::from(m)) >> 16) as i32
+}
+
+/// Measurements the rating formulas are based on, in the units of the S6 ride struct.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RideStats {
+    pub ride_type: u8,
+    pub cars_per_train: u8,
+    /// 16.16 fixed point speeds.
+    pub max_speed: i32,
+    pub average_speed: i32,
+    /// Metres.
+    pub length: i32,
+    /// Seconds.
+    pub duration: u32,
+    /// Hundredths of a G.
+    pub max_positive_vertical_g: i32,
+    pub max_negative_vertical_g: i32,
+    pub max_lateral_g: i32,
+    pub turn_count_default: u16,
+    pub turn_count_banked: u16,
+    pub turn_count_sloped: u16,
+    pub inversions: u8,
+    pub drops: u8,
+    pub highest_drop_height: u8,
+    pub sheltered_length: i32,
+    pub num_sheltered_sections: u8,
+    /// Scores measured by the game while testing; not stored in saves or tracks.
+    pub proximity: i32,
+    pub scenery: i32
+}
+
+impl RideStats {
+    pub fn from_ride(r: &Ride) -> Self {
+        RideStats{
+            ride_type: r.ride_type,
+            cars_per_train: r.num_cars_per_train,
+            max_speed: r.max_speed,
+            average_speed: r.average_speed,
+            length: r.total_length(),
+            duration: r.total_time(),
+            max_positive_vertical_g: i32::from(r.max_positive_vertical_g),
+            max_negative_vertical_g: i32::from(r.max_negative_vertical_g),
+            max_lateral_g: i32::from(r.max_lateral_g),
+            turn_count_default: r.turn_count_default,
+            turn_count_banked: r.turn_count_banked,
+            turn_count_sloped: r.turn_count_sloped,
+            inversions: r.inversions,
+            drops: r.drops,
+            highest_drop_height: r.highest_drop_height,
+            sheltered_length: r.sheltered_length,
+            num_sheltered_sections: r.num_sheltered_sections,
+            ..Default::default()
+        }
+    }
+
+    /// Stats saved in a track design. Tracks keep rounded values and no turn counts,
+    /// shelter or timing, so ratings computed from them are an estimate.
+    pub fn from_track_design(td: &TrackDesign) -> Self {
+        let max_speed = i32::from(td.max_speed) << 16;
+        let average_speed = i32::from(td.average_speed) << 16;
+        let length = i32::from(td.ride_length);
+        RideStats{
+            ride_type: td.ride_type,
+            cars_per_train: td.cars_per_train,
+            max_speed,
+            average_speed,
+            length,
+            // Speed units are 2^-16 of roughly 0.2 m/s
+            duration: if average_speed > 0 { (length * 5 / (average_speed >> 16).max(1)) as u32 } else { 0 },
+            max_positive_vertical_g: i32::from(td.max_positive_vertical_g) * 32,
+            max_negative_vertical_g: i32::from(td.max_negative_vertical_g) * 32,
+            max_lateral_g: i32::from(td.max_lateral_g) * 32,
+            inversions: td.inversions & 0x1F,
+            drops: td.drops & 0x3F,
+            highest_drop_height: td.highest_drop_height,
+            ..Default::default()
+        }
+    }
+
+    fn turns(n: u16) -> [i32; 4] {
+        [i32::from(n & 0x1F), i32::from((n >> 5) & 0x7), i32::from((n >> 8) & 0x7), i32::from(n >> 11)]
+    }
+}
+
+/// Minimums a ride has to reach, as in the game's rating requirements; each one missed halves
+/// the ratings. Drop and negative G requirements are waived for rides with inversions.
+struct Requirements {
+    drop_height: u8,
+    /// 16.16 fixed point.
+    max_speed: i32,
+    /// Highest allowed maximum negative G, in hundredths.
+    negative_g: i32,
+    /// Hundredths of a G.
+    lateral_g: i32,
+    drops: u8
+}
+
+const NO_REQUIREMENTS: Requirements = Requirements{drop_height: 0, max_speed: 0, negative_g: i32::MAX, lateral_g: 0, drops: 0};
+
+/// Multipliers of a ride type's rating formula, all 16.16 fixed point unless noted.
+struct Formula {
+    base: (i32, i32, i32),
+    /// Maximum counted length and its multiplier.
+    length: (i32, i32),
+    train_length: i32,
+    max_speed: (i32, i32, i32),
+    average_speed: (i32, i32),
+    /// Maximum counted duration and its multiplier.
+    duration: (i32, i32),
+    gforces: (i32, i32, i32),
+    turns: (i32, i32, i32),
+    drops: (i32, i32, i32),
+    sheltered: (i32, i32, i32),
+    proximity: i32,
+    scenery: i32,
+    requirements: Requirements
+}
+
+// Constants of the per-ride-type rating functions, as documented by OpenRCT2's RideRatings.cpp.
+// Ride types sharing most of a formula are written as changes to another type's.
+
+const LOOPING_ROLLER_COASTER: Formula = Formula{
+    base: (300, 50, 20),
+    length: (6000, 764),
+    train_length: 187_245,
+    max_speed: (44_281, 88_562, 35_424),
+    average_speed: (291_271, 436_906),
+    duration: (150, 26_214),
+    gforces: (24_576, 35_746, 49_648),
+    turns: (26_749, 34_767, 45_749),
+    drops: (29_127, 46_811, 49_152),
+    sheltered: (15_420, 32_768, 35_108),
+    proximity: 20_130,
+    scenery: 6_693,
+    requirements: Requirements{drop_height: 14, max_speed: 0xA_0000, negative_g: 10, lateral_g: 0, drops: 2}
+};
+
+const CORKSCREW_ROLLER_COASTER: Formula = Formula{
+    requirements: Requirements{drop_height: 12, max_speed: 0xA_0000, negative_g: 40, lateral_g: 0, drops: 2},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const TWISTER_ROLLER_COASTER: Formula = Formula{
+    base: (350, 40, 30),
+    gforces: (24_576, 32_768, 49_648),
+    ..CORKSCREW_ROLLER_COASTER
+};
+
+const SPIRAL_ROLLER_COASTER: Formula = Formula{
+    base: (350, 80, 45),
+    length: (6000, 819),
+    train_length: 140_434,
+    max_speed: (51_366, 85_019, 35_424),
+    average_speed: (400_497, 572_281),
+    gforces: (20_480, 23_831, 49_648),
+    ..CORKSCREW_ROLLER_COASTER
+};
+
+const STAND_UP_ROLLER_COASTER: Formula = Formula{
+    base: (250, 300, 300),
+    max_speed: (44_281, 123_987, 35_424),
+    gforces: (24_576, 35_746, 59_578),
+    drops: (34_952, 46_811, 49_152),
+    sheltered: (12_850, 28_398, 30_427),
+    proximity: 17_893,
+    scenery: 5_577,
+    requirements: Requirements{drop_height: 12, max_speed: 0xA_0000, negative_g: 50, lateral_g: 0, drops: 2},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const SUSPENDED_SWINGING_COASTER: Formula = Formula{
+    base: (330, 290, 350),
+    gforces: (24_576, 38_130, 49_648),
+    turns: (26_749, 34_767, 48_036),
+    requirements: Requirements{drop_height: 8, max_speed: 0x8_0000, negative_g: 60, lateral_g: 150, drops: 2},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const INVERTED_ROLLER_COASTER: Formula = Formula{
+    base: (360, 280, 320),
+    gforces: (24_576, 29_789, 55_606),
+    turns: (26_749, 29_552, 57_186),
+    drops: (29_127, 39_009, 49_152),
+    sheltered: (15_420, 15_291, 35_108),
+    proximity: 15_657,
+    scenery: 8_366,
+    requirements: Requirements{drop_height: 12, max_speed: 0xA_0000, negative_g: 30, lateral_g: 0, drops: 2},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const COMPACT_INVERTED_COASTER: Formula = Formula{
+    base: (315, 280, 320),
+    gforces: (24_576, 30_980, 55_606),
+    ..INVERTED_ROLLER_COASTER
+};
+
+const INVERTED_IMPULSE_COASTER: Formula = Formula{
+    base: (400, 300, 320),
+    scenery: 9_760,
+    requirements: Requirements{drop_height: 20, max_speed: 0xA_0000, ..NO_REQUIREMENTS},
+    ..INVERTED_ROLLER_COASTER
+};
+
+const JUNIOR_ROLLER_COASTER: Formula = Formula{
+    base: (240, 250, 180),
+    gforces: (20_480, 23_831, 49_648),
+    sheltered: (25_700, 30_583, 35_108),
+    scenery: 9_760,
+    requirements: Requirements{drop_height: 6, max_speed: 0x7_0000, negative_g: 50, lateral_g: 0, drops: 1},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const MINI_ROLLER_COASTER: Formula = Formula{
+    base: (255, 240, 185),
+    requirements: Requirements{drop_height: 12, max_speed: 0x7_0000, negative_g: 50, lateral_g: 0, drops: 2},
+    ..JUNIOR_ROLLER_COASTER
+};
+
+const WATER_COASTER: Formula = Formula{
+    base: (270, 280, 210),
+    requirements: Requirements{drop_height: 8, max_speed: 0x7_0000, negative_g: 50, lateral_g: 0, drops: 1},
+    ..JUNIOR_ROLLER_COASTER
+};
+
+const STEEPLECHASE: Formula = Formula{
+    base: (270, 240, 180),
+    gforces: (20_480, 20_852, 20_852),
+    requirements: Requirements{drop_height: 4, max_speed: 0x8_0000, negative_g: 50, lateral_g: 0, drops: 2},
+    ..JUNIOR_ROLLER_COASTER
+};
+
+const BOBSLEIGH_COASTER: Formula = Formula{
+    base: (280, 320, 250),
+    gforces: (65_536, 23_831, 49_648),
+    drops: (0, 0, 0),
+    scenery: 5_577,
+    requirements: Requirements{max_speed: 0xC_0000, lateral_g: 120, ..NO_REQUIREMENTS},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const MINI_SUSPENDED_COASTER: Formula = Formula{
+    base: (280, 230, 140),
+    turns: (34_241, 34_767, 45_749),
+    sheltered: (12_850, 20_852, 35_108),
+    proximity: 17_893,
+    scenery: 9_760,
+    requirements: Requirements{drop_height: 6, max_speed: 0x8_0000, lateral_g: 130, ..NO_REQUIREMENTS},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const MINE_TRAIN_COASTER: Formula = Formula{
+    base: (290, 230, 210),
+    gforces: (40_960, 35_746, 49_648),
+    turns: (29_721, 34_767, 45_749),
+    sheltered: (19_275, 32_768, 35_108),
+    proximity: 21_472,
+    scenery: 16_732,
+    requirements: Requirements{drop_height: 8, max_speed: 0x7_0000, negative_g: 10, lateral_g: 0, drops: 2},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const MINE_RIDE: Formula = Formula{
+    base: (275, 100, 180),
+    train_length: 40_960,
+    gforces: (40_960, 29_789, 49_648),
+    requirements: Requirements{max_speed: 0x7_0000, ..NO_REQUIREMENTS},
+    ..MINE_TRAIN_COASTER
+};
+
+const WOODEN_ROLLER_COASTER: Formula = Formula{
+    base: (320, 260, 200),
+    length: (6000, 873),
+    average_speed: (364_088, 655_360),
+    gforces: (40_960, 34_555, 49_648),
+    turns: (26_749, 43_458, 45_749),
+    drops: (40_777, 46_811, 49_152),
+    sheltered: (16_705, 30_583, 35_108),
+    proximity: 22_367,
+    scenery: 11_155,
+    requirements: Requirements{drop_height: 12, max_speed: 0xA_0000, negative_g: 10, lateral_g: 0, drops: 2},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const SIDE_FRICTION_ROLLER_COASTER: Formula = Formula{
+    base: (250, 200, 150),
+    requirements: Requirements{drop_height: 6, max_speed: 0x5_0000, drops: 2, ..NO_REQUIREMENTS},
+    ..WOODEN_ROLLER_COASTER
+};
+
+const REVERSER_ROLLER_COASTER: Formula = Formula{
+    base: (240, 180, 170),
+    gforces: (28_672, 23_831, 49_648),
+    requirements: Requirements{drop_height: 10, max_speed: 0x7_0000, drops: 2, ..NO_REQUIREMENTS},
+    ..WOODEN_ROLLER_COASTER
+};
+
+const VIRGINIA_REEL: Formula = Formula{
+    base: (210, 190, 310),
+    train_length: 0,
+    gforces: (110_592, 29_789, 59_578),
+    turns: (52_012, 26_075, 45_749),
+    drops: (43_690, 46_811, 49_152),
+    requirements: Requirements{drop_height: 12, drops: 2, ..NO_REQUIREMENTS},
+    ..WOODEN_ROLLER_COASTER
+};
+
+const WOODEN_WILD_MOUSE: Formula = Formula{
+    base: (290, 290, 210),
+    gforces: (102_400, 35_746, 49_648),
+    turns: (29_721, 43_458, 45_749),
+    proximity: 17_893,
+    scenery: 5_577,
+    requirements: Requirements{drop_height: 8, max_speed: 0x7_0000, negative_g: 10, lateral_g: 150, drops: 3},
+    ..WOODEN_ROLLER_COASTER
+};
+
+const STEEL_WILD_MOUSE: Formula = Formula{
+    base: (280, 250, 210),
+    requirements: Requirements{drop_height: 6, max_speed: 0x7_0000, negative_g: 10, lateral_g: 150, drops: 2},
+    ..WOODEN_WILD_MOUSE
+};
+
+const INVERTED_HAIRPIN_COASTER: Formula = Formula{
+    base: (300, 265, 225),
+    ..WOODEN_WILD_MOUSE
+};
+
+const FLYING_ROLLER_COASTER: Formula = Formula{
+    base: (435, 185, 433),
+    gforces: (24_576, 38_130, 49_648),
+    requirements: Requirements{drop_height: 16, max_speed: 0xA_0000, negative_g: 40, lateral_g: 0, drops: 2},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const LAY_DOWN_ROLLER_COASTER: Formula = Formula{
+    base: (385, 115, 275),
+    ..CORKSCREW_ROLLER_COASTER
+};
+
+const MULTI_DIMENSION_COASTER: Formula = Formula{
+    base: (375, 195, 479),
+    requirements: Requirements{drop_height: 16, max_speed: 0xA_0000, ..NO_REQUIREMENTS},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const LIM_LAUNCHED_ROLLER_COASTER: Formula = Formula{
+    base: (290, 150, 220),
+    requirements: Requirements{drop_height: 10, max_speed: 0xA_0000, negative_g: 10, lateral_g: 0, drops: 2},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const HEARTLINE_TWISTER_COASTER: Formula = Formula{
+    base: (140, 170, 165),
+    requirements: NO_REQUIREMENTS,
+    ..LOOPING_ROLLER_COASTER
+};
+
+const GIGA_COASTER: Formula = Formula{
+    base: (385, 40, 35),
+    gforces: (36_864, 30_384, 49_648),
+    turns: (28_235, 34_767, 45_749),
+    drops: (43_690, 46_811, 49_152),
+    requirements: Requirements{drop_height: 16, max_speed: 0xC_0000, negative_g: 40, lateral_g: 0, drops: 2},
+    ..SPIRAL_ROLLER_COASTER
+};
+
+const VERTICAL_DROP_COASTER: Formula = Formula{
+    base: (320, 80, 30),
+    gforces: (40_960, 23_831, 49_648),
+    turns: (26_749, 34_767, 45_749),
+    drops: (58_254, 46_811, 49_152),
+    requirements: Requirements{drop_height: 20, max_speed: 0xA_0000, negative_g: 10, lateral_g: 0, drops: 1},
+    ..GIGA_COASTER
+};
+
+const DINGHY_SLIDE: Formula = Formula{
+    base: (270, 300, 230),
+    length: (2000, 7_208),
+    gforces: (65_536, 29_789, 49_648),
+    proximity: 11_183,
+    scenery: 5_577,
+    requirements: Requirements{drop_height: 12, max_speed: 0x7_0000, drops: 2, ..NO_REQUIREMENTS},
+    ..LOOPING_ROLLER_COASTER
+};
+
+const CAR_RIDE: Formula = Formula{
+    base: (200, 50, 0),
+    gforces: (0, 0, 0),
+    turns: (14_860, 0, 11_437),
+    drops: (8_738, 0, 0),
+    sheltered: (12_850, 6_553, 4_681),
+    proximity: 11_183,
+    scenery: 8_366,
+    requirements: NO_REQUIREMENTS,
+    ..LOOPING_ROLLER_COASTER
+};
+
+const GHOST_TRAIN: Formula = Formula{
+    base: (200, 20, 3),
+    sheltered: (25_700, 6_553, 4_681),
+    ..CAR_RIDE
+};
+
+const GO_KARTS: Formula = Formula{
+    base: (142, 173, 40),
+    train_length: 0,
+    average_speed: (0, 0),
+    turns: (4_458, 3_476, 5_718),
+    drops: (8_738, 5_461, 6_553),
+    sheltered: (2_570, 8_738, 2_340),
+    proximity: 4_130,
+    scenery: 4_183,
+    ..CAR_RIDE
+};
+
+const MINI_HELICOPTERS: Formula = Formula{
+    base: (160, 40, 30),
+    turns: (14_860, 0, 4_574),
+    sheltered: (5_140, 6_553, 18_724),
+    proximity: 8_946,
+    ..CAR_RIDE
+};
+
+const MONORAIL_CYCLES: Formula = Formula{
+    base: (140, 20, 0),
+    turns: (14_860, 0, 4_574),
+    sheltered: (5_140, 6_553, 2_340),
+    proximity: 8_946,
+    scenery: 11_155,
+    ..CAR_RIDE
+};
+
+const SUBMARINE_RIDE: Formula = Formula{
+    base: (220, 180, 140),
+    turns: (14_860, 0, 4_574),
+    sheltered: (0, 0, 0),
+    scenery: 22_310,
+    ..CAR_RIDE
+};
+
+const LOG_FLUME: Formula = Formula{
+    base: (150, 55, 30),
+    length: (2000, 7_208),
+    train_length: 0,
+    max_speed: (531_372, 655_360, 301_111),
+    average_speed: (0, 0),
+    duration: (300, 13_107),
+    gforces: (0, 0, 0),
+    turns: (22_291, 20_860, 4_574),
+    drops: (69_905, 62_415, 49_152),
+    sheltered: (16_705, 30_583, 35_108),
+    proximity: 22_367,
+    scenery: 11_155,
+    requirements: NO_REQUIREMENTS
+};
+
+const RIVER_RAPIDS: Formula = Formula{
+    base: (120, 70, 50),
+    length: (2000, 6_225),
+    max_speed: (115_130, 159_411, 106_274),
+    duration: (500, 13_107),
+    turns: (29_721, 22_598, 5_718),
+    drops: (40_777, 46_811, 49_152),
+    proximity: 31_314,
+    scenery: 13_943,
+    ..LOG_FLUME
+};
+
+const SPLASH_BOATS: Formula = Formula{
+    base: (146, 35, 30),
+    drops: (87_381, 93_622, 62_259),
+    ..LOG_FLUME
+};
+
+const RIVER_RAFTS: Formula = Formula{
+    base: (145, 25, 34),
+    duration: (500, 13_107),
+    drops: (78_643, 93_622, 62_259),
+    proximity: 13_943,
+    ..LOG_FLUME
+};
+
+const MINIATURE_RAILWAY: Formula = Formula{
+    base: (250, 0, 0),
+    length: (6000, 764),
+    train_length: 140_434,
+    max_speed: (44_281, 88_562, 35_424),
+    average_speed: (0, 0),
+    duration: (150, 26_214),
+    gforces: (0, 0, 0),
+    turns: (14_860, 0, 11_437),
+    drops: (8_738, 0, 0),
+    sheltered: (7_281, 0, 0),
+    proximity: 8_946,
+    scenery: 20_915,
+    requirements: NO_REQUIREMENTS
+};
+
+const MONORAIL: Formula = Formula{
+    base: (200, 0, 0),
+    train_length: 93_622,
+    max_speed: (44_281, 70_849, 35_424),
+    average_speed: (291_271, 218_453),
+    duration: (150, 21_845),
+    turns: (5_140, 6_553, 18_724),
+    drops: (8_738, 5_461, 6_553),
+    sheltered: (5_698, 5_461, 6_553),
+    proximity: 12_525,
+    scenery: 25_098,
+    ..MINIATURE_RAILWAY
+};
+
+const SUSPENDED_MONORAIL: Formula = Formula{
+    base: (215, 23, 8),
+    turns: (14_860, 0, 4_574),
+    sheltered: (12_850, 6_553, 4_681),
+    proximity: 25_098,
+    ..MONORAIL
+};
+
+const CHAIRLIFT: Formula = Formula{
+    base: (160, 40, 50),
+    train_length: 0,
+    turns: (7_430, 3_476, 4_574),
+    drops: (0, 0, 0),
+    sheltered: (0, 0, 0),
+    proximity: 25_098,
+    scenery: 25_098,
+    ..MINIATURE_RAILWAY
+};
+
+/// Rating formula of track-based ride types. Flat rides, shops and facilities have none, nor
+/// do the launched and freefall rides the game rates from their launch speed or height.
+fn formula(ride_type: u8) -> Option<&'static Formula> {
+    use RideType::*;
+    Some(match RideType::from(ride_type) {
+        SpiralRollerCoaster => &SPIRAL_ROLLER_COASTER,
+        StandUpRollerCoaster => &STAND_UP_ROLLER_COASTER,
+        SuspendedSwingingCoaster => &SUSPENDED_SWINGING_COASTER,
+        InvertedRollerCoaster => &INVERTED_ROLLER_COASTER,
+        JuniorRollerCoaster => &JUNIOR_ROLLER_COASTER,
+        MiniatureRailway => &MINIATURE_RAILWAY,
+        Monorail => &MONORAIL,
+        MiniSuspendedCoaster => &MINI_SUSPENDED_COASTER,
+        WoodenWildMouse => &WOODEN_WILD_MOUSE,
+        SteeplechaseCoaster => &STEEPLECHASE,
+        CarRide => &CAR_RIDE,
+        BobsleighCoaster => &BOBSLEIGH_COASTER,
+        LoopingRollerCoaster => &LOOPING_ROLLER_COASTER,
+        DinghySlide => &DINGHY_SLIDE,
+        MineTrainCoaster => &MINE_TRAIN_COASTER,
+        Chairlift => &CHAIRLIFT,
+        CorkscrewRollerCoaster => &CORKSCREW_ROLLER_COASTER,
+        GoKarts => &GO_KARTS,
+        LogFlume => &LOG_FLUME,
+        RiverRapids => &RIVER_RAPIDS,
+        GhostTrain => &GHOST_TRAIN,
+        TwisterRollerCoaster => &TWISTER_ROLLER_COASTER,
+        WoodenRollerCoaster => &WOODEN_ROLLER_COASTER,
+        SideFrictionRollerCoaster => &SIDE_FRICTION_ROLLER_COASTER,
+        SteelWildMouse => &STEEL_WILD_MOUSE,
+        MultiDimensionCoaster => &MULTI_DIMENSION_COASTER,
+        FlyingRollerCoaster => &FLYING_ROLLER_COASTER,
+        VirginiaReel => &VIRGINIA_REEL,
+        SplashBoats => &SPLASH_BOATS,
+        MiniHelicopters => &MINI_HELICOPTERS,
+        LayDownRollerCoaster => &LAY_DOWN_ROLLER_COASTER,
+        SuspendedMonorail => &SUSPENDED_MONORAIL,
+        ReverserRollerCoaster => &REVERSER_ROLLER_COASTER,
+        HeartlineTwisterCoaster => &HEARTLINE_TWISTER_COASTER,
+        GigaCoaster => &GIGA_COASTER,
+        MonorailCycles => &MONORAIL_CYCLES,
+        CompactInvertedCoaster => &COMPACT_INVERTED_COASTER,
+        WaterCoaster => &WATER_COASTER,
+        InvertedHairpinCoaster => &INVERTED_HAIRPIN_COASTER,
+        SubmarineRide => &SUBMARINE_RIDE,
+        RiverRafts => &RIVER_RAFTS,
+        InvertedImpulseCoaster => &INVERTED_IMPULSE_COASTER,
+        MiniRollerCoaster => &MINI_ROLLER_COASTER,
+        MineRide => &MINE_RIDE,
+        LimLaunchedRollerCoaster => &LIM_LAUNCHED_ROLLER_COASTER,
+        VerticalDropCoaster => &VERTICAL_DROP_COASTER,
+        _ => return None
+    })
+}
+
+fn gforce_ratings(s: &RideStats) -> Ratings {
+    let mut r = Ratings::default();
+    let pos = s.max_positive_vertical_g;
+    r.add(mul(pos, 5_242), mul(pos, 52_428), mul(pos, 17_039));
+    let neg = s.max_negative_vertical_g;
+    r.add(mul(neg.clamp(-250, 0), -15_728), mul(neg - 100, -52_428), mul(neg - 100, -14_563));
+    let lat = s.max_lateral_g;
+    r.add(mul(lat.min(150), 26_214), lat, mul(lat, 21_845));
+    if lat > 280 {
+        r.add(0, 375, 200);
+    }
+    if lat > 310 {
+        r.excitement /= 2;
+        r.add(0, 850, 400);
+    }
+    r
+}
+
+fn turns_ratings(s: &RideStats) -> Ratings {
+    let mut r = Ratings::default();
+    let [f1, f2, f3, f4] = RideStats::turns(s.turn_count_default);
+    let f3 = f3 + f4;
+    r.add(mul(f3, 0x2_8000) + mul(f2, 0x3_0000) + mul(f1, 63_421),
+        mul(f3, 81_920) + mul(f2, 49_152) + mul(f1, 21_140),
+        mul(f3, 49_152) + mul(f2, 32_768) + mul(f1, 42_281));
+    let [b1, b2, b3, b4] = RideStats::turns(s.turn_count_banked);
+    let b3 = b3 + b4;
+    r.add(mul(b3, 0x3_C000) + mul(b2, 0x3_C000) + mul(b1, 73_992),
+        mul(b3, 0x1_4000) + mul(b2, 49_152) + mul(b1, 8_456),
+        mul(b3, 0x1_4000) + mul(b2, 49_152) + mul(b1, 16_912));
+    let [s1, s2, s3, s4] = RideStats::turns(s.turn_count_sloped);
+    r.add(mul(s4.min(4), 0x7_8000) + mul(s3.min(6), 273_066) + mul(s2.min(6), 3_640) + mul(s1.min(7), 61_166), 0, 0);
+    let inv = i32::from(s.inversions);
+    r.add(mul(inv.min(6), 0x1A_AAAA), mul(inv, 0x32_0000), mul(inv, 0x15_AAAA));
+    r
+}
+
+fn drop_ratings(s: &RideStats) -> Ratings {
+    let drops = i32::from(s.drops);
+    let mut r = Ratings::new(mul(drops.min(9), 728_006), mul(drops.min(9), 928_389), mul(drops, 655_360));
+    let h = i32::from(s.highest_drop_height) * 2;
+    r.add(mul(h, 16_000), mul(h, 32_000), mul(h, 10_240));
+    r
+}
+
+fn sheltered_ratings(s: &RideStats) -> Ratings {
+    let len = s.sheltered_length >> 16;
+    let mut r = Ratings::new(mul(len.min(1000), 9_175), mul(len.min(2000), 0x2666), mul(len.min(1000), 0x4000));
+    if s.num_sheltered_sections & 0x40 != 0 {
+        r.add(20, 15, 20);
+    }
+    r.excitement += mul(i32::from(s.num_sheltered_sections & 0x1F).min(11), 774_516);
+    r
+}
+
+/// Computes ratings from measured stats, following the game's per-ride-type formulas.
+/// Returns None for ride types not rated from their track.
+pub fn calculate(s: &RideStats) -> Option<Ratings> {
+    let f = formula(s.ride_type)?;
+    let mut r = Ratings::new(f.base.0, f.base.1, f.base.2);
+    r.excitement += mul(s.length.min(f.length.0), f.length.1);
+    r.excitement += mul(i32::from(s.cars_per_train.max(1)) - 1, f.train_length);
+    let speed = s.max_speed >> 16;
+    r.add(mul(speed, f.max_speed.0), mul(speed, f.max_speed.1), mul(speed, f.max_speed.2));
+    let avg = s.average_speed >> 16;
+    r.add(mul(avg, f.average_speed.0), mul(avg, f.average_speed.1), 0);
+    r.excitement += mul((s.duration as i32).min(f.duration.0), f.duration.1);
+    r.apply(gforce_ratings(s), f.gforces.0, f.gforces.1, f.gforces.2);
+    r.apply(turns_ratings(s), f.turns.0, f.turns.1, f.turns.2);
+    r.apply(drop_ratings(s), f.drops.0, f.drops.1, f.drops.2);
+    r.apply(sheltered_ratings(s), f.sheltered.0, f.sheltered.1, f.sheltered.2);
+    r.excitement += mul(s.proximity, f.proximity) + mul(s.scenery, f.scenery);
+    let q = &f.requirements;
+    if s.inversions == 0 {
+        if s.highest_drop_height < q.drop_height {
+            r.divide(2, 2, 2);
+        }
+        if s.max_negative_vertical_g >= q.negative_g {
+            r.divide(2, 2, 2);
+        }
+        if s.drops < q.drops {
+            r.divide(2, 2, 2);
+        }
+    }
+    if s.max_speed < q.max_speed {
+        r.divide(2, 2, 2);
+    }
+    if s.max_lateral_g < q.lateral_g {
+        r.divide(2, 2, 2);
+    }
+    // Very intense rides lose excitement
+    for &bound in &[1000, 1100, 1200, 1320, 1450] {
+        if r.intensity >= bound {
+            r.excitement -= r.excitement >> 3;
+        }
+    }
+    Some(Ratings::new(r.excitement.max(0), r.intensity.max(0), r.nausea.max(0)))
+}
+
+/// Whether stored ratings are impossible or too far from the computed ones to come from the
+/// game. Computed ratings lack proximity and scenery bonuses, so the tolerance is wide.
+pub fn looks_corrupted(stored: Ratings, computed: Ratings) -> bool {
+    let invalid = |v: i32| !(0..=0x7FFF).contains(&v);
+    if invalid(stored.excitement) || invalid(stored.intensity) || invalid(stored.nausea) {
+        return true;
+    }
+    let far = |s: i32, c: i32| (s - c).abs() > 300.max(c / 2);
+    far(stored.excitement, computed.excitement) || far(stored.intensity, computed.intensity) || far(stored.nausea, computed.nausea)
+}
+
+/// Stored ratings of a saved ride, or None if it has not been tested.
+pub fn stored(r: &Ride) -> Option<Ratings> {
+    if r.excitement == RATING_UNDEFINED {
+        return None;
+    }
+    Some(Ratings::new(i32::from(r.excitement as i16), i32::from(r.intensity as i16), i32::from(r.nausea as i16)))
+}
+
+/// Stored ratings of a track design, which keeps them in tenths.
+pub fn stored_td6(td: &TrackDesign) -> Ratings {
+    Ratings::new(i32::from(td.excitement) * 10, i32::from(td.intensity) * 10, i32::from(td.nausea) * 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A wooden roller coaster that meets all of its requirements.
+    fn wooden_coaster() -> RideStats {
+        RideStats{
+            ride_type: 0x34,
+            cars_per_train: 6,
+            max_speed: 60 << 16,
+            average_speed: 25 << 16,
+            length: 900,
+            duration: 80,
+            max_positive_vertical_g: 350,
+            max_negative_vertical_g: -50,
+            max_lateral_g: 180,
+            turn_count_default: (1 << 5) | 2,
+            turn_count_banked: 2 << 5,
+            turn_count_sloped: 3,
+            drops: 6,
+            highest_drop_height: 20,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rates_wooden_coaster() {
+        assert_eq!(calculate(&wooden_coaster()), Some(Ratings::new(665, 968, 419)));
+    }
+
+    #[test]
+    fn missed_requirements_halve_ratings() {
+        // Base (300, 50, 20) plus the G-force term for no negative G, halved for the drop
+        // height, the number of drops and the speed
+        let mut s = RideStats{ride_type: 0x0F, ..Default::default()};
+        assert_eq!(calculate(&s), Some(Ratings::new(37, 11, 4)));
+        // Inversions waive the drop requirements
+        s.inversions = 1;
+        assert_eq!(calculate(&s), Some(Ratings::new(155, 59, 25)));
+    }
+
+    #[test]
+    fn intense_rides_lose_excitement() {
+        // 6.73 excitement before losing an eighth for passing 10.00 intensity
+        let s = RideStats{max_positive_vertical_g: 500, ..wooden_coaster()};
+        assert_eq!(calculate(&s), Some(Ratings::new(589, 1032, 449)));
+    }
+
+    #[test]
+    fn unrated_ride_types() {
+        assert_eq!(calculate(&RideStats{ride_type: 0x1C, ..Default::default()}), None);
+    }
+
+    #[test]
+    fn corrupted_ratings() {
+        let computed = Ratings::new(665, 968, 419);
+        assert!(!looks_corrupted(Ratings::new(700, 900, 400), computed));
+        assert!(looks_corrupted(Ratings::new(2000, 968, 419), computed));
+        assert!(looks_corrupted(Ratings::new(-1, 968, 419), computed));
+    }
+
+    #[test]
+    fn displays_ratings() {
+        assert_eq!(Ratings::new(650, 520, 300).to_string(), "6.50/5.20/3.00");
+        assert_eq!(Ratings::new(0, 5, 12_345).to_string(), "0.00/0.05/123.45");
+        assert_eq!(Ratings::new(-1, -150, 1).to_string(), "-0.01/-1.50/0.01");
+        assert_eq!(Ratings::default().to_string(), "0.00/0.00/0.00");
+    }
+}
@@ -1,142 +1,175 @@
 use std::fs::File;
-use std::io::Read;
-use crate::rctrle;
-use crate::util::{u16_from_slice, u32_from_slice};
+use std::io::{self, Read, Seek, SeekFrom};
+use crate::object::{DatObject, ObjectEntry, RideObject};
+use crate::repository::ObjectRepository;
+use crate::ride_type::{RideMode, RideType};
+use crate::s6::{self, SavedGame};
+use crate::sawyer::ChunkReader;
+use crate::td6::{self, TrackDesign};
+use crate::track;
+use crate::util::u16_from_slice;
 
-pub fn read_td6_file(mut f: &File) {
+pub fn read_td6_file(mut f: &File, repo: Option<&ObjectRepository>) -> io::Result<()> {
     let mut vf = Vec::new();
-    f.read_to_end(&mut vf).unwrap();
-    //println!("{} - {:?}", vf.len(), vf);
+    f.read_to_end(&mut vf)?;
+    let td = TrackDesign::read(&vf[..])?;
     let checksum = &vf[vf.len()-4..];
-    println!("CHECKSUM: {:x?}", checksum);
-    let mut rd = rctrle::Reader::new(&vf[0..vf.len()-4]);
-    let mut v = Vec::new();
-    rd.read_to_end(&mut v).unwrap();
-    //println!("{} - {:?}", v.len(), v);
-    let track_type = v[0];
-    println!("Track type: {}", track_type);
-    let air_time = i32::from(v[0x4A]) * 4;
+    println!("CHECKSUM: {:x?} ({})", checksum, if td6::validate_checksum(&vf) { "valid" } else { "invalid" });
+    println!("Track type: {} (0x{:02X})", RideType::from(td.ride_type).name(), td.ride_type);
+    println!("Mode: {}", RideMode::from(td.ride_mode).name());
+    let air_time = i32::from(td.total_air_time) * 4;
     println!("Air time: {}", air_time);
-    let num_of_trains = v[0x4C];
-    println!("Number of trains: {}", num_of_trains);
-    let cars_per_train = v[0x4D];
-    println!("Cars per train: {}", cars_per_train);
-    let speed = v[0x50];
-    println!("Speed: {}", speed);
-    let excitement = f32::from(v[0x5B]) / 10.0;
-    let intensity = f32::from(v[0x5C]) / 10.0;
-    let nausea = f32::from(v[0x5D]) / 10.0;
+    println!("Number of trains: {}", td.number_of_trains);
+    println!("Cars per train: {}", td.cars_per_train);
+    println!("Speed: {}", td.operation_setting);
+    let excitement = f32::from(td.excitement) / 10.0;
+    let intensity = f32::from(td.intensity) / 10.0;
+    let nausea = f32::from(td.nausea) / 10.0;
     println!("Excitement: {}; Intensity: {}; Nausea: {}", excitement, intensity, nausea);
-    let mut i = 0;
-    loop {
-        let b1 = v[0xA3+2*i];
-        if b1 == 0xFF {
-            break;
+    for e in &td.elements {
+        println!("Track [{}; q: {:08b}]", segment_name(e.kind), e.flags);
+    }
+    if td.is_maze() {
+        println!("Maze tiles: {}", td.maze.len());
+    } else {
+        let placed = track::walk(&td.elements);
+        if let Some(bb) = track::bounding_box(&placed) {
+            let (w, l) = bb.tiles();
+            println!("Footprint: {} tiles; size {}x{} (stored {}x{}); height {}", track::footprint(&placed).len(), w, l, td.space_required_x, td.space_required_y, (bb.max.2 - bb.min.2) / 8);
         }
-        let b2 = v[0xA3+2*i+1];
-        println!("Track [{}; q: {:08b}]", segment_name(b1), b2);
-        i += 1;
+    }
+    if let Some(r) = repo {
+        print_missing_objects(r, &td.object_refs(), &[]);
+    }
+    Ok(())
+}
+
+/// Objects referenced by a saved game or scenario.
+pub fn sv6_object_refs(mut f: &File) -> io::Result<Vec<ObjectEntry>> {
+    let mut cr = ChunkReader::new(f);
+    let hv = cr.read_chunk()?.into_data();
+    if hv.len() < 4 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid header chunk"));
+    }
+    if hv[0] == 1 {
+        cr.skip_chunk()?;
+    }
+    for _ in 0..u16_from_slice(&hv, 2) {
+        f.seek(SeekFrom::Current(16))?;
+        ChunkReader::new(f).skip_chunk()?;
+    }
+    let vi = ChunkReader::new(f).read_chunk()?.into_data();
+    Ok(sv6_object_list(&vi))
+}
+
+fn sv6_object_list(vi: &[u8]) -> Vec<ObjectEntry> {
+    vi.chunks_exact(16).map(ObjectEntry::from_slice).filter(|e| !e.is_empty()).collect()
+}
+
+fn print_missing_objects(repo: &ObjectRepository, refs: &[ObjectEntry], packed: &[DatObject]) {
+    let missing = repo.missing(refs).into_iter()
+        .filter(|e| !packed.iter().any(|o| o.entry.matches(e)))
+        .collect::<Vec<_>>();
+    println!("Objects: {} referenced, {} missing", refs.len(), missing.len());
+    for e in missing {
+        println!("| Missing: {} ({:?})", e.name(), e.object_type());
     }
 }
 
-pub fn read_sv6_file(f: &File) {
-    // Read header
+pub fn read_dat_file(mut f: &File) -> io::Result<()> {
+    let obj = DatObject::read(&mut f)?;
+    println!("Object: {} ({:?})", obj.entry.name(), obj.entry.object_type());
+    println!("Flags: {:08X}; Checksum: {:08X} ({})", obj.entry.flags, obj.entry.checksum, if obj.is_valid() { "valid" } else { "invalid" });
+    let ro = match obj.ride_object() {
+        Some(r) => r?,
+        None => return Ok(())
+    };
+    println!("Name: {}", ro.name.english());
+    for (lang, name) in ro.name.translations() {
+        println!("| {}: {}", lang.code(), name);
+    }
+    println!("Description: {}", ro.description.english());
+    println!("Capacity: {}", ro.capacity.english());
+    println!("Ride types: {:?}", ro.ride_type.iter().filter(|&&t| t != 0xFF).map(|&t| RideType::from(t).name()).collect::<Vec<_>>());
+    println!("Cars per train: {}-{}", ro.min_cars_in_train, ro.max_cars_in_train);
+    println!("Excitement: {:+}; Intensity: {:+}; Nausea: {:+}", ro.excitement_multiplier, ro.intensity_multiplier, ro.nausea_multiplier);
+    for (i, v) in ro.vehicles.iter().enumerate().filter(|(_, v)| v.car_mass != 0) {
+        println!("Vehicle {} [seats: {}; mass: {}; spacing: {}; flags: {:08X}]", i, v.seats(), v.car_mass, v.spacing, v.flags);
+    }
+    if let Some((e, _)) = ro.preview() {
+        println!("Preview: {}x{}", e.width, e.height);
+    }
+    println!("Images: {}", ro.images.entries.len());
+    Ok(())
+}
+
+pub fn read_sv6_file(f: &File, repo: Option<&ObjectRepository>) -> io::Result<()> {
+    let sg = SavedGame::read(f)?;
     println!("Header...");
-    let hv = read_sv6_chunk(&f);
-    let co = u16_from_slice(&hv, 2);
+    if sg.is_scenario() {
+        println!("Scenario info...");
+    }
 
-    // How to read custom import objects?
-    if co > 0 {
-        println!("Can't read Custom Objects yet... Sorry.");
-        return;
+    // Custom objects packed into the save
+    for obj in &sg.packed {
+        println!("Packed object: {} ({:?})", obj.entry.name(), obj.entry.object_type());
     }
 
-    // Read available items
+    // Available items
     println!("Items...");
-    read_sv6_chunk(&f);
+    if let Some(r) = repo {
+        print_missing_objects(r, &sg.objects(), &sg.packed);
+    }
 
-    // Read date
+    // Date
     println!("Flags 1...");
-    let vd = read_sv6_chunk(&f);
-    let month = u16_from_slice(&vd, 0);
-    let day = u32::from(u16_from_slice(&vd, 2)) * 16 / 0x8421 + 1;
+    let month = sg.months();
+    let day = sg.chunks.get(1).and_then(|c| c.data().get(2..4)).map_or(0, |b| u32::from(u16_from_slice(b, 0))) * 16 / 0x8421 + 1;
     println!("Day: {}; Month: {}; Year: {}", day, month % 8, month / 8);
 
-    // Read game map
     println!("Map...");
-    read_sv6_chunk(&f);
-
-    // Read game data
     println!("Game data...");
-    let vx = read_sv6_chunk(&f);
-    println!("Initial cash: {}", u32_from_slice(&vx, 0x27_1024));
-    println!("Loan: {}", u32_from_slice(&vx, 0x27_1028));
-    println!("Entrance fee: {}", u32_from_slice(&vx, 0x27_1030));
-    let guests_in_park = u16_from_slice(&vx, 0x27_148C);
-    println!("Guests in park: {}", guests_in_park);
-    let park_rating = u16_from_slice(&vx, 0x27_18F8);
-    println!("Park rating: {}", park_rating);
-    println!("Real cash: {}", decrypt_money(u32_from_slice(&vx, 0x27_2440)));
+    let show = |name: &str, v: Option<u32>| match v {
+        Some(v) => println!("{}: {}", name, v),
+        None => println!("{}: not stored", name)
+    };
+    show("Initial cash", sg.read_u32(s6::INITIAL_CASH));
+    show("Loan", sg.read_u32(s6::CURRENT_LOAN));
+    show("Entrance fee", sg.read_u32(s6::PARK_ENTRANCE_FEE));
+    show("Guests in park", sg.read_u16(s6::GUESTS_IN_PARK).map(u32::from));
+    show("Park rating", sg.read_u16(s6::PARK_RATING).map(u32::from));
+    show("Real cash", sg.read_u32(s6::CASH).map(decrypt_money));
 
-    read_sv6_rides(&vx[0x27_C540..0x2A_22E0], month);
+    read_sv6_rides(&sg);
+    Ok(())
 }
 
-fn read_sv6_chunk(mut f: &File) -> Vec<u8> {
-    let mut cb: [u8; 5] = [0; 5];
-    f.read_exact(&mut cb[..]).unwrap();
-    let sz = u32_from_slice(&cb, 1) as usize;
-    let mut ch = vec![0u8; sz];
-    f.read_exact(&mut ch).unwrap();
-    match cb[0] {
-        0 => {
-            ch
-        }
-        1 => {
-            let mut rd = rctrle::Reader::new(&ch[..]);
-            let mut cd = Vec::new();
-            rd.read_to_end(&mut cd).unwrap();
-            cd
-        },
-        2 => {
-            let mut rd = rctrle::Reader::new(&ch[..]);
-            let mut cd = Vec::new();
-            rd.read_to_end(&mut cd).unwrap();
-            rctrle::decompress(&mut cd)
+/// Prints the rides of a park with their suggested ticket prices.
+pub fn read_sv6_rides(sg: &SavedGame) {
+    let months = sg.months();
+    let rides = sg.rides();
+    for r in &rides {
+        let rt = RideType::from(r.ride_type);
+        let rating = sg.price_multipliers(r);
+        let age = r.age(months);
+        println!("+-= {} (0x{:02X}) =-", rt.name(), r.ride_type);
+        println!("| Category: {:?}; Mode: {}", rt.category(), RideMode::from(r.mode).name());
+        if let Some(o) = sg.ride_object(r) {
+            println!("| Vehicle: {}", o.name.english());
         }
-        3 => {
-            rctrle::rotate_bytes(&mut ch);
-            ch
-        },
-        _ => {
-            vec!()
+        println!("| Age (months): {}", age);
+        if !rt.is_shop() {
+            println!("| Excitement: {}; Intensity: {}; Nausea: {}", r.excitement, r.intensity, r.nausea);
+            println!("| Ticket price: {:.2} (suggested {:.2})", f32::from(r.price) / 10.0, calculate_price(rating, r.excitement, r.intensity, r.nausea, age) / 10.0);
+            println!("| Calculated: {:.2}", f64::from(calculate_price_orig(rating, r.excitement, r.intensity, r.nausea, age)) / 10.0);
         }
-    }
-}
-
-pub fn read_sv6_rides(mut b: &[u8], pmon: u16) {
-    let mut n = 0;
-    while !b.is_empty() {
-        if b[0] == 0xFF {
-            return;
-        }
-        let excitement = u16_from_slice(b, 0x140);
-        let intensity = u16_from_slice(b, 0x142);
-        let nausea = u16_from_slice(b, 0x144);
-        let constructed = u16_from_slice(b, 0x180);
-        println!("+-= RIDE 0x{:X} =-", b[0]);
-        println!("| Excitement: {}; Intensity: {}; Nausea: {}", excitement, intensity, nausea);
-        println!("| Age (months): {}", pmon - constructed);
-        println!("| Ticket price: {:.2} (suggested {:.2})", f32::from(u16_from_slice(b, 0x138)) / 10.0, calculate_price(b[0], excitement, intensity, nausea, pmon - constructed) / 10.0);
-        println!("| Calculated: {:.2}", f64::from(calculate_price_orig(b[0], excitement, intensity, nausea, pmon - constructed)) / 10.0);
         println!(".");
-        b = &b[608..];
-        n += 1
     }
-    println!("Number of rides: {}", n);
+    println!("Number of rides: {}", rides.len());
 }
 
-pub fn calculate_price(ride: u8, exc: u16, int: u16, nau: u16, age: u16) -> f64 {
-    let (m_exc, m_int, m_nau) = ride_rating(ride);
+pub fn calculate_price(rating: (i32, i32, i32), exc: u16, int: u16, nau: u16, age: u16) -> f64 {
+    let (m_exc, m_int, m_nau) = rating;
     let m_age = match age {
         _ if age < 5 => 1.5,
         _ if age < 13 => 1.2,
@@ -153,8 +186,8 @@ pub fn calculate_price(ride: u8, exc: u16, int: u16, nau: u16, age: u16) -> f64
     (m_age * f64::from(bv) * 2.0 - 1.0).floor().max(0.0)
 }
 
-pub fn calculate_price_orig(ride: u8, exc: u16, int: u16, nau: u16, age: u16) -> i32 {
-    let (m_exc, m_int, m_nau) = ride_rating(ride);
+pub fn calculate_price_orig(rating: (i32, i32, i32), exc: u16, int: u16, nau: u16, age: u16) -> i32 {
+    let (m_exc, m_int, m_nau) = rating;
     let (m_age, d_age) = match age {
         _ if age < 5 => (3, 2),
         _ if age < 13 => (6, 5),
@@ -176,79 +209,21 @@ pub fn decrypt_money(c: u32) -> u32 {
     (c ^ 0xF4EC_9621).rotate_left(13)
 }
 
+pub fn encrypt_money(m: u32) -> u32 {
+    m.rotate_right(13) ^ 0xF4EC_9621
+}
+
+/// Price multipliers of a ride built with a ride object: those of the ride type the object is
+/// built as, preferring `ride_type` when the object lists it. The object's own excitement,
+/// intensity and nausea multipliers are already part of the ride's stored ratings.
+pub fn object_rating(obj: &RideObject, ride_type: u8) -> (i32, i32, i32) {
+    let t = if ride_type != 0xFF && obj.ride_type.contains(&ride_type) { ride_type } else { obj.ride_type() };
+    ride_rating(t)
+}
+
+/// Price multipliers of a ride type.
 pub fn ride_rating(ride: u8) -> (i32, i32, i32) {
-    const C_RATING: (i32, i32, i32) = (50, 30, 10);
-    match ride {
-        0..=4 => C_RATING,
-        5 | 6 => (70, 6, -10),
-        7 => C_RATING,
-        8 => (70, 6, 0),
-        9 => (50, 30, 30),
-        0xA => C_RATING,
-        0xB => (70, 10, 10),
-        0xC => (50, 50, 10),
-        0xD => C_RATING,
-        0xE => (80, 10, 0),
-        0xF..=0x11 => C_RATING,
-        0x12 => (70, 10, 0),
-        0x13 => C_RATING,
-        0x14 => (50, 0, 0),
-        0x15 => (50, 10, 0),
-        0x16 => (120, 0, 0),
-        0x17 => (80, 34, 6),
-        0x18 => (72, 26, 6),
-        0x19 => (40, 20, 0),
-        0x1A | 0x1B => C_RATING,
-        // Stalls (1C-20)
-        0x21 => (50, 10, 0),
-        // Buildings (22-24)
-        0x25 => (60, 20, 10),
-        0x26 => (24, 20, 10),
-        0x27 => (20, 10, 0),
-        0x28 => (24, 20, 10),
-        0x29 => (12, 4, 4),
-        0x2A => (44, 66, 10),
-        0x2B => (80, 10, 0),
-        0x2C => (52, 38, 10),
-        // ATM (2D)
-        0x2E => (40, 20, 10),
-        0x2F => (20, 10, 0),
-        // First Aid (30)
-        0x31 => (20, 10, 0),
-        0x32 => (70, 10, 10),
-        0x33 => (52, 36, 10),
-        0x34 => (52, 33, 8),
-        0x35 => (48, 28, 7),
-        0x36 => (50, 30, 30),
-        0x37 | 0x39 => C_RATING,
-        // NONE (38, 3A)
-        0x3B => (30, 15, 25),
-        0x3C => (80, 34, 6),
-        0x3D => (70, 10, 10),
-        0x3E => C_RATING,
-        0x3F => (70, 6, -10),
-        // NONE (40)
-        0x41 => (48, 28, 7),
-        0x42 | 0x43 => C_RATING,
-        0x44 => (51, 32, 10),
-        0x45 => (50, 50, 10),
-        0x46 => (50, 25, 0),
-        0x47 => (15, 8, 0),
-        0x48 => (50, 10, 10),
-        0x49 | 0x4A => C_RATING,
-        0x4B => (44, 66, 10),
-        0x4C => (50, 30, 30),
-        0x4D => C_RATING,
-        0x4E => (70, 6, 0),
-        0x4F => (80, 34, 6),
-        // NONE (50)
-        0x51 => (50, 50, 0),
-        // NONE (52-55)
-        0x56 | 0x57 => C_RATING,
-        0x58 => (60, 20, 10),
-        0x5A => C_RATING,
-        _ => (0, 0, 0)
-    }
+    RideType::from(ride).info().price_multipliers
 }
 
 pub fn segment_name(n: u8) -> &'static str {
@@ -511,3 +486,52 @@ pub fn segment_name(n: u8) -> &'static str {
         _ => "UNK"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ride object data built as `ride_types`, with the given rating multipliers and nothing else.
+    fn ride_object(ride_types: [u8; 3], multipliers: [i8; 3]) -> RideObject {
+        let mut b = vec![0u8; 450];
+        b[12..15].copy_from_slice(&ride_types);
+        for (i, &m) in multipliers.iter().enumerate() {
+            b[434 + i] = m as u8;
+        }
+        b.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        RideObject::from_data(&b).unwrap()
+    }
+
+    #[test]
+    fn object_multipliers_are_not_applied_twice() {
+        let wooden = ride_object([0x34, 0xFF, 0xFF], [20, -10, 5]);
+        assert_eq!(object_rating(&wooden, 0x34), ride_rating(0x34));
+        assert_eq!(object_rating(&wooden, 0xFF), (52, 33, 8));
+    }
+
+    #[test]
+    fn object_ride_type_is_preferred_when_listed() {
+        let cars = ride_object([0x34, 0x0F, 0xFF], [0; 3]);
+        assert_eq!(object_rating(&cars, 0x0F), ride_rating(0x0F));
+        assert_eq!(object_rating(&cars, 0x15), ride_rating(0x34));
+    }
+
+    #[test]
+    fn suggested_prices() {
+        // A new wooden roller coaster rated 6.50/5.20/3.00 is worth 33 + 16 + 2 = 51, raised by half
+        // for its first five months; guests pay up to twice that, less 0.10
+        assert_eq!(calculate_price_orig(ride_rating(0x34), 650, 520, 300, 0), 151);
+        // After 40 months the value drops to three quarters
+        assert_eq!(calculate_price_orig(ride_rating(0x34), 650, 520, 300, 40), 75);
+        assert_eq!(calculate_price_orig(ride_rating(0x34), 0, 0, 0, 0), 0);
+        assert_eq!(calculate_price(ride_rating(0x34), 650, 520, 300, 0), 155.0);
+    }
+
+    #[test]
+    fn money_encryption() {
+        assert_eq!(encrypt_money(0), 0xF4EC_9621);
+        for &m in &[0u32, 1, 10_000, 0x7FFF_FFFF, (-5000i32) as u32] {
+            assert_eq!(decrypt_money(encrypt_money(m)), m);
+        }
+    }
+}
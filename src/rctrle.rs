@@ -1,4 +1,4 @@
-use std::io::{self, Read, Bytes};
+use std::io::{self, BufRead, Read, Bytes};
 
 enum RunState {
     None, Copy(usize), Repeat(usize, u8)
@@ -9,30 +9,34 @@ pub struct Reader<R> {
     state: RunState
 }
 
-impl<R: Read> Reader<R> {
+impl<R: BufRead> Reader<R> {
     pub fn new(r: R) -> Reader<R> {
         Reader{buf: r.bytes(), state: RunState::None}
     }
-    fn read_run(&mut self) {
+    fn read_run(&mut self) -> io::Result<()> {
         if let RunState::None = self.state {
-            if let Some(Ok(b)) = self.buf.next() {
-                let z = b as i8;
+            if let Some(b) = self.buf.next() {
+                let z = b? as i8;
                 if z > 0 {
                     self.state = RunState::Copy((z as usize) + 1);
                 } else {
-                    let c = self.buf.next().unwrap().unwrap();
-                    self.state = RunState::Repeat(((-z) as usize) + 1, c);
+                    let c = match self.buf.next() {
+                        Some(c) => c?,
+                        None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "RLE run is missing its byte"))
+                    };
+                    self.state = RunState::Repeat((1 - isize::from(z)) as usize, c);
                 }
-            }   
+            }
         }
+        Ok(())
     }
 }
-impl<R: Read> Read for Reader<R> {
+impl<R: BufRead> Read for Reader<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let mut br = 0;
 
         for slot in buf {
-            self.read_run();
+            self.read_run()?;
             match self.state {
                 RunState::Copy(n) => {
                     match self.buf.next() {
@@ -78,23 +82,171 @@ pub fn rotate_bytes(b: &mut [u8]) {
     }
 }
 
-pub fn decompress(b: &mut [u8]) -> Vec<u8> {
+/// Expands the back-references of RLE compressed chunks. Each byte copies up to 8 bytes from
+/// the last 32 written, or is 0xFF followed by a literal byte.
+pub fn decompress(b: &[u8]) -> io::Result<Vec<u8>> {
     let mut i = 0;
     let mut v = Vec::new();
     while i < b.len() {
         let x = b[i];
         if x == 0xFF {
-            v.push(b[i+1]);
+            match b.get(i+1) {
+                Some(&c) => v.push(c),
+                None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "compressed literal is missing its byte"))
+            }
             i += 2;
         } else {
-            let ln = i32::from(x & 7) + 1;
-            let of = i32::from(x >> 3) - 32;
-            let sx = (v.len() as i32) + of;
+            let ln = usize::from(x & 7) + 1;
+            let of = 32 - usize::from(x >> 3);
+            if of > v.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "compressed back-reference before the start of the data"));
+            }
+            let sx = v.len() - of;
             for nx in sx..(sx+ln) {
-                v.push(v[nx as usize]);
+                v.push(v[nx]);
             }
             i += 1;
         }
     }
+    Ok(v)
+}
+pub fn rotate_bytes_left(b: &mut [u8]) {
+    let mut i = 1;
+    for x in b {
+        *x = x.rotate_left(i);
+        i += 2;
+        if i > 7 {
+            i = 1;
+        }
+    }
+}
+
+/// Run-length encodes data the same way the game does, so unmodified data encodes to the original bytes.
+pub fn encode(b: &[u8]) -> Vec<u8> {
+    let mut v = Vec::with_capacity(b.len() + b.len() / 125 + 1);
+    let mut i = 0;
+    let mut start = 0;
+    let mut count = 0usize;
+    while i + 1 < b.len() {
+        if (count != 0 && b[i] == b[i+1]) || count > 125 {
+            v.push((count - 1) as u8);
+            v.extend_from_slice(&b[start..start+count]);
+            start += count;
+            count = 0;
+        }
+        if b[i] == b[i+1] {
+            while count < 125 && i + count < b.len() && b[i] == b[i+count] {
+                count += 1;
+            }
+            v.push((257 - count) as u8);
+            v.push(b[i]);
+            i += count;
+            start = i;
+            count = 0;
+        } else {
+            count += 1;
+            i += 1;
+        }
+    }
+    if i + 1 == b.len() {
+        count += 1;
+    }
+    if count != 0 {
+        v.push((count - 1) as u8);
+        v.extend_from_slice(&b[start..start+count]);
+    }
     v
-}
\ No newline at end of file
+}
+
+/// Inverse of `decompress`, searching the last 32 bytes for repeats of up to 8 bytes.
+pub fn compress(b: &[u8]) -> Vec<u8> {
+    let mut v = Vec::new();
+    if b.is_empty() {
+        return v;
+    }
+    v.push(0xFF);
+    v.push(b[0]);
+    let mut i = 1;
+    while i < b.len() {
+        let (mut best_at, mut best_len) = (0, 0);
+        for at in i.saturating_sub(32)..i {
+            let max = 7.min(i - 1 - at).min(b.len() - i - 1);
+            let ln = (0..=max).take_while(|&j| b[at+j] == b[i+j]).count();
+            if ln > best_len {
+                best_at = at;
+                best_len = ln;
+                if ln == 8 {
+                    break;
+                }
+            }
+        }
+        if best_len == 0 {
+            v.push(0xFF);
+            v.push(b[i]);
+            i += 1;
+        } else {
+            v.push(((best_len - 1) | ((32 - (i - best_at)) << 3)) as u8);
+            i += best_len;
+        }
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(b: &[u8]) -> io::Result<Vec<u8>> {
+        let mut v = Vec::new();
+        Reader::new(b).read_to_end(&mut v)?;
+        Ok(v)
+    }
+
+    #[test]
+    fn encodes_runs() {
+        assert_eq!(encode(&[1, 2, 3, 3, 3, 3]), [0x01, 1, 2, 0xFD, 3]);
+        assert_eq!(encode(&[7]), [0x00, 7]);
+        assert!(encode(&[]).is_empty());
+        let long = vec![9u8; 300];
+        let e = encode(&long);
+        assert_eq!(&e[..4], [0x84, 9, 0x84, 9]);
+        assert_eq!(decode(&e).unwrap(), long);
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let b: Vec<u8> = (0..2000u32).map(|i| if i % 300 < 150 { (i % 7) as u8 } else { (i * 31 % 251) as u8 }).collect();
+        assert_eq!(decode(&encode(&b)).unwrap(), b);
+    }
+
+    #[test]
+    fn truncated_run() {
+        assert_eq!(decode(&[0xFD]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn compress_round_trip() {
+        let b: Vec<u8> = b"ABCABCABCABCXYZXYZ-ABCABC".iter().cycle().take(500).copied().collect();
+        let c = compress(&b);
+        assert!(c.len() < b.len());
+        assert_eq!(decompress(&c).unwrap(), b);
+        assert!(compress(&[]).is_empty());
+    }
+
+    #[test]
+    fn decompress_errors() {
+        assert_eq!(decompress(&[0xFF, 1, 0xFF, 2, 0xF1]).unwrap(), [1, 2, 1, 2]);
+        assert_eq!(decompress(&[0xFF]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(decompress(&[0xFF, 1, 0x00]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rotation() {
+        let b = [0x81u8, 0x81, 0x81, 0x81, 0x81];
+        let mut r = b;
+        rotate_bytes(&mut r);
+        assert_eq!(r, [0xC0, 0x30, 0x0C, 0x03, 0xC0]);
+        rotate_bytes_left(&mut r);
+        assert_eq!(r, b);
+    }
+}
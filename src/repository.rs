@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use crate::object::{DatObject, ObjectEntry};
+use crate::util::DSer;
+
+const INDEX_MAGIC: &[u8; 8] = b"RCT2RIDX";
+const INDEX_VERSION: u32 = 1;
+
+/// Object file found while scanning a directory.
+#[derive(Clone, Debug)]
+pub struct RepositoryItem {
+    pub entry: ObjectEntry,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: u64
+}
+
+/// Index of DAT objects available locally (e.g. the game's `ObjData` directory).
+#[derive(Default)]
+pub struct ObjectRepository {
+    items: Vec<RepositoryItem>,
+    exact: HashMap<ObjectEntry, usize>,
+    by_name: HashMap<[u8; 8], Vec<usize>>
+}
+
+impl ObjectRepository {
+    /// Scans `dir` recursively, reading the header of every DAT file.
+    pub fn scan<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        Self::scan_cached(dir, None)
+    }
+
+    /// Scans `dir`, reusing entries from the index at `cache` for files that did not change,
+    /// then rewrites the index.
+    pub fn scan_with_cache<P: AsRef<Path>, C: AsRef<Path>>(dir: P, cache: C) -> io::Result<Self> {
+        let cache = cache.as_ref();
+        let cached = read_index(cache).unwrap_or_default();
+        let repo = Self::scan_cached(dir, Some(&cached))?;
+        repo.write_index(cache)?;
+        Ok(repo)
+    }
+
+    /// Index file location in the user's cache directory, unique for `dir`.
+    /// Returns `None` when no cache directory is known.
+    pub fn default_cache_path<P: AsRef<Path>>(dir: P) -> Option<PathBuf> {
+        let dir = dir.as_ref();
+        let mut h = DefaultHasher::new();
+        fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf()).hash(&mut h);
+        Some(user_cache_dir()?.join("rct2read").join(format!("{:016x}.idx", h.finish())))
+    }
+
+    fn scan_cached<P: AsRef<Path>>(dir: P, cached: Option<&[RepositoryItem]>) -> io::Result<Self> {
+        let cached: HashMap<&Path, &RepositoryItem> = cached.unwrap_or(&[]).iter().map(|i| (i.path.as_path(), i)).collect();
+        let mut files = Vec::new();
+        find_dat_files(dir.as_ref(), &mut files)?;
+        let mut repo = ObjectRepository::default();
+        for path in files {
+            let meta = fs::metadata(&path)?;
+            let size = meta.len();
+            let modified = meta.modified().ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs());
+            let item = match cached.get(path.as_path()) {
+                Some(&i) if i.size == size && i.modified == modified => i.clone(),
+                _ => {
+                    let entry = match BufReader::new(File::open(&path)?).read_dser::<ObjectEntry>() {
+                        Ok(e) => e,
+                        Err(_) => continue
+                    };
+                    RepositoryItem{entry, path, size, modified}
+                }
+            };
+            repo.add(item);
+        }
+        Ok(repo)
+    }
+
+    fn add(&mut self, item: RepositoryItem) {
+        let i = self.items.len();
+        self.exact.entry(item.entry).or_insert(i);
+        self.by_name.entry(item.entry.name).or_default().push(i);
+        self.items.push(item);
+    }
+
+    pub fn items(&self) -> &[RepositoryItem] {
+        &self.items
+    }
+
+    /// Finds the object a save or track refers to, using the game's matching rules.
+    pub fn find(&self, e: &ObjectEntry) -> Option<&RepositoryItem> {
+        if let Some(&i) = self.exact.get(e) {
+            return Some(&self.items[i]);
+        }
+        self.by_name.get(&e.name)?.iter()
+            .map(|&i| &self.items[i])
+            .find(|i| i.entry.matches(e))
+    }
+
+    pub fn load(&self, e: &ObjectEntry) -> io::Result<Option<DatObject>> {
+        match self.find(e) {
+            Some(i) => DatObject::read(&mut BufReader::new(File::open(&i.path)?)).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    /// Loads every referenced object available locally, skipping missing ones.
+    pub fn load_all(&self, refs: &[ObjectEntry]) -> io::Result<Vec<DatObject>> {
+        let mut v = Vec::new();
+        for e in refs {
+            if let Some(o) = self.load(e)? {
+                v.push(o);
+            }
+        }
+        Ok(v)
+    }
+
+    /// References which cannot be resolved locally.
+    pub fn missing<'a>(&self, refs: &'a [ObjectEntry]) -> Vec<&'a ObjectEntry> {
+        refs.iter().filter(|e| self.find(e).is_none()).collect()
+    }
+
+    /// Writes the index next to `p` under a fresh name and renames it into place,
+    /// so an existing file or link at `p` is replaced rather than written through.
+    pub fn write_index<P: AsRef<Path>>(&self, p: P) -> io::Result<()> {
+        let p = p.as_ref();
+        if let Some(d) = p.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(d)?;
+        }
+        let mut tmp = p.as_os_str().to_owned();
+        tmp.push(format!(".{}.tmp", std::process::id()));
+        let tmp = PathBuf::from(tmp);
+        let res = fs::OpenOptions::new().write(true).create_new(true).open(&tmp)
+            .and_then(|f| self.write_index_to(BufWriter::new(f)));
+        match res.and_then(|_| fs::rename(&tmp, p)) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let _ = fs::remove_file(&tmp);
+                Err(e)
+            }
+        }
+    }
+
+    fn write_index_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(INDEX_MAGIC)?;
+        w.write_all(&INDEX_VERSION.to_le_bytes())?;
+        w.write_all(&(self.items.len() as u32).to_le_bytes())?;
+        for i in &self.items {
+            w.write_all(&i.entry.flags.to_le_bytes())?;
+            w.write_all(&i.entry.name)?;
+            w.write_all(&i.entry.checksum.to_le_bytes())?;
+            w.write_all(&i.size.to_le_bytes())?;
+            w.write_all(&i.modified.to_le_bytes())?;
+            let p = i.path.to_string_lossy();
+            w.write_all(&(p.len() as u16).to_le_bytes())?;
+            w.write_all(p.as_bytes())?;
+        }
+        w.flush()
+    }
+}
+
+fn read_index(p: &Path) -> io::Result<Vec<RepositoryItem>> {
+    let mut r = BufReader::new(File::open(p)?);
+    let mut magic = [0u8; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != INDEX_MAGIC || r.read_u32()? != INDEX_VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not an object index"));
+    }
+    let n = r.read_u32()?;
+    // The count is untrusted; past the first records the list grows as they are read.
+    let mut items = Vec::with_capacity((n as usize).min(1024));
+    for _ in 0..n {
+        let entry = r.read_dser::<ObjectEntry>()?;
+        let size = r.read_u64()?;
+        let modified = r.read_u64()?;
+        let ln = r.read_u16()?;
+        let path = String::from_utf8_lossy(&r.read_bytes(ln as usize)?).into_owned();
+        items.push(RepositoryItem{entry, path: PathBuf::from(path), size, modified});
+    }
+    Ok(items)
+}
+
+/// `$XDG_CACHE_HOME`, `%LOCALAPPDATA%` or `~/.cache`, whichever is set first.
+fn user_cache_dir() -> Option<PathBuf> {
+    let var = |k| std::env::var_os(k).filter(|v| !v.is_empty()).map(PathBuf::from);
+    var("XDG_CACHE_HOME")
+        .or_else(|| var("LOCALAPPDATA"))
+        .or_else(|| var("HOME").map(|h| h.join(".cache")))
+        .filter(|p| p.is_absolute())
+}
+
+fn find_dat_files(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for e in entries {
+        let p = e.path();
+        if e.file_type()?.is_dir() {
+            find_dat_files(&p, out)?;
+        } else if p.extension().is_some_and(|x| x.eq_ignore_ascii_case("dat")) {
+            out.push(p);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(flags: u32, name: &[u8; 8], checksum: u32) -> ObjectEntry {
+        ObjectEntry{flags, name: *name, checksum}
+    }
+
+    fn scratch_dir(tag: &str) -> PathBuf {
+        let d = std::env::temp_dir().join(format!("rct2read-test-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&d);
+        fs::create_dir_all(&d).unwrap();
+        d
+    }
+
+    fn write_dat(p: &Path, e: &ObjectEntry) {
+        let mut b = e.flags.to_le_bytes().to_vec();
+        b.extend_from_slice(&e.name);
+        b.extend_from_slice(&e.checksum.to_le_bytes());
+        fs::write(p, b).unwrap();
+    }
+
+    #[test]
+    fn entry_matching() {
+        let shipped = entry(0x80, b"TOILETS ", 0x1234);
+        assert!(shipped.matches(&entry(0x80, b"TOILETS ", 0x9999)));
+        assert!(!shipped.matches(&entry(0x81, b"TOILETS ", 0x1234)));
+        assert!(!shipped.matches(&entry(0x80, b"TOILET2 ", 0x1234)));
+        let custom = entry(0x00, b"MYCOASTR", 0x1234);
+        assert!(custom.matches(&entry(0x00, b"MYCOASTR", 0x1234)));
+        assert!(!custom.matches(&entry(0x00, b"MYCOASTR", 0x1235)));
+    }
+
+    #[test]
+    fn finds_by_matching_rules() {
+        let dir = scratch_dir("find");
+        write_dat(&dir.join("TOILETS.DAT"), &entry(0x80, b"TOILETS ", 1));
+        write_dat(&dir.join("skip.txt"), &entry(0x80, b"SKIPPED ", 1));
+        let repo = ObjectRepository::scan(&dir).unwrap();
+        assert_eq!(repo.items().len(), 1);
+        assert!(repo.find(&entry(0x80, b"TOILETS ", 2)).is_some());
+        let refs = [entry(0x00, b"MISSING ", 0)];
+        assert_eq!(repo.missing(&refs).len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn index_round_trip() {
+        let dir = scratch_dir("index");
+        write_dat(&dir.join("A.DAT"), &entry(0x80, b"TOILETS ", 1));
+        write_dat(&dir.join("B.dat"), &entry(0x00, b"MYCOASTR", 2));
+        let cache = dir.join("cache").join("objects.idx");
+        let repo = ObjectRepository::scan_with_cache(&dir, &cache).unwrap();
+        let items = read_index(&cache).unwrap();
+        assert_eq!(items.len(), 2);
+        for (a, b) in items.iter().zip(repo.items()) {
+            assert_eq!((a.entry, &a.path, a.size, a.modified), (b.entry, &b.path, b.size, b.modified));
+        }
+        // Unchanged files keep their cached entry instead of being read again.
+        let mut fake = items.clone();
+        fake[0].entry.checksum = 7;
+        let reused = ObjectRepository::scan_cached(&dir, Some(&fake)).unwrap();
+        assert_eq!(reused.items()[0].entry.checksum, 7);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_index() {
+        let dir = scratch_dir("corrupt");
+        let p = dir.join("bad.idx");
+        let mut b = INDEX_MAGIC.to_vec();
+        b.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        b.extend_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(&p, &b).unwrap();
+        assert!(read_index(&p).is_err());
+        fs::write(&p, b"RCT2RIDX").unwrap();
+        assert!(read_index(&p).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn index_replaces_links() {
+        let dir = scratch_dir("link");
+        let target = dir.join("target");
+        fs::write(&target, b"keep").unwrap();
+        let link = dir.join("objects.idx");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        ObjectRepository::default().write_index(&link).unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"keep");
+        assert!(!fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cache_path_is_per_directory() {
+        if let Some(p) = ObjectRepository::default_cache_path("/a") {
+            assert!(p.is_absolute());
+            assert_ne!(Some(p), ObjectRepository::default_cache_path("/b"));
+        }
+    }
+}
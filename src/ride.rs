@@ -0,0 +1,141 @@
+use crate::util::{u16_from_slice, u32_from_slice};
+
+pub const RIDE_SIZE: usize = 0x260;
+pub const MAX_RIDES: usize = 255;
+
+/// Rating value of rides which have not been tested yet.
+pub const RATING_UNDEFINED: u16 = 0xFFFF;
+
+pub const STATUS_CLOSED: u8 = 0;
+pub const STATUS_OPEN: u8 = 1;
+pub const STATUS_TESTING: u8 = 2;
+
+/// Ride entry of a saved game.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ride {
+    pub index: u8,
+    pub ride_type: u8,
+    /// Index of the ride object in the object list.
+    pub subtype: u8,
+    pub mode: u8,
+    pub status: u8,
+    pub name: u16,
+    pub name_arguments: u32,
+    pub num_trains: u8,
+    pub num_cars_per_train: u8,
+    /// Speeds are 16.16 fixed point, in the game's internal units.
+    pub max_speed: i32,
+    pub average_speed: i32,
+    /// Length (16.16 metres) and time (seconds) of each station's circuit.
+    pub length: [i32; 4],
+    pub time: [u16; 4],
+    /// G-forces in hundredths of a G.
+    pub max_positive_vertical_g: i16,
+    pub max_negative_vertical_g: i16,
+    pub max_lateral_g: i16,
+    pub turn_count_default: u16,
+    pub turn_count_banked: u16,
+    pub turn_count_sloped: u16,
+    pub inversions: u8,
+    pub drops: u8,
+    pub highest_drop_height: u8,
+    pub sheltered_length: i32,
+    pub num_sheltered_sections: u8,
+    pub price: u16,
+    pub excitement: u16,
+    pub intensity: u16,
+    pub nausea: u16,
+    pub value: u16,
+    pub total_customers: u32,
+    pub total_profit: i32,
+    pub build_date: u16,
+    pub upkeep_cost: i16,
+    pub price_secondary: u16,
+    pub reliability: u16,
+    pub income_per_hour: i32,
+    pub profit: i32,
+    pub total_air_time: u16
+}
+
+impl Ride {
+    /// Parses a ride, returning None for unused slots.
+    pub fn from_slice(index: u8, b: &[u8]) -> Option<Self> {
+        if b[0] == 0xFF {
+            return None;
+        }
+        Some(Ride{
+            index,
+            ride_type: b[0x000],
+            subtype: b[0x001],
+            mode: b[0x004],
+            status: b[0x049],
+            name: u16_from_slice(b, 0x04A),
+            name_arguments: u32_from_slice(b, 0x04C),
+            num_trains: b[0x0C8],
+            num_cars_per_train: b[0x0C9],
+            max_speed: u32_from_slice(b, 0x0D8) as i32,
+            average_speed: u32_from_slice(b, 0x0DC) as i32,
+            length: [0, 1, 2, 3].map(|i| u32_from_slice(b, 0x0E4 + i * 4) as i32),
+            time: [0, 1, 2, 3].map(|i| u16_from_slice(b, 0x0F4 + i * 2)),
+            max_positive_vertical_g: u16_from_slice(b, 0x0FC) as i16,
+            max_negative_vertical_g: u16_from_slice(b, 0x0FE) as i16,
+            max_lateral_g: u16_from_slice(b, 0x100) as i16,
+            turn_count_default: u16_from_slice(b, 0x10E),
+            turn_count_banked: u16_from_slice(b, 0x110),
+            turn_count_sloped: u16_from_slice(b, 0x112),
+            inversions: b[0x114] & 0x1F,
+            drops: b[0x115] & 0x3F,
+            highest_drop_height: b[0x117],
+            sheltered_length: u32_from_slice(b, 0x118) as i32,
+            num_sheltered_sections: b[0x11E],
+            price: u16_from_slice(b, 0x138),
+            excitement: u16_from_slice(b, 0x140),
+            intensity: u16_from_slice(b, 0x142),
+            nausea: u16_from_slice(b, 0x144),
+            value: u16_from_slice(b, 0x146),
+            total_customers: u32_from_slice(b, 0x150),
+            total_profit: u32_from_slice(b, 0x154) as i32,
+            build_date: u16_from_slice(b, 0x180),
+            upkeep_cost: u16_from_slice(b, 0x182) as i16,
+            price_secondary: u16_from_slice(b, 0x194),
+            reliability: u16_from_slice(b, 0x196),
+            income_per_hour: u32_from_slice(b, 0x1B0) as i32,
+            profit: u32_from_slice(b, 0x1B4) as i32,
+            total_air_time: u16_from_slice(b, 0x1F4)
+        })
+    }
+
+    pub fn is_rated(&self) -> bool {
+        self.excitement != RATING_UNDEFINED
+    }
+
+    pub fn status_name(&self) -> &'static str {
+        match self.status {
+            STATUS_CLOSED => "closed",
+            STATUS_OPEN => "open",
+            STATUS_TESTING => "testing",
+            _ => "unknown"
+        }
+    }
+
+    /// Months since the ride was built.
+    pub fn age(&self, months: u16) -> u16 {
+        months.saturating_sub(self.build_date)
+    }
+
+    /// Total track length in metres.
+    pub fn total_length(&self) -> i32 {
+        self.length.iter().fold(0i32, |a, &l| a.wrapping_add(l)) >> 16
+    }
+
+    /// Total ride time in seconds.
+    pub fn total_time(&self) -> u32 {
+        self.time.iter().map(|&t| u32::from(t)).sum()
+    }
+
+    /// Reliability in percent.
+    pub fn reliability_percent(&self) -> u8 {
+        (self.reliability >> 8) as u8
+    }
+}
@@ -0,0 +1,425 @@
+/// Kind of ride, as stored in the first byte of rides and track designs.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RideType {
+    SpiralRollerCoaster,
+    StandUpRollerCoaster,
+    SuspendedSwingingCoaster,
+    InvertedRollerCoaster,
+    JuniorRollerCoaster,
+    MiniatureRailway,
+    Monorail,
+    MiniSuspendedCoaster,
+    BoatHire,
+    WoodenWildMouse,
+    SteeplechaseCoaster,
+    CarRide,
+    LaunchedFreefall,
+    BobsleighCoaster,
+    ObservationTower,
+    LoopingRollerCoaster,
+    DinghySlide,
+    MineTrainCoaster,
+    Chairlift,
+    CorkscrewRollerCoaster,
+    Maze,
+    SpiralSlide,
+    GoKarts,
+    LogFlume,
+    RiverRapids,
+    Dodgems,
+    SwingingShip,
+    SwingingInverterShip,
+    FoodStall,
+    Unused1D,
+    DrinkStall,
+    Unused1F,
+    Shop,
+    MerryGoRound,
+    Unused22,
+    InformationKiosk,
+    Toilets,
+    FerrisWheel,
+    MotionSimulator,
+    Cinema3D,
+    TopSpin,
+    SpaceRings,
+    ReverseFreefallCoaster,
+    Lift,
+    VerticalDropCoaster,
+    CashMachine,
+    Twist,
+    HauntedHouse,
+    FirstAid,
+    Circus,
+    GhostTrain,
+    TwisterRollerCoaster,
+    WoodenRollerCoaster,
+    SideFrictionRollerCoaster,
+    SteelWildMouse,
+    MultiDimensionCoaster,
+    Unused38,
+    FlyingRollerCoaster,
+    Unused3A,
+    VirginiaReel,
+    SplashBoats,
+    MiniHelicopters,
+    LayDownRollerCoaster,
+    SuspendedMonorail,
+    Unused40,
+    ReverserRollerCoaster,
+    HeartlineTwisterCoaster,
+    MiniGolf,
+    GigaCoaster,
+    RotoDrop,
+    FlyingSaucers,
+    CrookedHouse,
+    MonorailCycles,
+    CompactInvertedCoaster,
+    WaterCoaster,
+    AirPoweredVerticalCoaster,
+    InvertedHairpinCoaster,
+    MagicCarpet,
+    SubmarineRide,
+    RiverRafts,
+    Unused50,
+    Enterprise,
+    Unused52,
+    Unused53,
+    Unused54,
+    Unused55,
+    InvertedImpulseCoaster,
+    MiniRollerCoaster,
+    MineRide,
+    Unused59,
+    LimLaunchedRollerCoaster,
+    Unknown
+}
+
+/// Ride types by id.
+const RIDE_TYPES: [RideType; 91] = {
+    use RideType::*;
+    [
+        SpiralRollerCoaster, StandUpRollerCoaster, SuspendedSwingingCoaster, InvertedRollerCoaster,
+        JuniorRollerCoaster, MiniatureRailway, Monorail, MiniSuspendedCoaster,
+        BoatHire, WoodenWildMouse, SteeplechaseCoaster, CarRide,
+        LaunchedFreefall, BobsleighCoaster, ObservationTower, LoopingRollerCoaster,
+        DinghySlide, MineTrainCoaster, Chairlift, CorkscrewRollerCoaster,
+        Maze, SpiralSlide, GoKarts, LogFlume,
+        RiverRapids, Dodgems, SwingingShip, SwingingInverterShip,
+        FoodStall, Unused1D, DrinkStall, Unused1F,
+        Shop, MerryGoRound, Unused22, InformationKiosk,
+        Toilets, FerrisWheel, MotionSimulator, Cinema3D,
+        TopSpin, SpaceRings, ReverseFreefallCoaster, Lift,
+        VerticalDropCoaster, CashMachine, Twist, HauntedHouse,
+        FirstAid, Circus, GhostTrain, TwisterRollerCoaster,
+        WoodenRollerCoaster, SideFrictionRollerCoaster, SteelWildMouse, MultiDimensionCoaster,
+        Unused38, FlyingRollerCoaster, Unused3A, VirginiaReel,
+        SplashBoats, MiniHelicopters, LayDownRollerCoaster, SuspendedMonorail,
+        Unused40, ReverserRollerCoaster, HeartlineTwisterCoaster, MiniGolf,
+        GigaCoaster, RotoDrop, FlyingSaucers, CrookedHouse,
+        MonorailCycles, CompactInvertedCoaster, WaterCoaster, AirPoweredVerticalCoaster,
+        InvertedHairpinCoaster, MagicCarpet, SubmarineRide, RiverRafts,
+        Unused50, Enterprise, Unused52, Unused53,
+        Unused54, Unused55, InvertedImpulseCoaster, MiniRollerCoaster,
+        MineRide, Unused59, LimLaunchedRollerCoaster
+    ]
+};
+
+impl From<u8> for RideType {
+    fn from(n: u8) -> Self {
+        RIDE_TYPES.get(usize::from(n)).copied().unwrap_or(RideType::Unknown)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RideCategory {
+    Transport,
+    Gentle,
+    Coaster,
+    Thrill,
+    Water,
+    Shop,
+    Facility,
+    Unused
+}
+
+/// Static data about a ride type.
+#[derive(Copy, Clone, Debug)]
+pub struct RideTypeInfo {
+    pub name: &'static str,
+    pub category: RideCategory,
+    /// Excitement, intensity and nausea multipliers of the ride's value.
+    pub price_multipliers: (i32, i32, i32),
+    pub has_track: bool,
+    pub default_mode: RideMode
+}
+
+const fn ty(name: &'static str, category: RideCategory, price_multipliers: (i32, i32, i32), has_track: bool, default_mode: RideMode) -> RideTypeInfo {
+    RideTypeInfo{name, category, price_multipliers, has_track, default_mode}
+}
+
+impl RideType {
+    pub fn id(self) -> Option<u8> {
+        RIDE_TYPES.iter().position(|&t| t == self).map(|i| i as u8)
+    }
+
+    pub fn info(self) -> RideTypeInfo {
+        use RideCategory::*;
+        use RideMode as M;
+        const NONE: (i32, i32, i32) = (0, 0, 0);
+        const C_RATING: (i32, i32, i32) = (50, 30, 10);
+        match self {
+            RideType::SpiralRollerCoaster => ty("Spiral Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::StandUpRollerCoaster => ty("Stand-up Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::SuspendedSwingingCoaster => ty("Suspended Swinging Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::InvertedRollerCoaster => ty("Inverted Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::JuniorRollerCoaster => ty("Junior Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::MiniatureRailway => ty("Miniature Railway", Transport, (70, 6, -10), true, M::ContinuousCircuit),
+            RideType::Monorail => ty("Monorail", Transport, (70, 6, -10), true, M::ContinuousCircuit),
+            RideType::MiniSuspendedCoaster => ty("Mini Suspended Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::BoatHire => ty("Boat Hire", Water, (70, 6, 0), true, M::BoatHire),
+            RideType::WoodenWildMouse => ty("Wooden Wild Mouse", Coaster, (50, 30, 30), true, M::ContinuousCircuit),
+            RideType::SteeplechaseCoaster => ty("Steeplechase", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::CarRide => ty("Car Ride", Gentle, (70, 10, 10), true, M::ContinuousCircuit),
+            RideType::LaunchedFreefall => ty("Launched Freefall", Thrill, (50, 50, 10), true, M::UpwardLaunch),
+            RideType::BobsleighCoaster => ty("Bobsleigh Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::ObservationTower => ty("Observation Tower", Gentle, (80, 10, 0), true, M::RotatingLift),
+            RideType::LoopingRollerCoaster => ty("Looping Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::DinghySlide => ty("Dinghy Slide", Water, C_RATING, true, M::ContinuousCircuit),
+            RideType::MineTrainCoaster => ty("Mine Train Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::Chairlift => ty("Chairlift", Transport, (70, 10, 0), true, M::StationToStation),
+            RideType::CorkscrewRollerCoaster => ty("Corkscrew Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::Maze => ty("Maze", Gentle, (50, 0, 0), false, M::Maze),
+            RideType::SpiralSlide => ty("Spiral Slide", Gentle, (50, 10, 0), false, M::SingleRidePerAdmission),
+            RideType::GoKarts => ty("Go-Karts", Thrill, (120, 0, 0), true, M::Race),
+            RideType::LogFlume => ty("Log Flume", Water, (80, 34, 6), true, M::ContinuousCircuit),
+            RideType::RiverRapids => ty("River Rapids", Water, (72, 26, 6), true, M::ContinuousCircuit),
+            RideType::Dodgems => ty("Dodgems", Gentle, (40, 20, 0), false, M::Dodgems),
+            RideType::SwingingShip => ty("Swinging Ship", Thrill, C_RATING, false, M::Swing),
+            RideType::SwingingInverterShip => ty("Swinging Inverter Ship", Thrill, C_RATING, false, M::Swing),
+            RideType::FoodStall => ty("Food Stall", Shop, NONE, false, M::ShopStall),
+            RideType::Unused1D => ty("Unused (1D)", Shop, NONE, false, M::ShopStall),
+            RideType::DrinkStall => ty("Drink Stall", Shop, NONE, false, M::ShopStall),
+            RideType::Unused1F => ty("Unused (1F)", Shop, NONE, false, M::ShopStall),
+            RideType::Shop => ty("Shop", Shop, NONE, false, M::ShopStall),
+            RideType::MerryGoRound => ty("Merry-Go-Round", Gentle, (50, 10, 0), false, M::Rotation),
+            RideType::Unused22 => ty("Unused (22)", Facility, NONE, false, M::ShopStall),
+            RideType::InformationKiosk => ty("Information Kiosk", Shop, NONE, false, M::ShopStall),
+            RideType::Toilets => ty("Toilets", Facility, NONE, false, M::ShopStall),
+            RideType::FerrisWheel => ty("Ferris Wheel", Gentle, (60, 20, 10), false, M::ForwardRotation),
+            RideType::MotionSimulator => ty("Motion Simulator", Thrill, (24, 20, 10), false, M::FilmAvengingAviators),
+            RideType::Cinema3D => ty("3D Cinema", Thrill, (20, 10, 0), false, M::Film3DMouseTails),
+            RideType::TopSpin => ty("Top Spin", Thrill, (24, 20, 10), false, M::Beginners),
+            RideType::SpaceRings => ty("Space Rings", Gentle, (12, 4, 4), false, M::SpaceRings),
+            RideType::ReverseFreefallCoaster => ty("Reverse Freefall Coaster", Coaster, (44, 66, 10), true, M::LimPoweredLaunch),
+            RideType::Lift => ty("Lift", Transport, (80, 10, 0), true, M::Shuttle),
+            RideType::VerticalDropCoaster => ty("Vertical Drop Roller Coaster", Coaster, (52, 38, 10), true, M::ContinuousCircuit),
+            RideType::CashMachine => ty("Cash Machine", Facility, NONE, false, M::ShopStall),
+            RideType::Twist => ty("Twist", Thrill, (40, 20, 10), false, M::Rotation),
+            RideType::HauntedHouse => ty("Haunted House", Gentle, (20, 10, 0), false, M::HauntedHouse),
+            RideType::FirstAid => ty("First Aid Room", Facility, NONE, false, M::ShopStall),
+            RideType::Circus => ty("Circus", Gentle, (20, 10, 0), false, M::Circus),
+            RideType::GhostTrain => ty("Ghost Train", Gentle, (70, 10, 10), true, M::ContinuousCircuit),
+            RideType::TwisterRollerCoaster => ty("Twister Roller Coaster", Coaster, (52, 36, 10), true, M::ContinuousCircuit),
+            RideType::WoodenRollerCoaster => ty("Wooden Roller Coaster", Coaster, (52, 33, 8), true, M::ContinuousCircuit),
+            RideType::SideFrictionRollerCoaster => ty("Side-Friction Roller Coaster", Coaster, (48, 28, 7), true, M::ContinuousCircuit),
+            RideType::SteelWildMouse => ty("Steel Wild Mouse", Coaster, (50, 30, 30), true, M::ContinuousCircuit),
+            RideType::MultiDimensionCoaster => ty("Multi-Dimension Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::Unused38 => ty("Unused (38)", Unused, NONE, false, M::Normal),
+            RideType::FlyingRollerCoaster => ty("Flying Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::Unused3A => ty("Unused (3A)", Unused, NONE, false, M::Normal),
+            RideType::VirginiaReel => ty("Virginia Reel", Coaster, (30, 15, 25), true, M::ContinuousCircuit),
+            RideType::SplashBoats => ty("Splash Boats", Water, (80, 34, 6), true, M::ContinuousCircuit),
+            RideType::MiniHelicopters => ty("Mini Helicopters", Gentle, (70, 10, 10), true, M::ContinuousCircuit),
+            RideType::LayDownRollerCoaster => ty("Lay-down Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::SuspendedMonorail => ty("Suspended Monorail", Transport, (70, 6, -10), true, M::ContinuousCircuit),
+            RideType::Unused40 => ty("Unused (40)", Unused, NONE, false, M::Normal),
+            RideType::ReverserRollerCoaster => ty("Reverser Roller Coaster", Coaster, (48, 28, 7), true, M::ContinuousCircuit),
+            RideType::HeartlineTwisterCoaster => ty("Heartline Twister Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::MiniGolf => ty("Mini Golf", Gentle, C_RATING, true, M::ContinuousCircuit),
+            RideType::GigaCoaster => ty("Giga Coaster", Coaster, (51, 32, 10), true, M::ContinuousCircuit),
+            RideType::RotoDrop => ty("Roto-Drop", Thrill, (50, 50, 10), true, M::FreefallDrop),
+            RideType::FlyingSaucers => ty("Flying Saucers", Gentle, (50, 25, 0), false, M::Dodgems),
+            RideType::CrookedHouse => ty("Crooked House", Gentle, (15, 8, 0), false, M::CrookedHouse),
+            RideType::MonorailCycles => ty("Monorail Cycles", Gentle, (50, 10, 10), true, M::ContinuousCircuit),
+            RideType::CompactInvertedCoaster => ty("Compact Inverted Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::WaterCoaster => ty("Water Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::AirPoweredVerticalCoaster => ty("Air Powered Vertical Coaster", Coaster, (44, 66, 10), true, M::PoweredLaunchPassthrough),
+            RideType::InvertedHairpinCoaster => ty("Inverted Hairpin Coaster", Coaster, (50, 30, 30), true, M::ContinuousCircuit),
+            RideType::MagicCarpet => ty("Magic Carpet", Thrill, C_RATING, false, M::Swing),
+            RideType::SubmarineRide => ty("Submarine Ride", Water, (70, 6, 0), true, M::ContinuousCircuit),
+            RideType::RiverRafts => ty("River Rafts", Water, (80, 34, 6), true, M::ContinuousCircuit),
+            RideType::Unused50 => ty("Unused (50)", Unused, NONE, false, M::Normal),
+            RideType::Enterprise => ty("Enterprise", Thrill, (50, 50, 0), false, M::Rotation),
+            RideType::Unused52 => ty("Unused (52)", Unused, NONE, false, M::Normal),
+            RideType::Unused53 => ty("Unused (53)", Unused, NONE, false, M::Normal),
+            RideType::Unused54 => ty("Unused (54)", Unused, NONE, false, M::Normal),
+            RideType::Unused55 => ty("Unused (55)", Unused, NONE, false, M::Normal),
+            RideType::InvertedImpulseCoaster => ty("Inverted Impulse Coaster", Coaster, C_RATING, true, M::PoweredLaunchPassthrough),
+            RideType::MiniRollerCoaster => ty("Mini Roller Coaster", Coaster, C_RATING, true, M::ContinuousCircuit),
+            RideType::MineRide => ty("Mine Ride", Coaster, (60, 20, 10), true, M::ContinuousCircuit),
+            RideType::Unused59 => ty("Unused (59)", Unused, NONE, false, M::Normal),
+            RideType::LimLaunchedRollerCoaster => ty("LIM Launched Roller Coaster", Coaster, C_RATING, true, M::PoweredLaunchPassthrough),
+            RideType::Unknown => ty("Unknown", Unused, NONE, false, M::Normal)
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    pub fn category(self) -> RideCategory {
+        self.info().category
+    }
+
+    pub fn has_track(self) -> bool {
+        self.info().has_track
+    }
+
+    pub fn is_maze(self) -> bool {
+        self == RideType::Maze
+    }
+
+    /// Stalls and kiosks selling items, which have no ratings.
+    pub fn is_shop(self) -> bool {
+        self.category() == RideCategory::Shop
+    }
+}
+
+/// Operating mode of a ride.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RideMode {
+    Normal,
+    ContinuousCircuit,
+    ReverseInclineLaunchedShuttle,
+    PoweredLaunchPassthrough,
+    Shuttle,
+    BoatHire,
+    UpwardLaunch,
+    RotatingLift,
+    StationToStation,
+    SingleRidePerAdmission,
+    UnlimitedRidesPerAdmission,
+    Maze,
+    Race,
+    Dodgems,
+    Swing,
+    ShopStall,
+    Rotation,
+    ForwardRotation,
+    BackwardRotation,
+    FilmAvengingAviators,
+    Film3DMouseTails,
+    SpaceRings,
+    Beginners,
+    LimPoweredLaunch,
+    FilmThrillRiders,
+    Film3DStormChasers,
+    Film3DSpaceRaiders,
+    Intense,
+    Berserk,
+    HauntedHouse,
+    Circus,
+    DownwardLaunch,
+    CrookedHouse,
+    FreefallDrop,
+    ContinuousCircuitBlockSectioned,
+    PoweredLaunch,
+    PoweredLaunchBlockSectioned,
+    Unknown
+}
+
+impl From<u8> for RideMode {
+    fn from(n: u8) -> Self {
+        use RideMode::*;
+        match n {
+            0 => Normal,
+            1 => ContinuousCircuit,
+            2 => ReverseInclineLaunchedShuttle,
+            3 => PoweredLaunchPassthrough,
+            4 => Shuttle,
+            5 => BoatHire,
+            6 => UpwardLaunch,
+            7 => RotatingLift,
+            8 => StationToStation,
+            9 => SingleRidePerAdmission,
+            10 => UnlimitedRidesPerAdmission,
+            11 => Maze,
+            12 => Race,
+            13 => Dodgems,
+            14 => Swing,
+            15 => ShopStall,
+            16 => Rotation,
+            17 => ForwardRotation,
+            18 => BackwardRotation,
+            19 => FilmAvengingAviators,
+            20 => Film3DMouseTails,
+            21 => SpaceRings,
+            22 => Beginners,
+            23 => LimPoweredLaunch,
+            24 => FilmThrillRiders,
+            25 => Film3DStormChasers,
+            26 => Film3DSpaceRaiders,
+            27 => Intense,
+            28 => Berserk,
+            29 => HauntedHouse,
+            30 => Circus,
+            31 => DownwardLaunch,
+            32 => CrookedHouse,
+            33 => FreefallDrop,
+            34 => ContinuousCircuitBlockSectioned,
+            35 => PoweredLaunch,
+            36 => PoweredLaunchBlockSectioned,
+            _ => Unknown
+        }
+    }
+}
+
+impl RideMode {
+    pub fn name(self) -> &'static str {
+        use RideMode::*;
+        match self {
+            Normal => "Normal",
+            ContinuousCircuit => "Continuous circuit",
+            ReverseInclineLaunchedShuttle => "Reverse incline launched shuttle",
+            PoweredLaunchPassthrough => "Powered launch (passing through station)",
+            Shuttle => "Shuttle",
+            BoatHire => "Boat hire",
+            UpwardLaunch => "Upward launch",
+            RotatingLift => "Rotating lift",
+            StationToStation => "Station to station",
+            SingleRidePerAdmission => "Single ride per admission",
+            UnlimitedRidesPerAdmission => "Unlimited rides per admission",
+            Maze => "Maze",
+            Race => "Race",
+            Dodgems => "Dodgems",
+            Swing => "Swing",
+            ShopStall => "Shop stall",
+            Rotation => "Rotation",
+            ForwardRotation => "Forward rotation",
+            BackwardRotation => "Backward rotation",
+            FilmAvengingAviators => "Film: Avenging aviators",
+            Film3DMouseTails => "3D film: Mouse tails",
+            SpaceRings => "Space rings",
+            Beginners => "Beginners",
+            LimPoweredLaunch => "LIM powered launch",
+            FilmThrillRiders => "Film: Thrill riders",
+            Film3DStormChasers => "3D film: Storm chasers",
+            Film3DSpaceRaiders => "3D film: Space raiders",
+            Intense => "Intense",
+            Berserk => "Berserk",
+            HauntedHouse => "Haunted house",
+            Circus => "Circus",
+            DownwardLaunch => "Downward launch",
+            CrookedHouse => "Crooked house",
+            FreefallDrop => "Freefall drop",
+            ContinuousCircuitBlockSectioned => "Continuous circuit block sectioned mode",
+            PoweredLaunch => "Powered launch",
+            PoweredLaunchBlockSectioned => "Powered launch block sectioned mode",
+            Unknown => "Unknown"
+        }
+    }
+}
@@ -0,0 +1,640 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use crate::finance::{self, Finances};
+use crate::map::Map;
+use crate::object::{DatObject, ObjectEntry, RideObject};
+use crate::peep::{self, Guest, Peep, Staff};
+use crate::repository::ObjectRepository;
+use crate::sawyer::{self, Chunk, ChunkReader, ChunkWriter};
+use crate::rct::{calculate_price_orig, decrypt_money, encrypt_money, object_rating, ride_rating};
+use crate::ride::{self, Ride};
+use crate::strings::{self, Language};
+use crate::util::{u16_from_slice, u32_from_slice};
+
+// Offsets into the game data of a saved game (starting at the next free map element index)
+pub const SPRITES: usize = 0x00_0004;
+pub const INITIAL_CASH: usize = 0x27_1024;
+pub const CURRENT_LOAN: usize = 0x27_1028;
+pub const PARK_FLAGS: usize = 0x27_102C;
+pub const PARK_ENTRANCE_FEE: usize = 0x27_1030;
+pub const RESEARCHED_RIDE_TYPES: usize = 0x27_104C;
+pub const RESEARCHED_RIDE_ENTRIES: usize = 0x27_106C;
+pub const GUESTS_IN_PARK: usize = 0x27_148C;
+pub const EXPENDITURE_TABLE: usize = 0x27_1490;
+pub const RESEARCHED_SCENERY_ITEMS: usize = 0x27_1818;
+pub const PARK_RATING: usize = 0x27_18F8;
+pub const MAXIMUM_LOAN: usize = 0x27_1D38;
+pub const CASH_HISTORY: usize = 0x27_1D74;
+pub const CURRENT_EXPENDITURE: usize = 0x27_1F74;
+pub const CURRENT_PROFIT: usize = 0x27_1F78;
+pub const WEEKLY_PROFIT_HISTORY: usize = 0x27_1F84;
+pub const PARK_VALUE: usize = 0x27_2184;
+pub const PARK_VALUE_HISTORY: usize = 0x27_2188;
+pub const TOTAL_ADMISSIONS: usize = 0x27_238C;
+pub const INCOME_FROM_ADMISSIONS: usize = 0x27_2390;
+pub const COMPANY_VALUE: usize = 0x27_2394;
+pub const CASH: usize = 0x27_2440;
+pub const CUSTOM_STRINGS: usize = 0x27_453C;
+pub const RIDES: usize = 0x27_C540;
+pub const GAME_DATA_SIZE: usize = 0x2E_8570;
+
+/// Index of the tile element chunk, after the object list and date.
+const MAP_CHUNK: usize = 2;
+
+/// Scenarios split the game data into chunks starting at these offsets, leaving out history and research.
+const SCENARIO_GAME_CHUNKS: [usize; 8] = [0, 0x27_148C, 0x27_1810, 0x27_18F8, 0x27_193A, 0x27_1F74, 0x27_2184, 0x27_2388];
+
+pub const PARK_FLAG_NO_MONEY: u32 = 1 << 11;
+pub const PARK_FLAG_FREE_ENTRY: u32 = 1 << 13;
+pub const PARK_FLAG_UNLOCK_ALL_PRICES: u32 = 1 << 31;
+
+/// Sizes of the researched bit sets, in bytes.
+const RESEARCHED_RIDES_SIZE: usize = 32;
+const RESEARCHED_SCENERY_SIZE: usize = 224;
+
+/// Highest ride ticket price the game allows (20.00).
+pub const MAX_RIDE_PRICE: u16 = 200;
+/// Highest park entrance fee the game allows (200.00).
+pub const MAX_ENTRANCE_FEE: u16 = 2000;
+/// Park ratings run from 0 to 999.
+pub const MAX_PARK_RATING: u16 = 999;
+
+/// Ticket price update made by `SavedGame::optimise_prices`.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceChange {
+    pub ride: u8,
+    pub old: u16,
+    pub new: u16
+}
+
+/// Saved game (SV6) or scenario (SC6) split into its chunks.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SavedGame {
+    pub header: Chunk,
+    /// Scenario details, only present in scenarios.
+    pub info: Option<Chunk>,
+    pub packed: Vec<DatObject>,
+    /// Object list, date, map and game data chunks.
+    pub chunks: Vec<Chunk>,
+    /// Difference between the stored checksum and the byte sum of the file.
+    pub checksum_salt: u32
+}
+
+impl SavedGame {
+    pub fn read<S: Read + Seek>(mut s: S) -> io::Result<Self> {
+        let end = s.seek(SeekFrom::End(0))?;
+        s.seek(SeekFrom::Start(0))?;
+        if end < 4 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "file too short"));
+        }
+        let mut raw = vec![0u8; end as usize];
+        s.read_exact(&mut raw)?;
+        let body = &raw[..raw.len()-4];
+        let stored = u32::from_le_bytes([raw[raw.len()-4], raw[raw.len()-3], raw[raw.len()-2], raw[raw.len()-1]]);
+        let checksum_salt = stored.wrapping_sub(sawyer::calculate_checksum(body));
+
+        let mut c = io::Cursor::new(body);
+        let header = ChunkReader::new(&mut c).read_chunk()?;
+        if header.data().len() < 4 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid header chunk"));
+        }
+        let info = match header.data()[0] {
+            1 => Some(ChunkReader::new(&mut c).read_chunk()?),
+            _ => None
+        };
+        let mut packed = Vec::new();
+        for _ in 0..u16_from_slice(header.data(), 2) {
+            packed.push(DatObject::read(&mut c)?);
+        }
+        let mut chunks = Vec::new();
+        while (c.position() as usize) < body.len() {
+            chunks.push(ChunkReader::new(&mut c).read_chunk()?);
+        }
+        Ok(SavedGame{header, info, packed, chunks, checksum_salt})
+    }
+
+    /// Encodes every chunk and signs the file, keeping the original checksum salt.
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut v = Vec::new();
+        {
+            let mut cw = ChunkWriter::new(&mut v);
+            cw.write_chunk(&self.header)?;
+            if let Some(i) = &self.info {
+                cw.write_chunk(i)?;
+            }
+        }
+        for o in &self.packed {
+            o.write(&mut v)?;
+        }
+        let mut cw = ChunkWriter::new(&mut v);
+        for c in &self.chunks {
+            cw.write_chunk(c)?;
+        }
+        let checksum = sawyer::calculate_checksum(&v).wrapping_add(self.checksum_salt);
+        v.extend_from_slice(&checksum.to_le_bytes());
+        w.write_all(&v)
+    }
+
+    pub fn is_scenario(&self) -> bool {
+        self.header.data()[0] == 1
+    }
+
+    /// Entries of the object list, including empty slots.
+    pub fn object_list(&self) -> Vec<ObjectEntry> {
+        match self.chunks.first() {
+            Some(c) => c.data().chunks_exact(16).map(ObjectEntry::from_slice).collect(),
+            None => Vec::new()
+        }
+    }
+
+    /// Map tiles, from the tile element chunk.
+    pub fn map(&self) -> Option<Map> {
+        self.chunks.get(MAP_CHUNK).map(|c| Map::from_data(c.data()))
+    }
+
+    /// Objects used by the park.
+    pub fn objects(&self) -> Vec<ObjectEntry> {
+        self.object_list().into_iter().filter(|e| !e.is_empty()).collect()
+    }
+
+    /// Embeds every custom object the park uses that is not packed yet, taking them from `repo`.
+    /// Returns the objects that could not be found.
+    pub fn pack_objects(&mut self, repo: &ObjectRepository) -> io::Result<Vec<ObjectEntry>> {
+        let mut missing = Vec::new();
+        for e in self.objects().into_iter().filter(ObjectEntry::is_custom) {
+            if self.packed.iter().any(|o| o.entry.matches(&e)) {
+                continue;
+            }
+            match repo.load(&e)? {
+                Some(o) => self.packed.push(o),
+                None => missing.push(e)
+            }
+        }
+        let n = self.packed.len() as u16;
+        self.header.data_mut()[2..4].copy_from_slice(&n.to_le_bytes());
+        Ok(missing)
+    }
+
+    /// Finds the chunk containing a range of game data, returning its index and the offset inside it.
+    fn locate(&self, off: usize, len: usize) -> Option<(usize, usize)> {
+        // Object list, date and map come before the game data
+        if self.is_scenario() {
+            let i = SCENARIO_GAME_CHUNKS.iter().rposition(|&s| s <= off)?;
+            let at = off - SCENARIO_GAME_CHUNKS[i];
+            let c = self.chunks.get(3 + i)?;
+            if at + len <= c.data().len() { Some((3 + i, at)) } else { None }
+        } else {
+            let c = self.chunks.get(3)?;
+            if off + len <= c.data().len() { Some((3, off)) } else { None }
+        }
+    }
+
+    /// Offset into the game data where a chunk starts, for chunks holding game data.
+    pub fn game_data_offset(&self, chunk: usize) -> Option<usize> {
+        let i = chunk.checked_sub(3)?;
+        if self.is_scenario() {
+            SCENARIO_GAME_CHUNKS.get(i).copied()
+        } else if i == 0 {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// Bytes of the game data, or None if they are not stored in the file.
+    pub fn game_data(&self, off: usize, len: usize) -> Option<&[u8]> {
+        let (i, at) = self.locate(off, len)?;
+        Some(&self.chunks[i].data()[at..at+len])
+    }
+
+    pub fn game_data_mut(&mut self, off: usize, len: usize) -> Option<&mut [u8]> {
+        let (i, at) = self.locate(off, len)?;
+        Some(&mut self.chunks[i].data_mut()[at..at+len])
+    }
+
+    pub fn read_u16(&self, off: usize) -> Option<u16> {
+        self.game_data(off, 2).map(|b| u16_from_slice(b, 0))
+    }
+    pub fn read_u32(&self, off: usize) -> Option<u32> {
+        self.game_data(off, 4).map(|b| u32_from_slice(b, 0))
+    }
+    pub fn write_u16(&mut self, off: usize, v: u16) -> Option<()> {
+        self.game_data_mut(off, 2)?.copy_from_slice(&v.to_le_bytes());
+        Some(())
+    }
+    pub fn write_u32(&mut self, off: usize, v: u32) -> Option<()> {
+        self.game_data_mut(off, 4)?.copy_from_slice(&v.to_le_bytes());
+        Some(())
+    }
+
+    /// Elapsed months since the park opened, or 0 if the date chunk is missing or too short.
+    pub fn months(&self) -> u16 {
+        self.chunks.get(1).and_then(|c| c.data().get(..2)).map_or(0, |b| u16_from_slice(b, 0))
+    }
+
+    pub fn cash(&self) -> Option<i32> {
+        self.read_u32(CASH).map(|c| decrypt_money(c) as i32)
+    }
+    pub fn set_cash(&mut self, v: i32) -> Option<()> {
+        self.write_u32(CASH, encrypt_money(v as u32))
+    }
+    pub fn initial_cash(&self) -> Option<i32> {
+        self.read_u32(INITIAL_CASH).map(|v| v as i32)
+    }
+    pub fn loan(&self) -> Option<i32> {
+        self.read_u32(CURRENT_LOAN).map(|v| v as i32)
+    }
+    pub fn set_loan(&mut self, v: i32) -> Option<()> {
+        self.write_u32(CURRENT_LOAN, v as u32)
+    }
+    pub fn max_loan(&self) -> Option<i32> {
+        self.read_u32(MAXIMUM_LOAN).map(|v| v as i32)
+    }
+    pub fn set_max_loan(&mut self, v: i32) -> Option<()> {
+        self.write_u32(MAXIMUM_LOAN, v as u32)
+    }
+    pub fn entrance_fee(&self) -> Option<u16> {
+        self.read_u16(PARK_ENTRANCE_FEE)
+    }
+    pub fn set_entrance_fee(&mut self, v: u16) -> Option<()> {
+        self.write_u16(PARK_ENTRANCE_FEE, v)
+    }
+    pub fn park_rating(&self) -> Option<u16> {
+        self.read_u16(PARK_RATING)
+    }
+    pub fn set_park_rating(&mut self, v: u16) -> Option<()> {
+        self.write_u16(PARK_RATING, v)
+    }
+    pub fn park_flags(&self) -> Option<u32> {
+        self.read_u32(PARK_FLAGS)
+    }
+    pub fn set_park_flags(&mut self, v: u32) -> Option<()> {
+        self.write_u32(PARK_FLAGS, v)
+    }
+    pub fn guests_in_park(&self) -> Option<u16> {
+        self.read_u16(GUESTS_IN_PARK)
+    }
+
+    /// Player-entered string (ids 0x8000-0x8FFF), such as a custom ride name.
+    pub fn user_string(&self, id: u16) -> Option<String> {
+        if !(0x8000..0x9000).contains(&id) {
+            return None;
+        }
+        let b = self.game_data(CUSTOM_STRINGS + usize::from(id % 1024) * 32, 32)?;
+        let ln = b.iter().position(|&c| c == 0).unwrap_or(b.len());
+        Some(strings::decode(&b[..ln], Language::EnglishUK))
+    }
+
+    pub fn rides(&self) -> Vec<Ride> {
+        (0..ride::MAX_RIDES)
+            .filter_map(|i| Ride::from_slice(i as u8, self.game_data(RIDES + i * ride::RIDE_SIZE, ride::RIDE_SIZE)?))
+            .collect()
+    }
+
+    pub fn ride_name(&self, r: &Ride) -> String {
+        self.user_string(r.name).unwrap_or_else(|| format!("Ride {} (type 0x{:02X})", r.name_arguments >> 16, r.ride_type))
+    }
+
+    /// Guests and staff, in sprite order.
+    pub fn peeps(&self) -> Vec<Peep> {
+        (0..peep::MAX_SPRITES)
+            .filter_map(|i| Peep::from_slice(self.game_data(SPRITES + i * peep::SPRITE_SIZE, peep::SPRITE_SIZE)?))
+            .collect()
+    }
+
+    pub fn guests(&self) -> Vec<Guest> {
+        self.peeps().into_iter().filter_map(|p| match p {
+            Peep::Guest(g) => Some(g),
+            Peep::Staff(_) => None
+        }).collect()
+    }
+
+    pub fn staff(&self) -> Vec<Staff> {
+        self.peeps().into_iter().filter_map(|p| match p {
+            Peep::Staff(s) => Some(s),
+            Peep::Guest(_) => None
+        }).collect()
+    }
+
+    pub fn guest_name(&self, g: &Guest) -> String {
+        self.user_string(g.name).unwrap_or_else(|| format!("Guest {}", g.id))
+    }
+
+    pub fn staff_name(&self, s: &Staff) -> String {
+        self.user_string(s.name).unwrap_or_else(|| format!("{} {}", s.staff_type.name(), s.id))
+    }
+
+    /// Indices of the bits set in a researched bit set.
+    fn researched(&self, off: usize, len: usize) -> Vec<usize> {
+        self.game_data(off, len).map_or_else(Vec::new, |b| {
+            (0..len * 8).filter(|&i| b[i / 8] & (1 << (i % 8)) != 0).collect()
+        })
+    }
+
+    /// Ride types which have been invented.
+    pub fn researched_ride_types(&self) -> Vec<u8> {
+        self.researched(RESEARCHED_RIDE_TYPES, RESEARCHED_RIDES_SIZE).into_iter().map(|i| i as u8).collect()
+    }
+
+    /// Ride objects which have been invented, as indices into the object list.
+    pub fn researched_ride_entries(&self) -> Vec<u8> {
+        self.researched(RESEARCHED_RIDE_ENTRIES, RESEARCHED_RIDES_SIZE).into_iter().map(|i| i as u8).collect()
+    }
+
+    /// Scenery items which have been invented, numbered across all scenery object types.
+    pub fn researched_scenery_items(&self) -> Vec<u16> {
+        self.researched(RESEARCHED_SCENERY_ITEMS, RESEARCHED_SCENERY_SIZE).into_iter().map(|i| i as u16).collect()
+    }
+
+    /// Cash, loan, values and finance histories; None if the park's money is not stored.
+    pub fn finances(&self) -> Option<Finances> {
+        let graph = |off| self.game_data(off, finance::GRAPH_SIZE * 4).map(finance::read_graph).unwrap_or_default();
+        let table_size = finance::EXPENDITURE_MONTHS * finance::EXPENDITURE_TYPES * 4;
+        let expenditure = self.game_data(EXPENDITURE_TABLE, table_size).map(|b| {
+            b.chunks_exact(finance::EXPENDITURE_TYPES * 4)
+                .map(|m| {
+                    let mut month = [0i32; finance::EXPENDITURE_TYPES];
+                    for (i, v) in month.iter_mut().enumerate() {
+                        *v = u32_from_slice(m, i * 4) as i32;
+                    }
+                    month
+                })
+                .collect()
+        }).unwrap_or_default();
+        Some(Finances{
+            cash: self.cash()?,
+            loan: self.loan()?,
+            max_loan: self.max_loan()?,
+            park_value: self.read_u32(PARK_VALUE)? as i32,
+            company_value: self.read_u32(COMPANY_VALUE)? as i32,
+            current_expenditure: self.read_u32(CURRENT_EXPENDITURE)? as i32,
+            current_profit: self.read_u32(CURRENT_PROFIT)? as i32,
+            total_admissions: self.read_u32(TOTAL_ADMISSIONS)?,
+            income_from_admissions: self.read_u32(INCOME_FROM_ADMISSIONS)? as i32,
+            expenditure,
+            cash_history: graph(CASH_HISTORY),
+            weekly_profit_history: graph(WEEKLY_PROFIT_HISTORY),
+            park_value_history: graph(PARK_VALUE_HISTORY)
+        })
+    }
+
+    /// Decoded ride object a ride is built with, when it is packed in the save.
+    pub fn ride_object(&self, r: &Ride) -> Option<RideObject> {
+        let entry = self.object_list().into_iter().take(128).nth(usize::from(r.subtype)).filter(|e| !e.is_empty())?;
+        self.packed.iter().find(|o| o.entry.matches(&entry))?.ride_object()?.ok()
+    }
+
+    /// Price multipliers of a ride, from its ride object when it is packed and from its ride
+    /// type otherwise.
+    pub fn price_multipliers(&self, r: &Ride) -> (i32, i32, i32) {
+        match self.ride_object(r) {
+            Some(o) => object_rating(&o, r.ride_type),
+            None => ride_rating(r.ride_type)
+        }
+    }
+
+    pub fn set_ride_price(&mut self, index: u8, price: u16) -> Option<()> {
+        self.write_u16(RIDES + usize::from(index) * ride::RIDE_SIZE + 0x138, price)
+    }
+
+    /// Whether guests pay for rides: parks without money or with paid entry keep rides free.
+    pub fn ride_prices_unlocked(&self) -> bool {
+        let f = self.park_flags().unwrap_or(0);
+        f & PARK_FLAG_NO_MONEY == 0 && f & (PARK_FLAG_FREE_ENTRY | PARK_FLAG_UNLOCK_ALL_PRICES) != 0
+    }
+
+    /// Sets every rated ride to its suggested ticket price, capped at the game's maximum.
+    /// Shops and stalls, and parks where ride prices are locked, are left unchanged.
+    pub fn optimise_prices(&mut self) -> Vec<PriceChange> {
+        if !self.ride_prices_unlocked() {
+            return Vec::new();
+        }
+        let months = self.months();
+        let mut changes = Vec::new();
+        for r in self.rides() {
+            let rating = self.price_multipliers(&r);
+            if !r.is_rated() || rating == (0, 0, 0) {
+                continue;
+            }
+            let p = calculate_price_orig(rating, r.excitement, r.intensity, r.nausea, r.age(months));
+            let new = p.clamp(0, i32::from(MAX_RIDE_PRICE)) as u16;
+            if self.set_ride_price(r.index, new).is_some() {
+                changes.push(PriceChange{ride: r.index, old: r.price, new});
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Cursor;
+    use crate::sawyer::Encoding;
+
+    fn entry(flags: u32, name: &[u8; 8]) -> ObjectEntry {
+        ObjectEntry{flags, name: *name, checksum: 0x1234_5678}
+    }
+
+    /// Saved game using `objects`, with an empty slot at the end of the list.
+    fn save(objects: &[ObjectEntry], packed: Vec<DatObject>) -> SavedGame {
+        let mut header = vec![0u8; 0x20];
+        header[2..4].copy_from_slice(&(packed.len() as u16).to_le_bytes());
+        header[4..8].copy_from_slice(&120_001u32.to_le_bytes());
+        let mut list = Vec::new();
+        for e in objects {
+            list.extend_from_slice(&e.flags.to_le_bytes());
+            list.extend_from_slice(&e.name);
+            list.extend_from_slice(&e.checksum.to_le_bytes());
+        }
+        list.extend_from_slice(&[0xFF; 16]);
+        let mut game_data = vec![0; GAME_DATA_SIZE];
+        for i in 0..ride::MAX_RIDES {
+            game_data[RIDES + i * ride::RIDE_SIZE] = 0xFF;
+        }
+        SavedGame{
+            header: Chunk::new(Encoding::RLE, header),
+            info: None,
+            packed,
+            chunks: vec![
+                Chunk::new(Encoding::RLE, list),
+                Chunk::new(Encoding::RLE, vec![40, 0, 0, 0]),
+                Chunk::new(Encoding::RLECompressed, vec![0; 64]),
+                Chunk::new(Encoding::RLE, game_data)
+            ],
+            checksum_salt: 105_831
+        }
+    }
+
+    /// Writes an open ride with the given ratings and ticket price into slot `index`.
+    fn add_ride(sg: &mut SavedGame, index: u8, ride_type: u8, ratings: [u16; 3], price: u16) {
+        let b = sg.game_data_mut(RIDES + usize::from(index) * ride::RIDE_SIZE, ride::RIDE_SIZE).unwrap();
+        b.iter_mut().for_each(|x| *x = 0);
+        b[0x000] = ride_type;
+        b[0x001] = 0xFF;
+        b[0x049] = ride::STATUS_OPEN;
+        b[0x138..0x13A].copy_from_slice(&price.to_le_bytes());
+        for (i, r) in ratings.iter().enumerate() {
+            b[0x140 + i * 2..0x142 + i * 2].copy_from_slice(&r.to_le_bytes());
+        }
+    }
+
+    /// Scenario whose game data chunks hold the stored parts, with most of the expenditure and
+    /// research chunk left out.
+    fn scenario() -> SavedGame {
+        let mut sg = save(&[], Vec::new());
+        sg.header.data_mut()[0] = 1;
+        sg.info = Some(Chunk::new(Encoding::RLE, vec![0; 0x198]));
+        sg.chunks.truncate(3);
+        for (i, &start) in SCENARIO_GAME_CHUNKS.iter().enumerate() {
+            let end = SCENARIO_GAME_CHUNKS.get(i + 1).copied().unwrap_or(GAME_DATA_SIZE);
+            let len = if i == 1 { 4 } else { end - start };
+            sg.chunks.push(Chunk::new(Encoding::RLE, vec![0; len]));
+        }
+        sg
+    }
+
+    fn reread(sg: &SavedGame) -> SavedGame {
+        let mut v = Vec::new();
+        sg.write(&mut v).unwrap();
+        SavedGame::read(Cursor::new(v)).unwrap()
+    }
+
+    #[test]
+    fn save_round_trip() {
+        let custom = entry(0x0000_0000, b"MYCOASTR");
+        let packed = DatObject{entry: custom, encoding: Encoding::Rotate, data: vec![1, 2, 3, 4]};
+        let sg = save(&[entry(0x0000_8000, b"WOODCARS"), custom], vec![packed]);
+        let mut v = Vec::new();
+        sg.write(&mut v).unwrap();
+        let body = v.len() - 4;
+        let stored = u32_from_slice(&v, body);
+        assert_eq!(stored, sawyer::calculate_checksum(&v[..body]).wrapping_add(105_831));
+
+        let r = SavedGame::read(Cursor::new(&v)).unwrap();
+        assert!(!r.is_scenario());
+        assert_eq!(r.checksum_salt, 105_831);
+        assert_eq!(r.objects(), [entry(0x0000_8000, b"WOODCARS"), custom]);
+        assert_eq!(r.object_list().len(), 3);
+        assert_eq!(r.packed.len(), 1);
+        assert_eq!((r.packed[0].entry, r.packed[0].encoding, &r.packed[0].data), (custom, Encoding::Rotate, &vec![1, 2, 3, 4]));
+        for (a, b) in r.chunks.iter().zip(&sg.chunks) {
+            assert_eq!((a.encoding(), a.data()), (b.encoding(), b.data()));
+        }
+        assert_eq!(r.months(), 40);
+        let mut again = Vec::new();
+        r.write(&mut again).unwrap();
+        assert_eq!(again, v);
+    }
+
+    #[test]
+    fn truncated_saves() {
+        let mut v = Vec::new();
+        save(&[], Vec::new()).write(&mut v).unwrap();
+        assert!(SavedGame::read(Cursor::new(&v[..2])).is_err());
+        assert!(SavedGame::read(Cursor::new(&v[..40])).is_err());
+        assert!(reread(&save(&[], Vec::new())).packed.is_empty());
+    }
+
+    #[test]
+    fn packs_missing_custom_objects() {
+        let dir = std::env::temp_dir().join(format!("rct2read-test-pack-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let (found, packed, missing) = (entry(0, b"MYCOASTR"), entry(0, b"MYSHOP  "), entry(0, b"GONE    "));
+        let obj = DatObject{entry: found, encoding: Encoding::RLE, data: vec![5; 40]};
+        obj.write(File::create(dir.join("MYCOASTR.DAT")).unwrap()).unwrap();
+        let repo = ObjectRepository::scan(&dir).unwrap();
+
+        let already = DatObject{entry: packed, encoding: Encoding::None, data: vec![1]};
+        let mut sg = save(&[entry(0x0000_0080, b"WOODCARS"), found, packed, missing], vec![already]);
+        assert_eq!(sg.pack_objects(&repo).unwrap(), [missing]);
+        assert_eq!(sg.packed.iter().map(|o| o.entry).collect::<Vec<_>>(), [packed, found]);
+        assert_eq!(sg.packed[1].data, obj.data);
+        let r = reread(&sg);
+        assert_eq!(u16_from_slice(r.header.data(), 2), 2);
+        assert_eq!(r.packed.len(), 2);
+        assert_eq!(sg.pack_objects(&repo).unwrap(), [missing]);
+        assert_eq!(sg.packed.len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn edits_park_values() {
+        let mut sg = save(&[], Vec::new());
+        sg.set_cash(-12_345).unwrap();
+        sg.set_loan(50_000).unwrap();
+        sg.set_max_loan(100_000).unwrap();
+        sg.set_entrance_fee(MAX_ENTRANCE_FEE).unwrap();
+        sg.set_park_rating(MAX_PARK_RATING).unwrap();
+        sg.set_park_flags(PARK_FLAG_FREE_ENTRY).unwrap();
+        assert_eq!(sg.read_u32(CASH), Some(encrypt_money((-12_345i32) as u32)));
+        let r = reread(&sg);
+        assert_eq!(r.cash(), Some(-12_345));
+        assert_eq!((r.loan(), r.max_loan()), (Some(50_000), Some(100_000)));
+        assert_eq!(r.entrance_fee(), Some(2000));
+        assert_eq!(r.park_rating(), Some(999));
+        assert_eq!(r.park_flags(), Some(PARK_FLAG_FREE_ENTRY));
+    }
+
+    #[test]
+    fn edits_scenario_chunks() {
+        let mut sg = scenario();
+        assert!(sg.is_scenario());
+        assert_eq!(sg.game_data_offset(4), Some(0x27_148C));
+        assert_eq!(sg.game_data_offset(2), None);
+        sg.set_cash(5000).unwrap();
+        sg.set_park_rating(640).unwrap();
+        assert_eq!(u16_from_slice(sg.chunks[3 + 3].data(), PARK_RATING - 0x27_18F8), 640);
+        // Expenditure history is not stored in scenarios
+        assert!(sg.game_data(EXPENDITURE_TABLE, 4).is_none());
+        assert!(sg.write_u32(EXPENDITURE_TABLE, 1).is_none());
+        let r = reread(&sg);
+        assert!(r.info.is_some());
+        assert_eq!((r.cash(), r.park_rating()), (Some(5000), Some(640)));
+    }
+
+    #[test]
+    fn missing_game_data() {
+        let mut sg = save(&[], Vec::new());
+        sg.chunks.truncate(3);
+        assert_eq!(sg.cash(), None);
+        assert!(sg.set_entrance_fee(10).is_none());
+        assert_eq!(sg.months(), 40);
+        sg.chunks[1].data_mut().truncate(1);
+        assert_eq!(sg.months(), 0);
+    }
+
+    #[test]
+    fn optimises_ride_prices() {
+        let mut sg = save(&[], Vec::new());
+        sg.set_park_flags(PARK_FLAG_FREE_ENTRY).unwrap();
+        add_ride(&mut sg, 0, 0x34, [650, 520, 300], 10);
+        add_ride(&mut sg, 1, 0x34, [5000, 900, 300], 10);
+        add_ride(&mut sg, 2, 0x1C, [0, 0, 0], 15);
+        add_ride(&mut sg, 3, 0x34, [ride::RATING_UNDEFINED; 3], 10);
+        let changes = sg.optimise_prices();
+        let by_ride: Vec<_> = changes.iter().map(|c| (c.ride, c.old, c.new)).collect();
+        // 40 months old, so the wooden coaster is priced at half its new value
+        assert_eq!(by_ride, [(0, 10, 75), (1, 10, MAX_RIDE_PRICE)]);
+        let prices: Vec<_> = reread(&sg).rides().iter().map(|r| r.price).collect();
+        assert_eq!(prices, [75, MAX_RIDE_PRICE, 15, 10]);
+    }
+
+    #[test]
+    fn locked_ride_prices_are_kept() {
+        for &flags in &[0, PARK_FLAG_FREE_ENTRY | PARK_FLAG_NO_MONEY] {
+            let mut sg = save(&[], Vec::new());
+            sg.set_park_flags(flags).unwrap();
+            add_ride(&mut sg, 0, 0x34, [650, 520, 300], 10);
+            assert!(!sg.ride_prices_unlocked());
+            assert!(sg.optimise_prices().is_empty());
+            assert_eq!(sg.rides()[0].price, 10);
+        }
+        let mut sg = save(&[], Vec::new());
+        sg.set_park_flags(PARK_FLAG_UNLOCK_ALL_PRICES).unwrap();
+        assert!(sg.ride_prices_unlocked());
+    }
+}
@@ -1,8 +1,11 @@
-use std::io::{self, Read, Seek, SeekFrom};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use crate::rctrle;
 use crate::util;
 use crate::util::DSer;
 
-#[derive(Copy, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Encoding {
     None,
     RLE,
@@ -24,6 +27,38 @@ impl From<u8> for Encoding {
     }
 }
 
+impl From<Encoding> for u8 {
+    fn from(e: Encoding) -> Self {
+        use Encoding::*;
+        match e {
+            None => 0,
+            RLE => 1,
+            RLECompressed => 2,
+            Rotate => 3,
+            Unknown => 0xFF
+        }
+    }
+}
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        use Encoding::*;
+        match self {
+            None => "none",
+            RLE => "rle",
+            RLECompressed => "rle-compressed",
+            Rotate => "rotate",
+            Unknown => "unknown"
+        }
+    }
+
+    /// Parses a name given by `name`.
+    pub fn from_name(s: &str) -> Option<Self> {
+        use Encoding::*;
+        [None, RLE, RLECompressed, Rotate].iter().copied().find(|e| e.name() == s)
+    }
+}
+
 pub struct ChunkHeader {
     enc: Encoding,
     len: u32
@@ -37,7 +72,10 @@ impl util::DeSerializable for ChunkHeader {
     }
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Chunk {
+    #[cfg_attr(feature = "serde", serde(with = "crate::util::hex_bytes"))]
     data: Vec<u8>,
     enc: Encoding
 }
@@ -49,9 +87,15 @@ impl Chunk {
     pub fn data(&self) -> &Vec<u8> {
         &self.data
     }
+    pub fn data_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.data
+    }
     pub fn encoding(&self) -> Encoding {
         self.enc
     }
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
 }
 
 pub struct ChunkReader<S: Read + Seek> {
@@ -63,90 +107,86 @@ impl <S: Read + Seek> ChunkReader<S> {
         ChunkReader{s}
     }
 
-    fn get_position(&mut self) -> io::Result<u64> {
-        self.s.seek(SeekFrom::Current(0))
-    }
-
     pub fn skip_chunk(&mut self) -> io::Result<()> {
         let header = self.s.read_dser::<ChunkHeader>()?;
         self.s.seek(SeekFrom::Current(header.len.into()))?;
         Ok(())
     }
 
-    pub fn read_chunk(&mut self) -> io::Result<()> {
-        //let orig_pos = self.get_position()?;
+    pub fn read_chunk(&mut self) -> io::Result<Chunk> {
         let header = self.s.read_dser::<ChunkHeader>()?;
-        self.s.read_bytes(header.len as usize)?;
-        Ok(())
+        // Read through `take` so a corrupt length cannot allocate more than the stream holds
+        let mut raw = Vec::new();
+        (&mut self.s).take(header.len.into()).read_to_end(&mut raw)?;
+        if raw.len() != header.len as usize {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "chunk extends past the end of the data"));
+        }
+        let data = self.decode_chunk(&header, raw)?;
+        Ok(Chunk::new(header.enc, data))
     }
 
-    //pub fn read_chunk_to<T>(&mut self, d: *mut T) {}
-
-    fn decode_chunk(&self, h: &ChunkHeader, from: &[u8], to: &mut Vec<u8>) {
+    fn decode_chunk(&self, h: &ChunkHeader, mut from: Vec<u8>) -> io::Result<Vec<u8>> {
         use Encoding::*;
         match h.enc {
-            None => {
-                to.copy_from_slice(from);
-            },
+            None => Ok(from),
             RLE => {
-                self.decode_chunk_rle(from, to);
+                let mut to = Vec::new();
+                rctrle::Reader::new(&from[..]).read_to_end(&mut to)?;
+                Ok(to)
             },
             RLECompressed => {
-                self.decode_chunk_rle_repeat(from, to);
+                let mut to = Vec::new();
+                rctrle::Reader::new(&from[..]).read_to_end(&mut to)?;
+                rctrle::decompress(&to)
             },
             Rotate => {
-                self.decode_chunk_rotate(from, to);
+                rctrle::rotate_bytes(&mut from);
+                Ok(from)
             },
-            _ => {}
+            Unknown => Err(io::Error::new(io::ErrorKind::InvalidData, "unknown chunk encoding"))
         }
     }
-    fn decode_chunk_rle_repeat(&self, from: &[u8], to: &mut Vec<u8>) {
-        let mut imbuf = Vec::new();
-        self.decode_chunk_rle(from, &mut imbuf);
-        self.decode_chunk_repeat(&imbuf, to);
-    }
-    fn decode_chunk_rle(&self, from: &[u8], to: &mut Vec<u8>) {
-        let mut i = 0;
-        while i < from.len() {
-            let x = from[i];
-            if x & 128 != 0 {
-                
-            } else {
-                to.extend_from_slice(&from[i+1..i+1+x as usize]);
-                i += x as usize + 1;
-            }
-            i += 1;
-        }
+}
+
+pub struct ChunkWriter<W: Write> {
+    w: W
+}
+
+impl <W: Write> ChunkWriter<W> {
+    pub fn new(w: W) -> Self {
+        ChunkWriter{w}
     }
-    fn decode_chunk_repeat(&self, from: &[u8], to: &mut Vec<u8>) {
-        let mut i = 0;
-        while i < from.len() {
-            let x = from[i];
-            if x == 0xFF {
-                to.push(from[i+1]);
-                i += 2;
-            } else {
-                let ln = i32::from(x & 7) + 1;
-                let sx = (to.len() as i32) + i32::from(x >> 3) - 32;
-                let cto = to.clone();
-                let iter= cto.iter().skip(sx as usize).take(ln as usize);//.collect_into(to);
-                to.extend(iter);
-                //for nx in sx..(sx+ln) {to.push(to[nx as usize]);}
-                i += 1;
-            }
-        }
+
+    pub fn write_chunk(&mut self, c: &Chunk) -> io::Result<()> {
+        let raw = encode_chunk(c.enc, &c.data)?;
+        self.w.write_all(&[c.enc.into()])?;
+        self.w.write_all(&(raw.len() as u32).to_le_bytes())?;
+        self.w.write_all(&raw)
     }
-    fn decode_chunk_rotate(&self, from: &[u8], to: &mut Vec<u8>) {
-        let mut i = 1;
-        for x in from {
-            to.push(x.rotate_right(i));
-            i = (i + 2) % 8;
-        }
+}
+
+pub fn encode_chunk(enc: Encoding, data: &[u8]) -> io::Result<Vec<u8>> {
+    use Encoding::*;
+    match enc {
+        None => Ok(data.to_vec()),
+        RLE => Ok(rctrle::encode(data)),
+        RLECompressed => Ok(rctrle::encode(&rctrle::compress(data))),
+        Rotate => {
+            let mut v = data.to_vec();
+            rctrle::rotate_bytes_left(&mut v);
+            Ok(v)
+        },
+        Unknown => Err(io::Error::new(io::ErrorKind::InvalidInput, "unknown chunk encoding"))
     }
 }
 
+/// Sum of all bytes, as stored at the end of saved games and scenarios.
+pub fn calculate_checksum(b: &[u8]) -> u32 {
+    b.iter().fold(0u32, |c, &x| c.wrapping_add(u32::from(x)))
+}
+
 pub fn validate_checksum<S>(mut s: S) -> bool where S: Read + Seek {
-    let init_pos = s.seek(SeekFrom::Current(0)).unwrap();
+    let init_pos = s.stream_position().unwrap();
     let mut sz = s.seek(SeekFrom::End(0)).unwrap() - init_pos;
     if sz < 8 {
         return false;
@@ -156,18 +196,63 @@ pub fn validate_checksum<S>(mut s: S) -> bool where S: Read + Seek {
     let mut checksum = 0u32;
     while sz != 0 {
         let mut buf = [0u8; 4096];
-        let bs = sz.min(4096);
-        if s.read_exact(&mut buf[..]).is_err() {
+        let bs = sz.min(4096) as usize;
+        if s.read_exact(&mut buf[..bs]).is_err() {
             s.seek(SeekFrom::Start(init_pos)).unwrap();
             return false;
         }
-        for &x in buf.iter() {
-            checksum += u32::from(x);
+        for &x in buf[..bs].iter() {
+            checksum = checksum.wrapping_add(u32::from(x));
         }
-        sz -= bs;
+        sz -= bs as u64;
     }
     let mut chbuf = [0u8; 4];
     s.read_exact(&mut chbuf[..]).unwrap();
     let fch = u32::from_le_bytes(chbuf);
     checksum == fch
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn chunk_round_trip() {
+        let data: Vec<u8> = (0..600u32).map(|i| if i < 200 { 0 } else { (i % 13) as u8 }).collect();
+        for &enc in &[Encoding::None, Encoding::RLE, Encoding::RLECompressed, Encoding::Rotate] {
+            let mut v = Vec::new();
+            ChunkWriter::new(&mut v).write_chunk(&Chunk::new(enc, data.clone())).unwrap();
+            assert_eq!(v[0], u8::from(enc));
+            assert_eq!(u32::from_le_bytes([v[1], v[2], v[3], v[4]]) as usize, v.len() - 5);
+            let c = ChunkReader::new(Cursor::new(&v)).read_chunk().unwrap();
+            assert_eq!(c.encoding(), enc);
+            assert_eq!(c.data(), &data, "{}", enc.name());
+        }
+    }
+
+    #[test]
+    fn unknown_encoding() {
+        assert!(encode_chunk(Encoding::Unknown, &[1]).is_err());
+        let v = [9u8, 1, 0, 0, 0, 0];
+        assert!(ChunkReader::new(Cursor::new(&v)).read_chunk().is_err());
+    }
+
+    #[test]
+    fn chunk_length_past_the_end() {
+        let v = [0u8, 0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3];
+        let e = ChunkReader::new(Cursor::new(&v)).read_chunk().err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(ChunkReader::new(Cursor::new(&v[..3])).read_chunk().is_err());
+    }
+
+    #[test]
+    fn checksums() {
+        let mut v = vec![0xFFu8, 0x01, 0x10, 0x00, 0x20];
+        assert_eq!(calculate_checksum(&v), 0x130);
+        v.extend_from_slice(&0x130u32.to_le_bytes());
+        assert!(validate_checksum(Cursor::new(&v)));
+        v[0] = 0;
+        assert!(!validate_checksum(Cursor::new(&v)));
+    }
+}
@@ -0,0 +1,182 @@
+use encoding_rs::{Encoding, BIG5, EUC_KR, GBK, SHIFT_JIS, WINDOWS_1252};
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Language {
+    EnglishUK,
+    EnglishUS,
+    French,
+    German,
+    Spanish,
+    Italian,
+    Dutch,
+    Swedish,
+    Japanese,
+    Korean,
+    ChineseSimplified,
+    ChineseTraditional,
+    Portuguese,
+    Unknown
+}
+
+impl From<u8> for Language {
+    fn from(n: u8) -> Self {
+        use Language::*;
+        match n {
+            0 => EnglishUK,
+            1 => EnglishUS,
+            2 => French,
+            3 => German,
+            4 => Spanish,
+            5 => Italian,
+            6 => Dutch,
+            7 => Swedish,
+            8 => Japanese,
+            9 => Korean,
+            10 => ChineseSimplified,
+            11 => ChineseTraditional,
+            13 => Portuguese,
+            _ => Unknown
+        }
+    }
+}
+
+impl Language {
+    pub fn code(self) -> &'static str {
+        use Language::*;
+        match self {
+            EnglishUK => "en-GB",
+            EnglishUS => "en-US",
+            French => "fr-FR",
+            German => "de-DE",
+            Spanish => "es-ES",
+            Italian => "it-IT",
+            Dutch => "nl-NL",
+            Swedish => "sv-SE",
+            Japanese => "ja-JP",
+            Korean => "ko-KR",
+            ChineseSimplified => "zh-CN",
+            ChineseTraditional => "zh-TW",
+            Portuguese => "pt-BR",
+            Unknown => "und"
+        }
+    }
+
+    /// Code page used for double-byte characters, if the language has one.
+    fn multibyte(self) -> Option<&'static Encoding> {
+        use Language::*;
+        match self {
+            Japanese => Some(SHIFT_JIS),
+            Korean => Some(EUC_KR),
+            ChineseSimplified => Some(GBK),
+            ChineseTraditional => Some(BIG5),
+            _ => None
+        }
+    }
+}
+
+/// RCT2 glyphs placed over the upper half of Windows-1252.
+fn rct2_char(c: u8) -> Option<char> {
+    match c {
+        0x9F => Some('Ą'),
+        0xA0 => Some('▲'),
+        0xA2 => Some('Ć'),
+        0xA6 => Some('Ę'),
+        0xA7 => Some('Ł'),
+        0xAA => Some('▼'),
+        0xAC => Some('✓'),
+        0xAD => Some('✗'),
+        0xAF => Some('▶'),
+        0xB4 => Some('“'),
+        0xB5 => Some('€'),
+        0xBB => Some('▴'),
+        0xBC => Some('▾'),
+        0xBD => Some('◀'),
+        _ => None
+    }
+}
+
+/// Converts a string in the game's encoding to UTF-8.
+///
+/// A 0xFF byte starts a double-byte character (high byte first), which CJK
+/// languages use for code page characters.
+pub fn decode(b: &[u8], lang: Language) -> String {
+    if let Some(enc) = lang.multibyte() {
+        let mut mb = Vec::with_capacity(b.len());
+        let mut i = 0;
+        while i < b.len() {
+            if b[i] == 0xFF {
+                mb.extend_from_slice(b.get(i+1..i+3).unwrap_or(&[]));
+                i += 3;
+            } else {
+                mb.push(b[i]);
+                i += 1;
+            }
+        }
+        return enc.decode_without_bom_handling(&mb).0.into_owned();
+    }
+    let mut s = String::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i];
+        if c == 0xFF {
+            let hi = u32::from(*b.get(i+1).unwrap_or(&0));
+            let lo = u32::from(*b.get(i+2).unwrap_or(&0));
+            s.push(std::char::from_u32(hi << 8 | lo).unwrap_or(char::REPLACEMENT_CHARACTER));
+            i += 3;
+            continue;
+        }
+        match rct2_char(c) {
+            Some(ch) => s.push(ch),
+            None => s.push_str(&WINDOWS_1252.decode_without_bom_handling(&[c]).0)
+        }
+        i += 1;
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latin_text() {
+        assert_eq!(decode(b"Wooden Roller Coaster", Language::EnglishUK), "Wooden Roller Coaster");
+        // Windows-1252 outside the glyphs the game replaces
+        assert_eq!(decode(&[b'G', 0xE9, b'a', b'n', b't'], Language::French), "Géant");
+        assert_eq!(decode(&[0xDF], Language::German), "ß");
+    }
+
+    #[test]
+    fn rct2_glyphs() {
+        assert_eq!(decode(&[0xA0, 0xAA], Language::EnglishUK), "▲▼");
+        assert_eq!(decode(&[0xAC, 0xAD], Language::EnglishUK), "✓✗");
+        assert_eq!(decode(&[0xB5, b'5'], Language::EnglishUK), "€5");
+        assert_eq!(decode(&[0x9F, 0xA7], Language::Swedish), "ĄŁ");
+    }
+
+    #[test]
+    fn double_byte_characters() {
+        // Shift JIS 0x96D8, EUC-KR 0xB3AA, GBK 0xC4BE and Big5 0xA4EC
+        assert_eq!(decode(&[0xFF, 0x96, 0xD8], Language::Japanese), "木");
+        assert_eq!(decode(&[0xFF, 0xB3, 0xAA, b'!'], Language::Korean), "나!");
+        assert_eq!(decode(&[b'A', 0xFF, 0xC4, 0xBE], Language::ChineseSimplified), "A木");
+        assert_eq!(decode(&[0xFF, 0xA4, 0xEC], Language::ChineseTraditional), "木");
+    }
+
+    #[test]
+    fn unicode_escapes_outside_cjk() {
+        assert_eq!(decode(&[0xFF, 0x01, 0x41], Language::EnglishUK), "Ł");
+        // Truncated escapes at the end of a string do not fail the decoding
+        assert_eq!(decode(&[b'x', 0xFF], Language::EnglishUK), "x\u{0}");
+        assert_eq!(decode(&[b'x', 0xFF, 0x96], Language::Japanese), "x");
+    }
+
+    #[test]
+    fn language_ids() {
+        assert_eq!(Language::from(8), Language::Japanese);
+        assert_eq!(Language::from(12), Language::Unknown);
+        assert_eq!(Language::from(13), Language::Portuguese);
+        assert_eq!(Language::from(13).code(), "pt-BR");
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt::Write;
+use crate::mesh::{self, CentrelinePoint, Material};
+use crate::rct::segment_name;
+use crate::td6::TrackElement;
+use crate::track::{self, Slope};
+
+/// Pixels per tile.
+const SCALE: f32 = 24.0;
+const MARGIN: f32 = 40.0;
+
+/// Notable element highlighted on diagrams.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Marker {
+    Station,
+    Lift,
+    Brakes,
+    Inversion,
+    Drop
+}
+
+impl Marker {
+    fn colour(self) -> &'static str {
+        match self {
+            Marker::Station => "#d4a017",
+            Marker::Lift => "#555555",
+            Marker::Brakes => "#c00000",
+            Marker::Inversion => "#7030a0",
+            Marker::Drop => "#0070c0"
+        }
+    }
+}
+
+fn is_descending(kind: u8) -> bool {
+    track::geometry(kind).is_some_and(|g| g.start_height > g.end_height
+        || matches!(g.start_slope, Slope::Down25 | Slope::Down60 | Slope::Down90))
+}
+
+/// Elements worth labelling: the start of stations, lifts and drops, brakes and inversions.
+pub fn markers(elements: &[TrackElement]) -> Vec<(usize, Marker)> {
+    let mut v = Vec::new();
+    for (i, e) in elements.iter().enumerate() {
+        let g = match track::geometry(e.kind) {
+            Some(g) => g,
+            None => continue
+        };
+        let prev = i.checked_sub(1).map(|p| elements[p]);
+        if g.is_station() && !prev.is_some_and(|p| track::geometry(p.kind).is_some_and(|g| g.is_station())) {
+            v.push((i, Marker::Station));
+        } else if e.has_chain_lift() && !prev.is_some_and(|p| p.has_chain_lift()) {
+            v.push((i, Marker::Lift));
+        } else if matches!(e.kind, 0x63 | 0x84 | 0xD8) {
+            v.push((i, Marker::Brakes));
+        } else if g.is_inversion() {
+            v.push((i, Marker::Inversion));
+        } else if is_descending(e.kind) && !prev.is_some_and(|p| is_descending(p.kind)) {
+            v.push((i, Marker::Drop));
+        }
+    }
+    v
+}
+
+fn material_colour(m: Material) -> &'static str {
+    match m {
+        Material::Track => "#333333",
+        Material::Lift => "#888888",
+        Material::Station => "#d4a017"
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Draws points projected to 2D as coloured polylines with labelled markers.
+fn render(line: &[CentrelinePoint], points: &[(f32, f32)], elements: &[TrackElement], title: &str) -> String {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for &(x, y) in points {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    if points.is_empty() {
+        min_x = 0.0;
+        min_y = 0.0;
+        max_x = 0.0;
+        max_y = 0.0;
+    }
+    let px = |x: f32| (x - min_x) * SCALE + MARGIN;
+    let py = |y: f32| (y - min_y) * SCALE + MARGIN;
+    let (w, h) = ((max_x - min_x) * SCALE + MARGIN * 2.0, (max_y - min_y) * SCALE + MARGIN * 2.0);
+    let mut s = String::new();
+    writeln!(s, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="0 0 {:.0} {:.0}" font-family="sans-serif" font-size="10">"#, w, h, w, h).unwrap();
+    writeln!(s, r#"<text x="4" y="14" font-size="12">{}</text>"#, escape(title)).unwrap();
+    // One polyline per run of the same material
+    let mut i = 0;
+    while i + 1 < points.len() {
+        let m = line[i].material;
+        let mut j = i + 1;
+        while j + 1 < points.len() && line[j].material == m {
+            j += 1;
+        }
+        let pts = points[i..=j].iter().map(|&(x, y)| format!("{:.1},{:.1}", px(x), py(y))).collect::<Vec<_>>().join(" ");
+        writeln!(s, r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="3" stroke-linejoin="round"/>"#, pts, material_colour(m)).unwrap();
+        i = j;
+    }
+    for (idx, marker) in markers(elements) {
+        if let Some(k) = line.iter().position(|p| p.element == idx) {
+            let (x, y) = (px(points[k].0), py(points[k].1));
+            writeln!(s, r#"<circle cx="{:.1}" cy="{:.1}" r="4" fill="{}"/>"#, x, y, marker.colour()).unwrap();
+            writeln!(s, r#"<text x="{:.1}" y="{:.1}" fill="{}">{}</text>"#, x + 6.0, y - 6.0, marker.colour(), escape(segment_name(elements[idx].kind))).unwrap();
+        }
+    }
+    s.push_str("</svg>\n");
+    s
+}
+
+/// Top-down view of the layout.
+pub fn plan(elements: &[TrackElement]) -> String {
+    let line = mesh::centreline(elements, &track::walk(elements));
+    let points = line.iter().map(|p| (p.position[0], p.position[1])).collect::<Vec<_>>();
+    render(&line, &points, elements, "Plan")
+}
+
+/// Height profile of the layout along the distance travelled.
+pub fn elevation(elements: &[TrackElement]) -> String {
+    let line = mesh::centreline(elements, &track::walk(elements));
+    let mut dist = 0.0;
+    let mut points = Vec::with_capacity(line.len());
+    for (i, p) in line.iter().enumerate() {
+        if i > 0 {
+            let q = line[i - 1].position;
+            dist += ((p.position[0] - q[0]).powi(2) + (p.position[1] - q[1]).powi(2)).sqrt();
+        }
+        // SVG y grows downwards
+        points.push((dist, -p.position[2]));
+    }
+    render(&line, &points, elements, "Elevation")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn el(kind: u8, flags: u8) -> TrackElement {
+        TrackElement{kind, flags}
+    }
+
+    fn layout() -> Vec<TrackElement> {
+        let lift = TrackElement::FLAG_CHAIN_LIFT;
+        vec![el(0x02, 0), el(0x03, 0), el(0x06, lift), el(0x04, lift), el(0x09, 0), el(0x0C, 0), el(0x0A, 0), el(0x0F, 0), el(0x28, 0), el(0x63, 0)]
+    }
+
+    #[test]
+    fn marks_notable_elements() {
+        assert_eq!(markers(&layout()), [
+            (0, Marker::Station), (2, Marker::Lift), (5, Marker::Drop), (8, Marker::Inversion), (9, Marker::Brakes)
+        ]);
+        assert!(markers(&[el(0xFF, 0)]).is_empty());
+    }
+
+    #[test]
+    fn draws_plan() {
+        let svg = plan(&layout());
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(">Plan</text>"));
+        // Station, lift, then the rest of the track
+        assert_eq!(svg.matches("<polyline").count(), 3);
+        assert!(svg.contains(r##"stroke="#888888""##));
+        assert_eq!(svg.matches("<circle").count(), 5);
+    }
+
+    #[test]
+    fn draws_elevation() {
+        let svg = elevation(&layout());
+        assert!(svg.contains(">Elevation</text>"));
+        assert_eq!(svg.matches("<circle").count(), 5);
+        let empty = elevation(&[]);
+        assert!(empty.contains(r#"width="80" height="80""#));
+        assert!(!empty.contains("<polyline"));
+    }
+
+    #[test]
+    fn escapes_text() {
+        assert_eq!(escape("<a & b>"), "&lt;a &amp; b&gt;");
+    }
+}
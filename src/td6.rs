@@ -0,0 +1,436 @@
+use std::io::{self, Read, Write};
+use crate::object::ObjectEntry;
+use crate::rctrle;
+use crate::ride_type::RideType;
+use crate::util::{u16_from_slice, u32_from_slice};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackElement {
+    pub kind: u8,
+    pub flags: u8
+}
+
+impl TrackElement {
+    pub const FLAG_CHAIN_LIFT: u8 = 1 << 7;
+    pub const FLAG_INVERTED: u8 = 1 << 6;
+
+    pub fn has_chain_lift(&self) -> bool {
+        self.flags & Self::FLAG_CHAIN_LIFT != 0
+    }
+    pub fn is_inverted(&self) -> bool {
+        self.flags & Self::FLAG_INVERTED != 0
+    }
+    /// Colour scheme index (bits 4-5).
+    pub fn colour_scheme(&self) -> u8 {
+        (self.flags >> 4) & 3
+    }
+    /// Station index, brake speed or seat rotation depending on the element (bits 0-3).
+    pub fn extra(&self) -> u8 {
+        self.flags & 0x0F
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MazeElement {
+    pub x: i8,
+    pub y: i8,
+    pub maze_entry: u16
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntranceElement {
+    pub z: i8,
+    pub direction: u8,
+    pub x: i16,
+    pub y: i16
+}
+
+impl EntranceElement {
+    pub fn is_exit(&self) -> bool {
+        self.direction & 0x80 != 0
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneryElement {
+    pub entry: ObjectEntry,
+    pub x: i8,
+    pub y: i8,
+    pub z: i8,
+    pub flags: u8,
+    pub primary_colour: u8,
+    pub secondary_colour: u8
+}
+
+/// Decoded TD6 track design.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackDesign {
+    pub ride_type: u8,
+    pub vehicle_type: u8,
+    pub flags: u32,
+    pub ride_mode: u8,
+    pub version_and_colour_scheme: u8,
+    pub vehicle_colours: [[u8; 2]; 32],
+    pub pad_48: u8,
+    pub entrance_style: u8,
+    pub total_air_time: u8,
+    pub depart_flags: u8,
+    pub number_of_trains: u8,
+    pub cars_per_train: u8,
+    pub min_waiting_time: u8,
+    pub max_waiting_time: u8,
+    pub operation_setting: u8,
+    pub max_speed: i8,
+    pub average_speed: i8,
+    pub ride_length: u16,
+    pub max_positive_vertical_g: u8,
+    pub max_negative_vertical_g: i8,
+    pub max_lateral_g: u8,
+    pub inversions: u8,
+    pub drops: u8,
+    pub highest_drop_height: u8,
+    pub excitement: u8,
+    pub intensity: u8,
+    pub nausea: u8,
+    pub upkeep_cost: u16,
+    pub track_spine_colour: [u8; 4],
+    pub track_rail_colour: [u8; 4],
+    pub track_support_colour: [u8; 4],
+    pub flags2: u32,
+    pub vehicle_object: ObjectEntry,
+    pub space_required_x: u8,
+    pub space_required_y: u8,
+    pub vehicle_additional_colour: [u8; 32],
+    pub lift_hill_speed_num_circuits: u8,
+    pub elements: Vec<TrackElement>,
+    pub maze: Vec<MazeElement>,
+    pub entrances: Vec<EntranceElement>,
+    pub scenery: Vec<SceneryElement>,
+    /// Bytes after the scenery list, kept so unmodified designs are written back unchanged.
+    pub trailing: Vec<u8>
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl TrackDesign {
+    /// Reads an RLE encoded TD6 file, including its trailing checksum.
+    pub fn read<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut vf = Vec::new();
+        r.read_to_end(&mut vf)?;
+        if vf.len() < 4 {
+            return Err(invalid("track design too short"));
+        }
+        let mut v = Vec::new();
+        rctrle::Reader::new(&vf[0..vf.len()-4]).read_to_end(&mut v)?;
+        Self::from_data(&v)
+    }
+
+    /// Parses decoded TD6 data.
+    pub fn from_data(v: &[u8]) -> io::Result<Self> {
+        if v.len() < 0xA3 {
+            return Err(invalid("track design header too short"));
+        }
+        let mut vehicle_colours = [[0u8; 2]; 32];
+        for (i, c) in vehicle_colours.iter_mut().enumerate() {
+            c.copy_from_slice(&v[0x08+i*2..0x0A+i*2]);
+        }
+        let mut colours = [[0u8; 4]; 3];
+        for (i, c) in colours.iter_mut().enumerate() {
+            c.copy_from_slice(&v[0x60+i*4..0x64+i*4]);
+        }
+        let mut vehicle_additional_colour = [0u8; 32];
+        vehicle_additional_colour.copy_from_slice(&v[0x82..0xA2]);
+
+        let mut td = TrackDesign{
+            ride_type: v[0x00],
+            vehicle_type: v[0x01],
+            flags: u32_from_slice(v, 0x02),
+            ride_mode: v[0x06],
+            version_and_colour_scheme: v[0x07],
+            vehicle_colours,
+            pad_48: v[0x48],
+            entrance_style: v[0x49],
+            total_air_time: v[0x4A],
+            depart_flags: v[0x4B],
+            number_of_trains: v[0x4C],
+            cars_per_train: v[0x4D],
+            min_waiting_time: v[0x4E],
+            max_waiting_time: v[0x4F],
+            operation_setting: v[0x50],
+            max_speed: v[0x51] as i8,
+            average_speed: v[0x52] as i8,
+            ride_length: u16_from_slice(v, 0x53),
+            max_positive_vertical_g: v[0x55],
+            max_negative_vertical_g: v[0x56] as i8,
+            max_lateral_g: v[0x57],
+            inversions: v[0x58],
+            drops: v[0x59],
+            highest_drop_height: v[0x5A],
+            excitement: v[0x5B],
+            intensity: v[0x5C],
+            nausea: v[0x5D],
+            upkeep_cost: u16_from_slice(v, 0x5E),
+            track_spine_colour: colours[0],
+            track_rail_colour: colours[1],
+            track_support_colour: colours[2],
+            flags2: u32_from_slice(v, 0x6C),
+            vehicle_object: ObjectEntry::from_slice(&v[0x70..0x80]),
+            space_required_x: v[0x80],
+            space_required_y: v[0x81],
+            vehicle_additional_colour,
+            lift_hill_speed_num_circuits: v[0xA2],
+            elements: Vec::new(),
+            maze: Vec::new(),
+            entrances: Vec::new(),
+            scenery: Vec::new(),
+            trailing: Vec::new()
+        };
+
+        let at = |i: usize, n: usize| v.get(i..i+n).ok_or_else(|| invalid("unterminated track design list"));
+        let mut i = 0xA3;
+        if td.is_maze() {
+            // Maze tiles end with an empty element
+            while u32_from_slice(at(i, 4)?, 0) != 0 {
+                td.maze.push(MazeElement{x: v[i] as i8, y: v[i+1] as i8, maze_entry: u16_from_slice(v, i+2)});
+                i += 4;
+            }
+            i += 4;
+        } else {
+            while at(i, 1)?[0] != 0xFF {
+                let b = at(i, 2)?;
+                td.elements.push(TrackElement{kind: b[0], flags: b[1]});
+                i += 2;
+            }
+            i += 1;
+            while at(i, 1)?[0] != 0xFF {
+                let b = at(i, 6)?;
+                td.entrances.push(EntranceElement{
+                    z: b[0] as i8,
+                    direction: b[1],
+                    x: u16_from_slice(b, 2) as i16,
+                    y: u16_from_slice(b, 4) as i16
+                });
+                i += 6;
+            }
+            i += 1;
+        }
+        while at(i, 1)?[0] != 0xFF {
+            let b = at(i, 22)?;
+            td.scenery.push(SceneryElement{
+                entry: ObjectEntry::from_slice(&b[0..16]),
+                x: b[16] as i8,
+                y: b[17] as i8,
+                z: b[18] as i8,
+                flags: b[19],
+                primary_colour: b[20],
+                secondary_colour: b[21]
+            });
+            i += 22;
+        }
+        td.trailing = v[i+1..].to_vec();
+        Ok(td)
+    }
+
+    /// Serializes the design to decoded TD6 data.
+    pub fn to_data(&self) -> Vec<u8> {
+        let mut v = Vec::with_capacity(0xA3 + self.elements.len() * 2 + self.scenery.len() * 22 + 16);
+        v.push(self.ride_type);
+        v.push(self.vehicle_type);
+        v.extend_from_slice(&self.flags.to_le_bytes());
+        v.push(self.ride_mode);
+        v.push(self.version_and_colour_scheme);
+        for c in &self.vehicle_colours {
+            v.extend_from_slice(c);
+        }
+        v.extend_from_slice(&[
+            self.pad_48, self.entrance_style, self.total_air_time, self.depart_flags,
+            self.number_of_trains, self.cars_per_train, self.min_waiting_time, self.max_waiting_time,
+            self.operation_setting, self.max_speed as u8, self.average_speed as u8
+        ]);
+        v.extend_from_slice(&self.ride_length.to_le_bytes());
+        v.extend_from_slice(&[
+            self.max_positive_vertical_g, self.max_negative_vertical_g as u8, self.max_lateral_g,
+            self.inversions, self.drops, self.highest_drop_height,
+            self.excitement, self.intensity, self.nausea
+        ]);
+        v.extend_from_slice(&self.upkeep_cost.to_le_bytes());
+        v.extend_from_slice(&self.track_spine_colour);
+        v.extend_from_slice(&self.track_rail_colour);
+        v.extend_from_slice(&self.track_support_colour);
+        v.extend_from_slice(&self.flags2.to_le_bytes());
+        write_entry(&mut v, &self.vehicle_object);
+        v.push(self.space_required_x);
+        v.push(self.space_required_y);
+        v.extend_from_slice(&self.vehicle_additional_colour);
+        v.push(self.lift_hill_speed_num_circuits);
+
+        if self.is_maze() {
+            for m in &self.maze {
+                v.push(m.x as u8);
+                v.push(m.y as u8);
+                v.extend_from_slice(&m.maze_entry.to_le_bytes());
+            }
+            v.extend_from_slice(&[0; 4]);
+        } else {
+            for e in &self.elements {
+                v.push(e.kind);
+                v.push(e.flags);
+            }
+            v.push(0xFF);
+            for e in &self.entrances {
+                v.push(e.z as u8);
+                v.push(e.direction);
+                v.extend_from_slice(&e.x.to_le_bytes());
+                v.extend_from_slice(&e.y.to_le_bytes());
+            }
+            v.push(0xFF);
+        }
+        for s in &self.scenery {
+            write_entry(&mut v, &s.entry);
+            v.extend_from_slice(&[s.x as u8, s.y as u8, s.z as u8, s.flags, s.primary_colour, s.secondary_colour]);
+        }
+        v.push(0xFF);
+        v.extend_from_slice(&self.trailing);
+        v
+    }
+
+    /// Writes an RLE encoded TD6 file with its checksum.
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let enc = rctrle::encode(&self.to_data());
+        w.write_all(&enc)?;
+        w.write_all(&calculate_checksum(&enc).to_le_bytes())
+    }
+
+    pub fn is_maze(&self) -> bool {
+        RideType::from(self.ride_type).is_maze()
+    }
+
+    /// Colour scheme (bits 0-1 of the version byte).
+    pub fn colour_scheme(&self) -> u8 {
+        self.version_and_colour_scheme & 3
+    }
+    /// Lift hill speed (bits 0-4).
+    pub fn lift_hill_speed(&self) -> u8 {
+        self.lift_hill_speed_num_circuits & 0x1F
+    }
+    /// Number of circuits (bits 5-7).
+    pub fn num_circuits(&self) -> u8 {
+        self.lift_hill_speed_num_circuits >> 5
+    }
+
+    /// Vehicle and scenery objects the design uses.
+    pub fn object_refs(&self) -> Vec<ObjectEntry> {
+        let mut refs = vec![self.vehicle_object];
+        refs.extend(self.scenery.iter().map(|s| s.entry));
+        refs
+    }
+}
+
+fn write_entry(v: &mut Vec<u8>, e: &ObjectEntry) {
+    v.extend_from_slice(&e.flags.to_le_bytes());
+    v.extend_from_slice(&e.name);
+    v.extend_from_slice(&e.checksum.to_le_bytes());
+}
+
+/// Subtracted from the checksum of RCT2 track designs.
+pub const CHECKSUM_SALT: u32 = 120_001;
+
+/// Rotating byte sum behind the checksums of track designs and RCT1 saves, before any salt.
+pub fn rotating_checksum(enc: &[u8]) -> u32 {
+    let mut c = 0u32;
+    for &b in enc {
+        c = (c & 0xFFFF_FF00) | u32::from((c as u8).wrapping_add(b));
+        c = c.rotate_left(3);
+    }
+    c
+}
+
+/// Checksum stored after the encoded track design data.
+pub fn calculate_checksum(enc: &[u8]) -> u32 {
+    rotating_checksum(enc).wrapping_sub(CHECKSUM_SALT)
+}
+
+/// Checks the checksum of a whole TD6 file.
+pub fn validate_checksum(file: &[u8]) -> bool {
+    file.len() >= 4 && calculate_checksum(&file[..file.len()-4]) == u32_from_slice(file, file.len()-4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decoded design with an empty header, version 2 (TD6).
+    fn header(ride_type: u8) -> Vec<u8> {
+        let mut v = vec![0u8; 0xA3];
+        v[0x00] = ride_type;
+        v[0x07] = 2 << 2;
+        v[0x70..0x74].copy_from_slice(&0x80u32.to_le_bytes());
+        v[0x74..0x7C].copy_from_slice(b"WOODCARS");
+        v[0xA2] = 5 | (2 << 5);
+        v
+    }
+
+    fn coaster() -> Vec<u8> {
+        let mut v = header(0x34);
+        v.extend_from_slice(&[0x02, 0x00, 0x01, 0x80, 0x00, 0x00, 0xFF]);
+        v.extend_from_slice(&[0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0xFF]);
+        v.extend_from_slice(&[0x81, 0, 0, 0, b'T', b'R', b'E', b'E', b'1', b' ', b' ', b' ', 0, 0, 0, 0, 2, 3, 4, 0, 7, 8]);
+        v.extend_from_slice(&[0xFF, 0xAA, 0xBB]);
+        v
+    }
+
+    #[test]
+    fn reads_track_elements() {
+        let td = TrackDesign::from_data(&coaster()).unwrap();
+        assert!(!td.is_maze());
+        assert_eq!(td.elements.len(), 3);
+        assert!(td.elements[1].has_chain_lift());
+        assert_eq!(td.entrances, [EntranceElement{z: 0, direction: 0, x: 32, y: 0}]);
+        assert_eq!(td.scenery[0].entry.name(), "TREE1");
+        assert_eq!((td.scenery[0].x, td.scenery[0].y, td.scenery[0].z), (2, 3, 4));
+        assert_eq!(td.trailing, [0xAA, 0xBB]);
+        assert_eq!((td.lift_hill_speed(), td.num_circuits()), (5, 2));
+        assert_eq!(td.object_refs().iter().map(|e| e.name()).collect::<Vec<_>>(), ["WOODCARS", "TREE1"]);
+        assert_eq!(td.to_data(), coaster());
+    }
+
+    #[test]
+    fn reads_mazes() {
+        let mut v = header(0x14);
+        v.extend_from_slice(&[1, 2, 0x34, 0x12, 0, 0, 0, 0, 0xFF]);
+        let td = TrackDesign::from_data(&v).unwrap();
+        assert!(td.is_maze());
+        assert_eq!(td.maze, [MazeElement{x: 1, y: 2, maze_entry: 0x1234}]);
+        assert!(td.elements.is_empty());
+        assert_eq!(td.to_data(), v);
+    }
+
+    #[test]
+    fn writes_signed_files() {
+        let td = TrackDesign::from_data(&coaster()).unwrap();
+        let mut f = Vec::new();
+        td.write(&mut f).unwrap();
+        assert!(validate_checksum(&f));
+        assert_eq!(TrackDesign::read(&f[..]).unwrap().to_data(), coaster());
+        let n = f.len();
+        f[n-1] ^= 1;
+        assert!(!validate_checksum(&f));
+    }
+
+    #[test]
+    fn truncated_designs() {
+        assert!(TrackDesign::from_data(&header(0x34)[..0xA0]).is_err());
+        let v = coaster();
+        for n in [0xA3, 0xA4, 0xAA, 0xAE, 0xBA] {
+            assert!(TrackDesign::from_data(&v[..n]).is_err(), "{}", n);
+        }
+        assert!(TrackDesign::read(&[0u8; 3][..]).is_err());
+    }
+}
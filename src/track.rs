@@ -0,0 +1,659 @@
+//! Geometry of track elements.
+//!
+//! Coordinates follow the game: a tile is 32 units wide, heights are in units of 8 per land
+//! step, and direction 0 faces -x. Elements are described as placed in direction 0 (or
+//! diagonal direction 4 for pieces starting on a diagonal).
+
+use std::collections::BTreeSet;
+use std::f64::consts::PI;
+use crate::td6::TrackElement;
+
+pub const TILE_SIZE: i32 = 32;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Slope {
+    Flat,
+    Up25,
+    Up60,
+    Up90,
+    Down25,
+    Down60,
+    Down90
+}
+
+impl Slope {
+    pub fn is_flat(self) -> bool {
+        self == Slope::Flat
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Bank {
+    Flat,
+    Left,
+    Right,
+    UpsideDown
+}
+
+impl Bank {
+    fn mirror(self) -> Self {
+        match self {
+            Bank::Left => Bank::Right,
+            Bank::Right => Bank::Left,
+            b => b
+        }
+    }
+}
+
+/// Tile occupied by an element, relative to its first tile and base height.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TrackBlock {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16
+}
+
+#[derive(Clone, Debug)]
+pub struct TrackGeometry {
+    /// Directions 0-3 are orthogonal, 4-7 diagonal.
+    pub start_direction: u8,
+    pub end_direction: u8,
+    /// Heights at both ends, above the lowest point of the element.
+    pub start_height: i16,
+    pub end_height: i16,
+    pub start_slope: Slope,
+    pub end_slope: Slope,
+    pub start_bank: Bank,
+    pub end_bank: Bank,
+    /// Last tile for orthogonal ends; the tile the next element starts on for diagonal ones.
+    pub end_x: i16,
+    pub end_y: i16,
+    pub blocks: Vec<TrackBlock>,
+    /// Approximate length of the rails in coordinate units.
+    pub length: u16,
+    pub flags: u8
+}
+
+impl TrackGeometry {
+    pub const FLAG_CURVE: u8 = 1 << 0;
+    pub const FLAG_SLOPE: u8 = 1 << 1;
+    pub const FLAG_INVERSION: u8 = 1 << 2;
+    pub const FLAG_BANKED: u8 = 1 << 3;
+    pub const FLAG_STATION: u8 = 1 << 4;
+    pub const FLAG_VERTICAL: u8 = 1 << 5;
+
+    pub fn is_curve(&self) -> bool {
+        self.flags & Self::FLAG_CURVE != 0
+    }
+    pub fn is_slope(&self) -> bool {
+        self.flags & Self::FLAG_SLOPE != 0
+    }
+    pub fn is_inversion(&self) -> bool {
+        self.flags & Self::FLAG_INVERSION != 0
+    }
+    pub fn is_banked(&self) -> bool {
+        self.flags & Self::FLAG_BANKED != 0
+    }
+    pub fn is_station(&self) -> bool {
+        self.flags & Self::FLAG_STATION != 0
+    }
+}
+
+/// Path an element follows across tiles, turning left.
+#[derive(Copy, Clone)]
+enum Shape {
+    Straight(u8),
+    /// Stays on its tile, for vertical track and towers.
+    Vertical,
+    Tower,
+    Quarter(u8),
+    VerticalQuarter,
+    /// Two quarter turns, ending in the opposite direction.
+    Half(u8),
+    EighthToDiag,
+    EighthToOrthogonal,
+    Diagonal,
+    SBend,
+    Loop,
+    HalfLoopUp,
+    HalfLoopDown,
+    LargeHalfLoopUp,
+    LargeHalfLoopDown,
+    QuarterLoopUp,
+    QuarterLoopDown,
+    Corkscrew,
+    Reverser
+}
+
+struct Path {
+    start: u8,
+    end: u8,
+    tiles: Vec<(i16, i16)>,
+    end_pos: (i16, i16)
+}
+
+fn straight(n: i16) -> Vec<(i16, i16)> {
+    (0..n).map(|i| (-32 * i, 0)).collect()
+}
+
+fn quarter(n: u8) -> Path {
+    let tiles = match n {
+        1 => vec![(0, 0)],
+        3 => vec![(0, 0), (-32, 0), (0, -32), (-32, -32)],
+        _ => vec![(0, 0), (-32, 0), (0, -32), (-32, -32), (-64, -32), (-32, -64), (-64, -64)]
+    };
+    let e = *tiles.last().unwrap();
+    Path{start: 0, end: 3, tiles, end_pos: e}
+}
+
+impl Shape {
+    fn path(self) -> Path {
+        let p = |tiles: Vec<(i16, i16)>, end: u8| {
+            let end_pos = *tiles.last().unwrap();
+            Path{start: 0, end, tiles, end_pos}
+        };
+        let negate_x = |v: Vec<(i16, i16)>| v.into_iter().map(|(x, y)| (-x, y)).collect::<Vec<_>>();
+        match self {
+            Shape::Straight(n) => p(straight(i16::from(n)), 0),
+            Shape::Vertical => Path{start: 0, end: 0, tiles: vec![(0, 0)], end_pos: (32, 0)},
+            Shape::Tower => Path{
+                start: 0, end: 0,
+                tiles: vec![(0, 0), (-32, -32), (-32, 0), (-32, 32), (0, -32), (0, 32), (32, -32), (32, 0), (32, 32)],
+                end_pos: (32, 0)
+            },
+            Shape::Quarter(n) => quarter(n),
+            Shape::VerticalQuarter => Path{start: 0, end: 3, tiles: vec![(0, 0)], end_pos: (0, 32)},
+            Shape::Half(n) => {
+                let q = quarter(n);
+                let (ex, ey) = q.end_pos;
+                let mut tiles = q.tiles.clone();
+                for &(x, y) in &q.tiles {
+                    let t = (ex - y, ey + x);
+                    if !tiles.contains(&t) {
+                        tiles.push(t);
+                    }
+                }
+                Path{start: 0, end: 2, tiles, end_pos: (ex - ey, ey + ex)}
+            },
+            Shape::EighthToDiag => Path{start: 0, end: 4, tiles: vec![(0, 0), (-32, 0), (-64, 0), (-32, -32)], end_pos: (-64, -32)},
+            Shape::EighthToOrthogonal => Path{start: 4, end: 3, tiles: vec![(0, 0), (0, -32), (-32, -32), (-32, -64)], end_pos: (-32, -64)},
+            Shape::Diagonal => Path{start: 4, end: 4, tiles: vec![(0, 0), (-32, 0), (0, -32)], end_pos: (-32, -32)},
+            Shape::SBend => p(vec![(0, 0), (-32, 0), (-64, -32), (-96, -32)], 0),
+            Shape::Loop => p(vec![(0, 0), (-32, 0), (-64, 0), (-32, -32), (-64, -32), (-96, -32)], 0),
+            Shape::HalfLoopUp => p(straight(3), 2),
+            Shape::HalfLoopDown => p(negate_x(straight(3)), 2),
+            Shape::LargeHalfLoopUp => p(vec![(0, 0), (-32, 0), (-64, 0), (-96, 0), (-64, -32), (-96, -32)], 2),
+            Shape::LargeHalfLoopDown => p(vec![(0, 0), (32, 0), (64, 0), (96, 0), (64, -32), (96, -32)], 2),
+            Shape::QuarterLoopUp => p(straight(2), 2),
+            Shape::QuarterLoopDown => p(negate_x(straight(2)), 2),
+            Shape::Corkscrew => p(vec![(0, 0), (-32, 0), (-32, -32)], 3),
+            Shape::Reverser => p(vec![(0, 0), (-32, 0), (-64, 0), (-64, -32), (-32, -32), (0, -32)], 2)
+        }
+    }
+
+    /// Horizontal length of the path, in coordinate units.
+    fn run(self) -> f64 {
+        match self {
+            Shape::Straight(n) => f64::from(n) * 32.0,
+            Shape::Vertical | Shape::Tower | Shape::VerticalQuarter => 0.0,
+            Shape::Quarter(n) => PI / 2.0 * 16.0 * f64::from(n),
+            Shape::Half(n) => PI * 16.0 * f64::from(n),
+            Shape::EighthToDiag | Shape::EighthToOrthogonal => PI / 4.0 * 80.0,
+            Shape::Diagonal => 32.0 * 2f64.sqrt(),
+            Shape::SBend => 132.0,
+            Shape::Loop | Shape::LargeHalfLoopUp | Shape::LargeHalfLoopDown | Shape::Reverser => 128.0,
+            Shape::HalfLoopUp | Shape::HalfLoopDown => 96.0,
+            Shape::QuarterLoopUp | Shape::QuarterLoopDown => 64.0,
+            Shape::Corkscrew => 80.0
+        }
+    }
+}
+
+struct Desc {
+    shape: Shape,
+    slope: (Slope, Slope),
+    bank: (Bank, Bank),
+    height: (i16, i16),
+    /// Highest point of loops, above the element's base.
+    peak: i16,
+    inversion: bool,
+    right: bool
+}
+
+fn d(shape: Shape, s0: Slope, s1: Slope, z0: i16, z1: i16) -> Desc {
+    Desc{shape, slope: (s0, s1), bank: (Bank::Flat, Bank::Flat), height: (z0, z1), peak: 0, inversion: false, right: false}
+}
+
+impl Desc {
+    fn bank(mut self, b0: Bank, b1: Bank) -> Self {
+        self.bank = (b0, b1);
+        self
+    }
+
+    fn inversion(mut self, peak: i16) -> Self {
+        self.inversion = true;
+        self.peak = peak;
+        self
+    }
+
+    /// Mirrored version of a left-hand element.
+    fn right(mut self) -> Self {
+        self.right = true;
+        self.bank = (self.bank.0.mirror(), self.bank.1.mirror());
+        self
+    }
+}
+
+/// Describes left-hand elements; right-hand ones are their mirror images.
+fn desc(kind: u8) -> Option<Desc> {
+    use Shape::*;
+    use Slope as S;
+    use Bank as B;
+    let flat = |shape| d(shape, S::Flat, S::Flat, 0, 0);
+    let one = Straight(1);
+    let desc = match kind {
+        0x00..=0x03 | 0x63 | 0x64 | 0x70..=0x72 | 0x75 | 0x78 | 0x84 | 0xAC | 0xAD | 0xD8 => flat(one),
+        0x04 => d(one, S::Up25, S::Up25, 0, 16),
+        0x05 => d(one, S::Up60, S::Up60, 0, 64),
+        0x06 => d(one, S::Flat, S::Up25, 0, 8),
+        0x07 => d(one, S::Up25, S::Up60, 0, 24),
+        0x08 => d(one, S::Up60, S::Up25, 0, 24),
+        0x09 => d(one, S::Up25, S::Flat, 0, 8),
+        0x0A => d(one, S::Down25, S::Down25, 16, 0),
+        0x0B => d(one, S::Down60, S::Down60, 64, 0),
+        0x0C => d(one, S::Flat, S::Down25, 8, 0),
+        0x0D => d(one, S::Down25, S::Down60, 24, 0),
+        0x0E => d(one, S::Down60, S::Down25, 24, 0),
+        0x0F => d(one, S::Down25, S::Flat, 8, 0),
+        0x10 => flat(Quarter(5)),
+        0x12 => flat(one).bank(B::Flat, B::Left),
+        0x14 => flat(one).bank(B::Left, B::Flat),
+        0x16 => flat(Quarter(5)).bank(B::Left, B::Left),
+        0x18 => d(one, S::Flat, S::Up25, 0, 8).bank(B::Left, B::Flat),
+        0x1A => d(one, S::Up25, S::Flat, 0, 8).bank(B::Flat, B::Left),
+        0x1C => d(one, S::Flat, S::Down25, 8, 0).bank(B::Left, B::Flat),
+        0x1E => d(one, S::Down25, S::Flat, 8, 0).bank(B::Flat, B::Left),
+        0x20 => flat(one).bank(B::Left, B::Left),
+        0x22 => d(Quarter(5), S::Up25, S::Up25, 0, 64),
+        0x24 => d(Quarter(5), S::Down25, S::Down25, 64, 0),
+        0x26 => flat(SBend),
+        0x28 => flat(Loop).inversion(152),
+        0x2A => flat(Quarter(3)),
+        0x2C => flat(Quarter(3)).bank(B::Left, B::Left),
+        0x2E => d(Quarter(3), S::Up25, S::Up25, 0, 32),
+        0x30 => d(Quarter(3), S::Down25, S::Down25, 32, 0),
+        0x32 => flat(Quarter(1)),
+        0x34 => flat(Straight(3)).bank(B::UpsideDown, B::Flat).inversion(0),
+        0x36 => flat(Straight(3)).bank(B::Flat, B::UpsideDown).inversion(0),
+        0x38 => d(HalfLoopUp, S::Flat, S::Flat, 0, 152).bank(B::Flat, B::UpsideDown).inversion(152),
+        0x39 => d(HalfLoopDown, S::Flat, S::Flat, 152, 0).bank(B::UpsideDown, B::Flat).inversion(152),
+        0x3A => d(Corkscrew, S::Flat, S::Flat, 0, 48).bank(B::Flat, B::UpsideDown).inversion(48),
+        0x3C => d(Corkscrew, S::Flat, S::Flat, 48, 0).bank(B::UpsideDown, B::Flat).inversion(48),
+        0x3E => d(one, S::Flat, S::Up60, 0, 24),
+        0x3F => d(one, S::Up60, S::Flat, 0, 24),
+        0x40 => d(one, S::Flat, S::Down60, 24, 0),
+        0x41 => d(one, S::Down60, S::Flat, 24, 0),
+        0x42 => d(Tower, S::Flat, S::Up90, 0, 32),
+        0x43 => d(Vertical, S::Up90, S::Up90, 0, 32),
+        // Covered versions of the basic pieces
+        0x44..=0x50 => desc([0x00, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F][usize::from(kind - 0x44)])?,
+        0x51 => flat(Quarter(5)),
+        0x53 => flat(SBend),
+        0x55 => flat(Quarter(3)),
+        0x57 => d(Half(3), S::Flat, S::Flat, 0, 16).bank(B::Left, B::Left),
+        0x59 => d(Half(3), S::Flat, S::Flat, 16, 0).bank(B::Left, B::Left),
+        0x5B => d(Half(5), S::Flat, S::Flat, 0, 16).bank(B::Left, B::Left),
+        0x5D => d(Half(5), S::Flat, S::Flat, 16, 0).bank(B::Left, B::Left),
+        0x5F => d(Quarter(1), S::Up60, S::Up60, 0, 64),
+        0x61 => d(Quarter(1), S::Down60, S::Down60, 64, 0),
+        0x65 => d(QuarterLoopUp, S::Up90, S::Flat, 0, 64).bank(B::UpsideDown, B::Flat).inversion(64),
+        0x66 => d(Quarter(5), S::Flat, S::Flat, 0, 16).bank(B::Left, B::Left),
+        0x68 => d(Quarter(5), S::Flat, S::Flat, 16, 0).bank(B::Left, B::Left),
+        0x6A => d(Quarter(5), S::Flat, S::Flat, 0, 16),
+        0x6C => d(Quarter(5), S::Flat, S::Flat, 16, 0),
+        0x6E => d(one, S::Up25, S::Up25, 0, 16).bank(B::Left, B::Left),
+        0x73 => d(one, S::Down25, S::Down25, 16, 0).bank(B::Left, B::Left),
+        0x76 => d(Straight(4), S::Flat, S::Up60, 0, 64),
+        0x77 => d(Straight(4), S::Up60, S::Flat, 0, 64),
+        0x79 => d(Straight(4), S::Down60, S::Flat, 64, 0),
+        0x7A => d(Straight(4), S::Flat, S::Down60, 64, 0),
+        0x7B => d(Straight(4), S::Up60, S::Up60, 0, 96),
+        0x7C => d(Straight(7), S::Flat, S::Up90, 0, 96),
+        0x7D => d(Vertical, S::Up90, S::Up90, 0, 32),
+        0x7E => d(Vertical, S::Up90, S::Up90, 0, 32),
+        0x7F => d(Vertical, S::Down90, S::Down90, 32, 0),
+        0x80 => d(one, S::Up60, S::Up90, 0, 56),
+        0x81 => d(one, S::Down90, S::Down60, 56, 0),
+        0x82 => d(one, S::Up90, S::Up60, 0, 56),
+        0x83 => d(one, S::Down60, S::Down90, 56, 0),
+        0x85 => flat(EighthToDiag),
+        0x87 => flat(EighthToOrthogonal),
+        0x89 => flat(EighthToDiag).bank(B::Left, B::Left),
+        0x8B => flat(EighthToOrthogonal).bank(B::Left, B::Left),
+        0x8D => flat(Diagonal),
+        0x8E => d(Diagonal, S::Up25, S::Up25, 0, 16),
+        0x8F => d(Diagonal, S::Up60, S::Up60, 0, 64),
+        0x90 => d(Diagonal, S::Flat, S::Up25, 0, 8),
+        0x91 => d(Diagonal, S::Up25, S::Up60, 0, 24),
+        0x92 => d(Diagonal, S::Up60, S::Up25, 0, 24),
+        0x93 => d(Diagonal, S::Up25, S::Flat, 0, 8),
+        0x94 => d(Diagonal, S::Down25, S::Down25, 16, 0),
+        0x95 => d(Diagonal, S::Down60, S::Down60, 64, 0),
+        0x96 => d(Diagonal, S::Flat, S::Down25, 8, 0),
+        0x97 => d(Diagonal, S::Down25, S::Down60, 24, 0),
+        0x98 => d(Diagonal, S::Down60, S::Down25, 24, 0),
+        0x99 => d(Diagonal, S::Down25, S::Flat, 8, 0),
+        0x9A => d(Diagonal, S::Flat, S::Up60, 0, 24),
+        0x9B => d(Diagonal, S::Up60, S::Flat, 0, 24),
+        0x9C => d(Diagonal, S::Flat, S::Down60, 24, 0),
+        0x9D => d(Diagonal, S::Down60, S::Flat, 24, 0),
+        0x9E => flat(Diagonal).bank(B::Flat, B::Left),
+        0xA0 => flat(Diagonal).bank(B::Left, B::Flat),
+        0xA2 => d(Diagonal, S::Flat, S::Up25, 0, 8).bank(B::Left, B::Flat),
+        0xA4 => d(Diagonal, S::Up25, S::Flat, 0, 8).bank(B::Flat, B::Left),
+        0xA6 => d(Diagonal, S::Flat, S::Down25, 8, 0).bank(B::Left, B::Flat),
+        0xA8 => d(Diagonal, S::Down25, S::Flat, 8, 0).bank(B::Flat, B::Left),
+        0xAA => flat(Diagonal).bank(B::Left, B::Left),
+        0xAE => flat(Straight(3)).bank(B::Flat, B::UpsideDown).inversion(0),
+        0xB0 => flat(Straight(3)).bank(B::UpsideDown, B::Flat).inversion(0),
+        0xB2 => d(Quarter(3), S::Flat, S::Up25, 0, 32).bank(B::Left, B::Flat),
+        0xB4 => d(Quarter(3), S::Down25, S::Flat, 32, 0).bank(B::Flat, B::Left),
+        0xB6 => d(one, S::Up25, S::Up25, 0, 16),
+        0xB7 => d(LargeHalfLoopUp, S::Flat, S::Flat, 0, 248).bank(B::Flat, B::UpsideDown).inversion(248),
+        0xB9 => desc(0xBA)?.right(),
+        0xBA => d(LargeHalfLoopDown, S::Flat, S::Flat, 248, 0).bank(B::UpsideDown, B::Flat).inversion(248),
+        0xBB => flat(Straight(3)).bank(B::Flat, B::UpsideDown).inversion(0),
+        0xBD => flat(Straight(3)).bank(B::UpsideDown, B::Flat).inversion(0),
+        0xBF => desc(0x38)?,
+        0xC0 => desc(0x39)?,
+        0xC1 => desc(0x3A)?,
+        0xC3 => desc(0x3C)?,
+        0xC5 => d(Straight(4), S::Flat, S::Flat, 0, 32),
+        0xC6 => d(Straight(4), S::Flat, S::Flat, 32, 0),
+        0xC7 => flat(Straight(6)).inversion(0),
+        0xC9..=0xCD => flat(Straight(2)),
+        0xCE => d(QuarterLoopDown, S::Flat, S::Down90, 64, 0).bank(B::UpsideDown, B::Flat).inversion(64),
+        0xCF => d(QuarterLoopUp, S::Up90, S::Flat, 0, 64).bank(B::Flat, B::UpsideDown).inversion(64),
+        0xD0 => d(QuarterLoopDown, S::Flat, S::Down90, 64, 0).bank(B::UpsideDown, B::Flat).inversion(64),
+        0xD1 => d(Quarter(5), S::Up25, S::Up25, 0, 64),
+        0xD3 => flat(Reverser),
+        0xD5 => d(Straight(4), S::Up90, S::Down90, 0, 0).inversion(96),
+        0xD6 => d(Vertical, S::Down90, S::Down90, 32, 0),
+        0xD7 => d(Straight(5), S::Down90, S::Flat, 96, 0),
+        0xD9 => d(Quarter(3), S::Up25, S::Up25, 0, 32).bank(B::Left, B::Left),
+        0xDB => d(Quarter(3), S::Down25, S::Down25, 32, 0).bank(B::Left, B::Left),
+        0xDD => d(Quarter(5), S::Up25, S::Up25, 0, 64).bank(B::Left, B::Left),
+        0xDF => d(Quarter(5), S::Down25, S::Down25, 64, 0).bank(B::Left, B::Left),
+        0xE1 => d(one, S::Up25, S::Up25, 0, 16).bank(B::Flat, B::Left),
+        0xE3 => d(one, S::Up25, S::Up25, 0, 16).bank(B::Left, B::Flat),
+        0xE5 => d(one, S::Down25, S::Down25, 16, 0).bank(B::Flat, B::Left),
+        0xE7 => d(one, S::Down25, S::Down25, 16, 0).bank(B::Left, B::Flat),
+        0xE9 => d(one, S::Flat, S::Up25, 0, 8).bank(B::Left, B::Left),
+        0xEB => d(one, S::Up25, S::Flat, 0, 8).bank(B::Left, B::Left),
+        0xED => d(one, S::Flat, S::Down25, 8, 0).bank(B::Left, B::Left),
+        0xEF => d(one, S::Down25, S::Flat, 8, 0).bank(B::Left, B::Left),
+        0xF1 => d(one, S::Flat, S::Up25, 0, 8).bank(B::Flat, B::Left),
+        0xF3 => d(one, S::Up25, S::Flat, 0, 8).bank(B::Left, B::Flat),
+        0xF5 => d(one, S::Flat, S::Down25, 8, 0).bank(B::Flat, B::Left),
+        0xF7 => d(one, S::Down25, S::Flat, 8, 0).bank(B::Left, B::Flat),
+        0xF9 => d(VerticalQuarter, S::Up90, S::Up90, 0, 96),
+        0xFB => d(VerticalQuarter, S::Down90, S::Down90, 96, 0),
+        0xFD => d(QuarterLoopUp, S::Up90, S::Flat, 0, 64).bank(B::Flat, B::UpsideDown).inversion(64),
+        0xFE => d(QuarterLoopDown, S::Flat, S::Down90, 64, 0).bank(B::Flat, B::UpsideDown).inversion(64),
+        // Right-hand versions follow their left-hand counterparts
+        0x11 | 0x13 | 0x15 | 0x17 | 0x19 | 0x1B | 0x1D | 0x1F | 0x21 | 0x23 | 0x25 | 0x27 | 0x29
+            | 0x2B | 0x2D | 0x2F | 0x31 | 0x33 | 0x35 | 0x37 | 0x3B | 0x3D | 0x52 | 0x54 | 0x56
+            | 0x58 | 0x5A | 0x5C | 0x5E | 0x60 | 0x62 | 0x67 | 0x69 | 0x6B | 0x6D | 0x6F | 0x74
+            | 0x86 | 0x88 | 0x8A | 0x8C | 0x9F | 0xA1 | 0xA3 | 0xA5 | 0xA7 | 0xA9 | 0xAB | 0xAF
+            | 0xB1 | 0xB3 | 0xB5 | 0xB8 | 0xBC | 0xBE | 0xC2 | 0xC4 | 0xC8 | 0xD2 | 0xD4 | 0xDA
+            | 0xDC | 0xDE | 0xE0 | 0xE2 | 0xE4 | 0xE6 | 0xE8 | 0xEA | 0xEC | 0xEE | 0xF0 | 0xF2
+            | 0xF4 | 0xF6 | 0xF8 | 0xFA | 0xFC => desc(kind - 1)?.right(),
+        _ => return None
+    };
+    Some(desc)
+}
+
+fn mirror_direction(d: u8) -> u8 {
+    match d {
+        1 => 3,
+        3 => 1,
+        4 => 5,
+        5 => 4,
+        6 => 7,
+        7 => 6,
+        d => d
+    }
+}
+
+/// Rotates a point by a direction (0-3) around the origin.
+pub fn rotate(x: i32, y: i32, direction: u8) -> (i32, i32) {
+    match direction & 3 {
+        0 => (x, y),
+        1 => (y, -x),
+        2 => (-x, -y),
+        _ => (-y, x)
+    }
+}
+
+/// Offset to the next tile in an orthogonal direction.
+pub fn direction_delta(direction: u8) -> (i32, i32) {
+    rotate(-TILE_SIZE, 0, direction)
+}
+
+/// Geometry of a track element type, or None for unknown ones.
+pub fn geometry(kind: u8) -> Option<TrackGeometry> {
+    let d = desc(kind)?;
+    let mut path = d.shape.path();
+    if d.right {
+        path.tiles.iter_mut().for_each(|t| t.1 = -t.1);
+        path.end_pos.1 = -path.end_pos.1;
+        path.end = mirror_direction(path.end);
+        // Keep diagonal pieces starting in direction 4
+        if path.start & 4 != 0 {
+            path.tiles.iter_mut().for_each(|t| *t = (-t.1, t.0));
+            path.end_pos = (-path.end_pos.1, path.end_pos.0);
+            path.end = (path.end & 4) | ((path.end + 3) & 3);
+        }
+    }
+    let (z0, z1) = d.height;
+    let n = path.tiles.len() as i16;
+    let blocks = path.tiles.iter().enumerate().map(|(i, &(x, y))| {
+        let i = i as i16;
+        let z = if d.peak > 0 && n > 2 {
+            // Rise to the peak over the first half of the blocks, then descend
+            let h = (n - 1) / 2;
+            if i <= h { z0 + (d.peak - z0) * i / h.max(1) } else { d.peak + (z1 - d.peak) * (i - h) / (n - 1 - h).max(1) }
+        } else if n > 1 {
+            z0 + (z1 - z0) * i / (n - 1)
+        } else {
+            z0.min(z1)
+        };
+        TrackBlock{x, y, z}
+    }).collect();
+    let rise = f64::from((z1 - z0).abs()) + f64::from(d.peak) * if d.peak > 0 { PI / 2.0 } else { 0.0 };
+    let mut flags = 0;
+    if path.end != path.start || path.end_pos.1 != 0 && path.start & 4 == 0 {
+        flags |= TrackGeometry::FLAG_CURVE;
+    }
+    if !d.slope.0.is_flat() || !d.slope.1.is_flat() || z0 != z1 {
+        flags |= TrackGeometry::FLAG_SLOPE;
+    }
+    if d.inversion {
+        flags |= TrackGeometry::FLAG_INVERSION;
+    }
+    if [d.bank.0, d.bank.1].iter().any(|&b| b == Bank::Left || b == Bank::Right) {
+        flags |= TrackGeometry::FLAG_BANKED;
+    }
+    if (0x01..=0x03).contains(&kind) {
+        flags |= TrackGeometry::FLAG_STATION;
+    }
+    if [d.slope.0, d.slope.1].iter().any(|&s| s == Slope::Up90 || s == Slope::Down90) {
+        flags |= TrackGeometry::FLAG_VERTICAL;
+    }
+    Some(TrackGeometry{
+        start_direction: path.start,
+        end_direction: path.end,
+        start_height: z0,
+        end_height: z1,
+        start_slope: d.slope.0,
+        end_slope: d.slope.1,
+        start_bank: d.bank.0,
+        end_bank: d.bank.1,
+        end_x: path.end_pos.0,
+        end_y: path.end_pos.1,
+        blocks,
+        length: d.shape.run().hypot(rise).round() as u16,
+        flags
+    })
+}
+
+/// Track element placed in world coordinates.
+#[derive(Clone, Debug)]
+pub struct PlacedElement {
+    pub index: usize,
+    pub kind: u8,
+    /// First tile and height of the rails where the element starts.
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub direction: u8,
+    /// Position, height and direction the next element starts at.
+    pub end: (i32, i32, i32),
+    pub end_direction: u8,
+    /// Occupied tiles as (x, y, z) coordinates.
+    pub blocks: Vec<(i32, i32, i32)>
+}
+
+/// Lays out elements one after another, starting at the origin facing direction 0.
+/// Unknown elements are skipped.
+pub fn walk(elements: &[TrackElement]) -> Vec<PlacedElement> {
+    let (mut x, mut y, mut z, mut dir) = (0i32, 0i32, 0i32, 0u8);
+    let mut placed = Vec::with_capacity(elements.len());
+    for (index, e) in elements.iter().enumerate() {
+        let g = match geometry(e.kind) {
+            Some(g) => g,
+            None => continue
+        };
+        let base = z - i32::from(g.start_height);
+        let blocks = g.blocks.iter().map(|b| {
+            let (bx, by) = rotate(i32::from(b.x), i32::from(b.y), dir);
+            (x + bx, y + by, base + i32::from(b.z))
+        }).collect();
+        let (ex, ey) = rotate(i32::from(g.end_x), i32::from(g.end_y), dir);
+        let next = (g.end_direction & 4) | ((g.end_direction + dir) & 3);
+        let (mut nx, mut ny) = (x + ex, y + ey);
+        if next & 4 == 0 {
+            let (dx, dy) = direction_delta(next);
+            nx += dx;
+            ny += dy;
+        }
+        let nz = base + i32::from(g.end_height);
+        placed.push(PlacedElement{index, kind: e.kind, x, y, z, direction: dir, end: (nx, ny, nz), end_direction: next, blocks});
+        x = nx;
+        y = ny;
+        z = nz;
+        dir = next;
+    }
+    placed
+}
+
+/// Tiles (in tile units) covered by placed elements.
+pub fn footprint(placed: &[PlacedElement]) -> BTreeSet<(i32, i32)> {
+    placed.iter()
+        .flat_map(|p| p.blocks.iter())
+        .map(|&(x, y, _)| (x.div_euclid(TILE_SIZE), y.div_euclid(TILE_SIZE)))
+        .collect()
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BoundingBox {
+    pub min: (i32, i32, i32),
+    pub max: (i32, i32, i32)
+}
+
+impl BoundingBox {
+    /// Width and length in tiles.
+    pub fn tiles(&self) -> (i32, i32) {
+        ((self.max.0 - self.min.0) / TILE_SIZE + 1, (self.max.1 - self.min.1) / TILE_SIZE + 1)
+    }
+}
+
+/// Extent of the blocks of placed elements.
+pub fn bounding_box(placed: &[PlacedElement]) -> Option<BoundingBox> {
+    let mut it = placed.iter().flat_map(|p| p.blocks.iter());
+    let &(x, y, z) = it.next()?;
+    let mut bb = BoundingBox{min: (x, y, z), max: (x, y, z)};
+    for &(x, y, z) in it {
+        bb.min = (bb.min.0.min(x), bb.min.1.min(y), bb.min.2.min(z));
+        bb.max = (bb.max.0.max(x), bb.max.1.max(y), bb.max.2.max(z));
+    }
+    Some(bb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(kinds: &[u8]) -> Vec<TrackElement> {
+        kinds.iter().map(|&kind| TrackElement{kind, flags: 0}).collect()
+    }
+
+    #[test]
+    fn straight_pieces() {
+        let g = geometry(0x00).unwrap();
+        assert_eq!((g.start_direction, g.end_direction, g.length), (0, 0, 32));
+        assert_eq!(g.blocks, [TrackBlock{x: 0, y: 0, z: 0}]);
+        assert_eq!(g.flags, 0);
+        assert!(geometry(0x02).unwrap().is_station());
+        assert!(geometry(0xFF).is_none());
+    }
+
+    #[test]
+    fn quarter_turns_are_mirrored() {
+        let left = geometry(0x10).unwrap();
+        let right = geometry(0x11).unwrap();
+        assert!(left.is_curve() && right.is_curve());
+        assert_eq!((left.end_direction, right.end_direction), (3, 1));
+        assert_eq!(left.end_y, -right.end_y);
+        assert_eq!(left.blocks.len(), 7);
+        assert!(geometry(0x16).unwrap().is_banked());
+    }
+
+    #[test]
+    fn slopes_change_height() {
+        let placed = walk(&elements(&[0x06, 0x04, 0x04, 0x09]));
+        assert_eq!(placed.iter().map(|p| p.z).collect::<Vec<_>>(), [0, 8, 24, 40]);
+        assert_eq!(placed[3].end, (-4 * TILE_SIZE, 0, 48));
+        assert!(geometry(0x04).unwrap().is_slope());
+        assert!(geometry(0x28).unwrap().is_inversion());
+    }
+
+    #[test]
+    fn turns_close_a_circuit() {
+        for &kind in &[0x2A, 0x2B, 0x32, 0x33] {
+            let placed = walk(&elements(&[kind; 4]));
+            let last = placed.last().unwrap();
+            assert_eq!((last.end, last.end_direction), ((0, 0, 0), 0), "{:02X}", kind);
+        }
+        let placed = walk(&elements(&[0x00, 0x2A, 0x2A, 0x00, 0x2A, 0x2A]));
+        assert_eq!(placed.last().unwrap().end, (0, 0, 0));
+    }
+
+    #[test]
+    fn unknown_elements_are_skipped() {
+        let placed = walk(&elements(&[0x00, 0xFF, 0x00]));
+        assert_eq!(placed.iter().map(|p| p.index).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(placed[1].x, -TILE_SIZE);
+    }
+
+    #[test]
+    fn footprint_and_bounds() {
+        let placed = walk(&elements(&[0x00, 0x00, 0x06]));
+        assert_eq!(footprint(&placed).into_iter().collect::<Vec<_>>(), [(-2, 0), (-1, 0), (0, 0)]);
+        let bb = bounding_box(&placed).unwrap();
+        assert_eq!(bb.tiles(), (3, 1));
+        assert!(bounding_box(&[]).is_none());
+        assert_eq!(direction_delta(1), (0, TILE_SIZE));
+        assert_eq!(rotate(1, 2, 2), (-1, -2));
+    }
+}
@@ -0,0 +1,446 @@
+use std::io;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::Line;
+use ratatui::widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Row, Table, TableState, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+use crate::finance::{format_money as money, ExpenditureType, Finances, EXPENDITURE_TYPES};
+use crate::ratings::Ratings;
+use crate::ride_type::RideType;
+use crate::s6::SavedGame;
+
+const TABS: [&str; 7] = ["Overview", "Rides", "Guests", "Staff", "Finances", "Research", "Map"];
+const MAP_LEGEND: &str = "E entrance  # ride  + path  * scenery  | wall  ~ water  . park land";
+/// Rows moved by Page Up and Page Down.
+const PAGE: usize = 20;
+
+/// Table with its column headings and widths.
+struct TableData {
+    header: &'static [&'static str],
+    widths: &'static [Constraint],
+    rows: Vec<Vec<String>>,
+    state: TableState
+}
+
+impl TableData {
+    fn new(header: &'static [&'static str], widths: &'static [Constraint], rows: Vec<Vec<String>>) -> Self {
+        let state = TableState::default().with_selected(if rows.is_empty() { None } else { Some(0) });
+        TableData{header, widths, rows, state}
+    }
+
+    fn scroll(&mut self, by: isize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let i = self.state.selected().unwrap_or(0) as isize + by;
+        self.state.select(Some(i.clamp(0, self.rows.len() as isize - 1) as usize));
+    }
+}
+
+/// Everything shown, read from the save once.
+struct App {
+    title: String,
+    overview: Vec<(&'static str, String)>,
+    tables: [TableData; 3],
+    finances: Option<Finances>,
+    research: Vec<String>,
+    /// Lines of the text map, None if the file has no map.
+    map: Option<Vec<String>>,
+    tab: usize,
+    map_scroll: (u16, u16)
+}
+
+impl App {
+    fn new(sg: &SavedGame, title: String) -> Self {
+        let months = sg.months();
+        let rides = sg.rides();
+        let guests = sg.guests();
+        let staff = sg.staff();
+        let finances = sg.finances();
+        let opt = |v: Option<String>| v.unwrap_or_else(|| "not stored".to_string());
+        let mut overview = vec![
+            ("File", if sg.is_scenario() { "scenario" } else { "saved game" }.to_string()),
+            ("Date", format!("month {} of year {}", months % 8 + 1, months / 8 + 1)),
+            ("Cash", opt(sg.cash().map(money))),
+            ("Loan", opt(sg.loan().map(money))),
+            ("Maximum loan", opt(sg.max_loan().map(money))),
+            ("Entrance fee", opt(sg.entrance_fee().map(|v| money(i32::from(v))))),
+            ("Park rating", opt(sg.park_rating().map(|v| v.to_string()))),
+            ("Guests in park", opt(sg.guests_in_park().map(|v| v.to_string())))
+        ];
+        if let Some(f) = &finances {
+            overview.push(("Park value", money(f.park_value)));
+            overview.push(("Company value", money(f.company_value)));
+            overview.push(("Total admissions", f.total_admissions.to_string()));
+        }
+        overview.push(("Rides", rides.len().to_string()));
+        overview.push(("Guests", guests.len().to_string()));
+        overview.push(("Staff", staff.len().to_string()));
+        overview.push(("Objects", format!("{} ({} packed)", sg.objects().len(), sg.packed.len())));
+
+        let ride_rows = rides.iter().map(|r| {
+            let rt = RideType::from(r.ride_type);
+            let ratings = if r.is_rated() && !rt.is_shop() {
+                Ratings::new(i32::from(r.excitement), i32::from(r.intensity), i32::from(r.nausea)).to_string()
+            } else {
+                "-".to_string()
+            };
+            vec![
+                r.index.to_string(), sg.ride_name(r), rt.name().to_string(), r.status_name().to_string(), ratings,
+                money(i32::from(r.price)), money(r.profit), format!("{}%", r.reliability_percent()), r.age(months).to_string()
+            ]
+        }).collect();
+        let guest_rows = guests.iter().map(|g| vec![
+            g.id.to_string(), sg.guest_name(g), g.state.name().to_string(), format!("{}%", g.happiness_percent()),
+            g.energy.to_string(), g.hunger.to_string(), g.thirst.to_string(), g.nausea.to_string(),
+            money(g.cash_in_pocket), money(g.cash_spent), g.rides_been_on.to_string()
+        ]).collect();
+        let staff_rows = staff.iter().map(|s| vec![
+            s.id.to_string(), sg.staff_name(s), s.staff_type.name().to_string(), s.state.name().to_string(),
+            s.energy.to_string(), format!("{:04b}", s.orders & 0xF),
+            s.work_done.iter().map(u16::to_string).collect::<Vec<_>>().join(" / ")
+        ]).collect();
+
+        App{
+            title,
+            overview,
+            tables: [
+                TableData::new(
+                    &["#", "Name", "Type", "Status", "Exc / Int / Nau", "Price", "Profit", "Reliability", "Age"],
+                    &[Constraint::Length(3), Constraint::Min(16), Constraint::Length(24), Constraint::Length(7), Constraint::Length(18),
+                        Constraint::Length(8), Constraint::Length(10), Constraint::Length(11), Constraint::Length(4)],
+                    ride_rows
+                ),
+                TableData::new(
+                    &["Id", "Name", "State", "Happy", "Energy", "Hunger", "Thirst", "Nausea", "Cash", "Spent", "Rides"],
+                    &[Constraint::Length(6), Constraint::Min(16), Constraint::Length(14), Constraint::Length(5), Constraint::Length(6),
+                        Constraint::Length(6), Constraint::Length(6), Constraint::Length(6), Constraint::Length(9), Constraint::Length(9),
+                        Constraint::Length(5)],
+                    guest_rows
+                ),
+                TableData::new(
+                    &["Id", "Name", "Type", "State", "Energy", "Orders", "Work done"],
+                    &[Constraint::Length(6), Constraint::Min(16), Constraint::Length(14), Constraint::Length(16), Constraint::Length(6),
+                        Constraint::Length(6), Constraint::Length(24)],
+                    staff_rows
+                )
+            ],
+            finances,
+            research: research(sg),
+            map: sg.map().map(|m| m.ascii().lines().map(str::to_string).collect()),
+            tab: 0,
+            map_scroll: (0, 0)
+        }
+    }
+
+    /// Handles a key, returning false to quit.
+    fn key(&mut self, code: KeyCode) -> bool {
+        let table = match self.tab {
+            1..=3 => Some(&mut self.tables[self.tab - 1]),
+            _ => None
+        };
+        let on_map = self.tab == TABS.len() - 1;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Tab => self.tab = (self.tab + 1) % TABS.len(),
+            KeyCode::BackTab => self.tab = (self.tab + TABS.len() - 1) % TABS.len(),
+            KeyCode::Char(c @ '1'..='7') => self.tab = usize::from(c as u8 - b'1'),
+            KeyCode::Right | KeyCode::Char('l') if on_map => self.map_scroll.1 = self.map_scroll.1.saturating_add(4),
+            KeyCode::Left | KeyCode::Char('h') if on_map => self.map_scroll.1 = self.map_scroll.1.saturating_sub(4),
+            KeyCode::Down | KeyCode::Char('j') if on_map => self.map_scroll.0 = self.map_scroll.0.saturating_add(2),
+            KeyCode::Up | KeyCode::Char('k') if on_map => self.map_scroll.0 = self.map_scroll.0.saturating_sub(2),
+            KeyCode::Right | KeyCode::Char('l') => self.tab = (self.tab + 1) % TABS.len(),
+            KeyCode::Left | KeyCode::Char('h') => self.tab = (self.tab + TABS.len() - 1) % TABS.len(),
+            KeyCode::Down | KeyCode::Char('j') => table.into_iter().for_each(|t| t.scroll(1)),
+            KeyCode::Up | KeyCode::Char('k') => table.into_iter().for_each(|t| t.scroll(-1)),
+            KeyCode::PageDown => table.into_iter().for_each(|t| t.scroll(PAGE as isize)),
+            KeyCode::PageUp => table.into_iter().for_each(|t| t.scroll(-(PAGE as isize))),
+            KeyCode::Home => table.into_iter().for_each(|t| t.scroll(isize::MIN / 2)),
+            KeyCode::End => table.into_iter().for_each(|t| t.scroll(isize::MAX / 2)),
+            _ => ()
+        }
+        true
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [tabs, body, help] = Layout::vertical([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let titles = TABS.iter().enumerate().map(|(i, t)| format!("{} {}", i + 1, t));
+        frame.render_widget(
+            Tabs::new(titles).select(self.tab)
+                .block(Block::default().borders(Borders::ALL).title(self.title.as_str()))
+                .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+            tabs
+        );
+        match self.tab {
+            0 => self.draw_overview(frame, body),
+            1..=3 => self.draw_table(frame, body),
+            4 => self.draw_finances(frame, body),
+            5 => frame.render_widget(
+                Paragraph::new(self.research.iter().map(|l| Line::from(l.as_str())).collect::<Vec<_>>())
+                    .block(Block::default().borders(Borders::ALL).title("Research")),
+                body
+            ),
+            _ => self.draw_map(frame, body)
+        }
+        let keys = if self.tab == TABS.len() - 1 { "arrows/hjkl scroll" } else { "←/→ tab  ↑/↓ PgUp/PgDn Home/End scroll" };
+        frame.render_widget(Paragraph::new(format!(" q quit  Tab/1-7 switch tab  {}", keys)).style(Style::default().fg(Color::DarkGray)), help);
+    }
+
+    fn draw_overview(&self, frame: &mut Frame, area: Rect) {
+        let lines = self.overview.iter().map(|(k, v)| Line::from(format!("{:<18}{}", format!("{}:", k), v))).collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Park")), area);
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
+        let t = &mut self.tables[self.tab - 1];
+        let title = format!("{} ({})", TABS[self.tab], t.rows.len());
+        let table = Table::new(t.rows.iter().map(|r| Row::new(r.iter().map(String::as_str))), t.widths.iter().copied())
+            .header(Row::new(t.header.iter().copied()).style(Style::default().add_modifier(Modifier::BOLD)))
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, area, &mut t.state);
+    }
+
+    fn draw_finances(&self, frame: &mut Frame, area: Rect) {
+        let f = match &self.finances {
+            Some(f) => f,
+            None => {
+                frame.render_widget(Paragraph::new("The park's money is not stored in this file.")
+                    .block(Block::default().borders(Borders::ALL).title("Finances")), area);
+                return;
+            }
+        };
+        let [chart, table] = Layout::vertical([Constraint::Min(10), Constraint::Length(EXPENDITURE_TYPES as u16 + 4)]).areas(area);
+        // Histories are most recent first; plot them oldest first, one point a week
+        let points = |h: &[i32]| h.iter().rev().enumerate().map(|(i, &v)| (i as f64, f64::from(v) / 10.0)).collect::<Vec<_>>();
+        let series = [
+            ("Cash", Color::Green, points(&f.cash_history)),
+            ("Park value", Color::Cyan, points(&f.park_value_history)),
+            ("Weekly profit", Color::Yellow, points(&f.weekly_profit_history))
+        ];
+        let values = series.iter().flat_map(|s| s.2.iter().map(|p| p.1));
+        let (lo, hi) = values.fold((0f64, 0f64), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        let weeks = series.iter().map(|s| s.2.len()).max().unwrap_or(0).max(2) as f64 - 1.0;
+        let datasets = series.iter().map(|(name, colour, data)| {
+            Dataset::default().name(*name).marker(Marker::Braille).graph_type(GraphType::Line)
+                .style(Style::default().fg(*colour)).data(data)
+        }).collect::<Vec<_>>();
+        let hi = if hi > lo { hi } else { lo + 1.0 };
+        frame.render_widget(
+            Chart::new(datasets)
+                .block(Block::default().borders(Borders::ALL).title("Finances by week"))
+                .x_axis(Axis::default().title("weeks").bounds([0.0, weeks]).labels(["oldest".to_string(), "now".to_string()]))
+                .y_axis(Axis::default().bounds([lo, hi]).labels([format!("{:.0}", lo), format!("{:.0}", (lo + hi) / 2.0), format!("{:.0}", hi)])),
+            chart
+        );
+        let month = f.expenditure.first().copied().unwrap_or_default();
+        let rows = month.iter().enumerate().map(|(i, &v)| Row::new([ExpenditureType::from(i as u8).name().to_string(), money(v)]))
+            .chain(std::iter::once(Row::new(["Total".to_string(), money(f.month_total(0).unwrap_or(0))]).style(Style::default().add_modifier(Modifier::BOLD))));
+        let title = format!("This month (cash {}, loan {}, profit {})", money(f.cash), money(f.loan), money(f.current_profit));
+        frame.render_widget(
+            Table::new(rows, [Constraint::Length(24), Constraint::Length(12)]).block(Block::default().borders(Borders::ALL).title(title)),
+            table
+        );
+    }
+
+    fn draw_map(&mut self, frame: &mut Frame, area: Rect) {
+        let lines = match &self.map {
+            Some(m) if m.is_empty() => vec![Line::from("Nothing has been built.")],
+            Some(m) => m.iter().map(|l| Line::from(l.as_str())).collect(),
+            None => vec![Line::from("No map stored.")]
+        };
+        // Keep some of the map in view
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+        self.map_scroll = (self.map_scroll.0.min((lines.len() as u16).saturating_sub(1)), self.map_scroll.1.min(width.saturating_sub(1)));
+        frame.render_widget(
+            Paragraph::new(lines).scroll(self.map_scroll).block(Block::default().borders(Borders::ALL).title(format!("Map  {}", MAP_LEGEND))),
+            area
+        );
+    }
+}
+
+/// Lines of the research tab.
+fn research(sg: &SavedGame) -> Vec<String> {
+    let types = sg.researched_ride_types();
+    let objects = sg.object_list();
+    let entries = sg.researched_ride_entries();
+    let mut lines = vec![format!("Ride types invented ({}):", types.len())];
+    lines.extend(types.iter().map(|&t| format!("  {}", RideType::from(t).name())));
+    lines.push(String::new());
+    lines.push(format!("Ride objects invented ({}):", entries.len()));
+    lines.extend(entries.iter().map(|&i| match objects.get(usize::from(i)).filter(|e| !e.is_empty()) {
+        Some(e) => format!("  {}", e.name()),
+        None => format!("  ride object {}", i)
+    }));
+    lines.push(String::new());
+    lines.push(format!("Scenery items invented: {}", sg.researched_scenery_items().len()));
+    lines
+}
+
+fn run_app(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    loop {
+        terminal.draw(|f| app.draw(f))?;
+        if let Event::Key(k) = event::read()? {
+            if k.kind == KeyEventKind::Press && !app.key(k.code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Browses a save in the terminal until the user quits. `title` is shown above the tabs.
+pub fn run(sg: &SavedGame, title: String) -> io::Result<()> {
+    let mut app = App::new(sg, title);
+    let mut terminal = ratatui::init();
+    let r = run_app(&mut terminal, &mut app);
+    ratatui::restore();
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use crate::ride;
+    use crate::s6::{CUSTOM_STRINGS, GAME_DATA_SIZE, RIDES, SPRITES};
+    use crate::sawyer::{Chunk, Encoding};
+
+    /// Saved game with a named ride, two guests and a one tile map, 40 months into the park.
+    fn save() -> SavedGame {
+        let mut game_data = vec![0; GAME_DATA_SIZE];
+        for i in 0..ride::MAX_RIDES {
+            game_data[RIDES + i * ride::RIDE_SIZE] = 0xFF;
+        }
+        let mut sg = SavedGame{
+            header: Chunk::new(Encoding::RLE, vec![0; 0x20]),
+            info: None,
+            packed: Vec::new(),
+            chunks: vec![
+                Chunk::new(Encoding::RLE, vec![0xFF; 16]),
+                Chunk::new(Encoding::RLE, vec![40, 0, 0, 0]),
+                Chunk::new(Encoding::RLECompressed, vec![0, 0x80, 0, 0, 0, 0, 0, 0]),
+                Chunk::new(Encoding::RLE, game_data)
+            ],
+            checksum_salt: 0
+        };
+        sg.set_cash(10_000).unwrap();
+        let b = sg.game_data_mut(RIDES, ride::RIDE_SIZE).unwrap();
+        b[0x000] = 0x34;
+        b[0x001] = 0;
+        b[0x049] = ride::STATUS_OPEN;
+        b[0x04A..0x04C].copy_from_slice(&0x8000u16.to_le_bytes());
+        b[0x138..0x13A].copy_from_slice(&60u16.to_le_bytes());
+        b[0x140..0x146].copy_from_slice(&[0x8A, 0x02, 0x08, 0x02, 0x2C, 0x01]);
+        sg.game_data_mut(CUSTOM_STRINGS, 5).unwrap().copy_from_slice(b"Woody");
+        for (i, id) in [(0, 11u32), (3, 12)] {
+            let b = sg.game_data_mut(SPRITES + i * 0x100, 0x100).unwrap();
+            b[0x00] = 1;
+            b[0x9C..0xA0].copy_from_slice(&id.to_le_bytes());
+        }
+        sg
+    }
+
+    /// Text of the screen after drawing the app, one string a row.
+    fn screen(app: &mut App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(110, 40)).unwrap();
+        terminal.draw(|f| app.draw(f)).unwrap();
+        let buf = terminal.backend().buffer();
+        (0..buf.area.height).map(|y| (0..buf.area.width).map(|x| buf[(x, y)].symbol()).collect::<String>()).collect()
+    }
+
+    fn contains(screen: &[String], text: &str) -> bool {
+        screen.iter().any(|l| l.contains(text))
+    }
+
+    #[test]
+    fn reads_the_park() {
+        let app = App::new(&save(), " park.sv6 ".to_string());
+        let overview = |k: &str| app.overview.iter().find(|o| o.0 == k).map(|o| o.1.as_str());
+        assert_eq!(overview("File"), Some("saved game"));
+        assert_eq!(overview("Date"), Some("month 1 of year 6"));
+        assert_eq!(overview("Cash"), Some("1000.00"));
+        assert_eq!(overview("Rides"), Some("1"));
+        assert_eq!(overview("Guests"), Some("2"));
+        assert_eq!(app.tables[0].rows, [["0", "Woody", "Wooden Roller Coaster", "open", "6.50/5.20/3.00", "6.00", "0.00", "0%", "40"]]);
+        assert_eq!(app.tables[1].rows.iter().map(|r| r[1].as_str()).collect::<Vec<_>>(), ["Guest 11", "Guest 12"]);
+        assert!(app.tables[2].rows.is_empty());
+        assert_eq!(app.tables[2].state.selected(), None);
+        assert_eq!(app.research, ["Ride types invented (0):", "", "Ride objects invented (0):", "", "Scenery items invented: 0"]);
+    }
+
+    #[test]
+    fn keys() {
+        let mut app = App::new(&save(), String::new());
+        assert!(app.key(KeyCode::Tab));
+        assert_eq!(app.tab, 1);
+        app.key(KeyCode::BackTab);
+        app.key(KeyCode::BackTab);
+        assert_eq!(app.tab, TABS.len() - 1);
+        app.key(KeyCode::Char('3'));
+        assert_eq!(app.tab, 2);
+        // Tables scroll within their rows
+        app.key(KeyCode::Down);
+        app.key(KeyCode::PageDown);
+        assert_eq!(app.tables[1].state.selected(), Some(1));
+        app.key(KeyCode::Char('k'));
+        assert_eq!(app.tables[1].state.selected(), Some(0));
+        app.key(KeyCode::End);
+        assert_eq!(app.tables[1].state.selected(), Some(1));
+        app.key(KeyCode::Home);
+        assert_eq!(app.tables[1].state.selected(), Some(0));
+        app.key(KeyCode::Char('4'));
+        app.key(KeyCode::Down);
+        assert_eq!(app.tables[2].state.selected(), None);
+        // Arrows switch tabs, except on the map where they scroll
+        app.key(KeyCode::Right);
+        assert_eq!(app.tab, 4);
+        app.key(KeyCode::Char('7'));
+        app.key(KeyCode::Right);
+        app.key(KeyCode::Down);
+        assert_eq!((app.tab, app.map_scroll), (6, (2, 4)));
+        app.key(KeyCode::Char('k'));
+        app.key(KeyCode::Char('k'));
+        assert_eq!(app.map_scroll, (0, 4));
+        assert!(!app.key(KeyCode::Char('q')));
+        assert!(!app.key(KeyCode::Esc));
+    }
+
+    #[test]
+    fn draws_every_tab() {
+        let mut app = App::new(&save(), " park.sv6 ".to_string());
+        let expected = [
+            "Cash:             1000.00",
+            "0   Woody            Wooden Roller Coaster   open    6.50/5.20/3.00",
+            "12     Guest 12",
+            "Staff (0)",
+            "This month (cash 1000.00, loan 0.00, profit 0.00)",
+            "Scenery items invented: 0",
+            "Nothing has been built."
+        ];
+        for (tab, text) in expected.iter().enumerate() {
+            app.tab = tab;
+            let screen = screen(&mut app);
+            assert!(screen[0].contains(" park.sv6 "));
+            assert!(contains(&screen, text), "tab {} has no {:?}", tab, text);
+        }
+        // The map is kept in view however far it is scrolled
+        app.map_scroll = (50, 500);
+        screen(&mut app);
+        assert_eq!(app.map_scroll, (0, "Nothing has been built.".len() as u16 - 1));
+    }
+
+    #[test]
+    fn draws_missing_data() {
+        let mut sg = save();
+        sg.chunks.truncate(2);
+        let mut app = App::new(&sg, String::new());
+        assert_eq!(app.overview.iter().find(|o| o.0 == "Cash").unwrap().1, "not stored");
+        app.tab = 4;
+        assert!(contains(&screen(&mut app), "The park's money is not stored in this file."));
+        app.tab = 6;
+        assert!(contains(&screen(&mut app), "No map stored."));
+    }
+}
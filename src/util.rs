@@ -5,6 +5,7 @@ pub trait DSer {
     fn read_u32(&mut self) -> io::Result<u32>;
     fn read_u16(&mut self) -> io::Result<u16>;
     fn read_u8(&mut self) -> io::Result<u8>;
+    fn read_i8(&mut self) -> io::Result<i8>;
     fn read_bytes(&mut self, sz: usize) -> io::Result<Vec<u8>>;
     fn read_dser<T>(&mut self) -> io::Result<T> where T: DeSerializable;
 }
@@ -30,8 +31,11 @@ impl <S: Read + Seek> DSer for S {
         self.read_exact(&mut v)?;
         Ok(v[0])
     }
+    fn read_i8(&mut self) -> io::Result<i8> {
+        Ok(self.read_u8()? as i8)
+    }
     fn read_bytes(&mut self, sz: usize) -> io::Result<Vec<u8>> {
-        let mut v = Vec::with_capacity(sz);
+        let mut v = vec![0u8; sz];
         self.read_exact(&mut v)?;
         Ok(v)
     }
@@ -43,6 +47,7 @@ impl <S: Read + Seek> DSer for S {
 }
 
 pub trait DeSerializable {
+    #[allow(clippy::wrong_self_convention)]
     fn from_dser<S>(&mut self, ds: &mut S) -> io::Result<()> where S: DSer;
 }
 
@@ -55,4 +60,54 @@ pub fn u16_from_slice(b: &[u8], at: usize) -> u16 {
     let mut v = [0u8; 2];
     v.copy_from_slice(&b[at..at+2]);
     u16::from_le_bytes(v)
-}
\ No newline at end of file
+}
+
+/// Serializes byte buffers as hex strings, which keeps chunks of saved games small in JSON.
+#[cfg(feature = "serde")]
+pub mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(b: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        let mut hex = String::with_capacity(b.len() * 2);
+        for x in b {
+            hex.push_str(&format!("{:02x}", x));
+        }
+        s.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(d)?;
+        if hex.len() % 2 != 0 {
+            return Err(D::Error::custom("odd number of hex digits"));
+        }
+        (0..hex.len()).step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i+2).unwrap_or("?"), 16).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+/// Serializes object identifiers as 8 character strings, one character per byte.
+#[cfg(feature = "serde")]
+pub mod name_bytes {
+    use std::convert::TryFrom;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(b: &[u8; 8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&b.iter().map(|&c| char::from(c)).collect::<String>())
+    }
+
+    /// Shorter names are padded with spaces, as the game does.
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[u8; 8], D::Error> {
+        let name = String::deserialize(d)?;
+        let mut b = [b' '; 8];
+        if name.chars().count() > 8 {
+            return Err(D::Error::custom(format!("object name {:?} is longer than 8 characters", name)));
+        }
+        for (x, c) in b.iter_mut().zip(name.chars()) {
+            *x = u8::try_from(u32::from(c)).map_err(|_| D::Error::custom(format!("object name {:?} has a character above U+00FF", name)))?;
+        }
+        Ok(b)
+    }
+}
@@ -0,0 +1,269 @@
+use std::fmt;
+use crate::ride_type::{RideCategory, RideMode, RideType};
+use crate::td6::TrackDesign;
+use crate::track;
+
+/// Most stations a ride can have.
+pub const MAX_STATIONS: usize = 4;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    Warning,
+    Error
+}
+
+/// Problem found in a track design, with the index of the element it concerns.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub element: Option<usize>,
+    pub message: String
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sev = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error"
+        };
+        match self.element {
+            Some(i) => write!(f, "{} [element {}]: {}", sev, i, self.message),
+            None => write!(f, "{}: {}", sev, self.message)
+        }
+    }
+}
+
+fn is_shuttle_mode(m: RideMode) -> bool {
+    use RideMode::*;
+    matches!(m, Shuttle | ReverseInclineLaunchedShuttle | PoweredLaunchPassthrough | PoweredLaunch
+        | PoweredLaunchBlockSectioned | LimPoweredLaunch | UpwardLaunch | DownwardLaunch | FreefallDrop | RotatingLift)
+}
+
+/// Whether a ride type can build inversions.
+fn has_inversions(t: RideType) -> bool {
+    use RideType::*;
+    t.category() == RideCategory::Coaster && !matches!(t, JuniorRollerCoaster | MineTrainCoaster | WoodenRollerCoaster | WoodenWildMouse
+        | SteelWildMouse | BobsleighCoaster | VirginiaReel | SideFrictionRollerCoaster | MiniRollerCoaster | WaterCoaster
+        | MineRide | SteeplechaseCoaster | SuspendedSwingingCoaster | MiniSuspendedCoaster | InvertedHairpinCoaster
+        | SpiralRollerCoaster)
+}
+
+/// Whether a ride type can use an element, for elements limited to particular rides.
+fn element_allowed(t: RideType, kind: u8) -> bool {
+    use RideType::*;
+    match kind {
+        0x42 | 0x43 => matches!(t, ObservationTower | LaunchedFreefall | RotoDrop | Lift),
+        0x70 | 0x71 | 0x78 => t == RiverRapids,
+        0x75 => matches!(t, SplashBoats | LogFlume | WaterCoaster | RiverRafts),
+        0x7B => t == GigaCoaster,
+        0x7C | 0x7D => t == ReverseFreefallCoaster,
+        0x7E..=0x83 | 0xCF | 0xD0 | 0xF9..=0xFC => matches!(t, VerticalDropCoaster | FlyingRollerCoaster | TwisterRollerCoaster
+            | LimLaunchedRollerCoaster | AirPoweredVerticalCoaster | ReverseFreefallCoaster | MultiDimensionCoaster
+            | InvertedImpulseCoaster | GigaCoaster),
+        0x84 => t == VerticalDropCoaster,
+        0xAC => t == LogFlume,
+        0xAD => t == GhostTrain,
+        0xB6 => matches!(t, LimLaunchedRollerCoaster | LogFlume),
+        0xBB..=0xC4 => matches!(t, FlyingRollerCoaster | LayDownRollerCoaster),
+        0xC5..=0xC8 => t == HeartlineTwisterCoaster,
+        0xC9..=0xCD => t == MiniGolf,
+        0x65 | 0xCE | 0xFD | 0xFE => t == MultiDimensionCoaster,
+        0xD1 | 0xD2 => t == SpiralRollerCoaster,
+        0xD3 | 0xD4 => t == ReverserRollerCoaster,
+        0xD5..=0xD7 => t == AirPoweredVerticalCoaster,
+        _ => true
+    }
+}
+
+/// Ride types with track that can be built upside down.
+fn can_invert(t: RideType) -> bool {
+    matches!(t, RideType::FlyingRollerCoaster | RideType::LayDownRollerCoaster | RideType::MultiDimensionCoaster)
+}
+
+/// Checks that a design's elements connect, form a circuit or shuttle, suit the ride type and
+/// carry consistent flags.
+pub fn validate_track(td: &TrackDesign) -> Vec<Diagnostic> {
+    let mut diags = Vec::new();
+    let mut push = |severity, element, message: String| diags.push(Diagnostic{severity, element, message});
+    let rt = RideType::from(td.ride_type);
+    if rt == RideType::Unknown {
+        push(Severity::Error, None, format!("unknown ride type 0x{:02X}", td.ride_type));
+    }
+    if td.is_maze() {
+        if td.maze.is_empty() {
+            push(Severity::Error, None, "maze has no tiles".to_string());
+        }
+        return diags;
+    }
+    if !rt.has_track() && rt != RideType::Unknown {
+        push(Severity::Error, None, format!("{} is not built from track", rt.name()));
+        return diags;
+    }
+    if td.elements.is_empty() {
+        push(Severity::Error, None, "design has no track elements".to_string());
+        return diags;
+    }
+    let geometries = td.elements.iter().map(|e| track::geometry(e.kind)).collect::<Vec<_>>();
+    let mut stations = 0;
+    for (i, (e, g)) in td.elements.iter().zip(&geometries).enumerate() {
+        let g = match g {
+            Some(g) => g,
+            None => {
+                push(Severity::Error, Some(i), format!("unknown track element 0x{:02X}", e.kind));
+                continue;
+            }
+        };
+        if rt != RideType::Unknown && !element_allowed(rt, e.kind) {
+            push(Severity::Error, Some(i), format!("{} cannot be built on a {}", crate::rct::segment_name(e.kind), rt.name()));
+        }
+        if g.is_inversion() && rt != RideType::Unknown && !has_inversions(rt) {
+            push(Severity::Error, Some(i), format!("{} cannot have inversions", rt.name()));
+        }
+        // Each element must start the way the previous one ended
+        if let Some(Some(p)) = i.checked_sub(1).map(|p| &geometries[p]) {
+            if p.end_slope != g.start_slope || p.end_bank != g.start_bank {
+                push(Severity::Error, Some(i), format!("starts {:?}/{:?} but previous element ends {:?}/{:?}",
+                    g.start_slope, g.start_bank, p.end_slope, p.end_bank));
+            }
+            if p.end_direction & 4 != g.start_direction & 4 {
+                push(Severity::Error, Some(i), "diagonal and straight track do not connect".to_string());
+            }
+        }
+        if g.is_station() {
+            let prev_station = i > 0 && geometries[i - 1].as_ref().is_some_and(|p| p.is_station());
+            if !prev_station {
+                stations += 1;
+            }
+            if e.extra() >= MAX_STATIONS as u8 {
+                push(Severity::Warning, Some(i), format!("station index {} out of range", e.extra()));
+            }
+        }
+        if e.has_chain_lift() && (g.is_station() || g.is_inversion() || g.start_height > g.end_height) {
+            push(Severity::Warning, Some(i), "chain lift on an element that cannot have one".to_string());
+        }
+        if e.is_inverted() && !can_invert(rt) {
+            push(Severity::Warning, Some(i), format!("inverted flag set but {} track cannot be inverted", rt.name()));
+        }
+    }
+    if stations == 0 {
+        push(Severity::Error, None, "track has no station".to_string());
+    } else if stations > MAX_STATIONS {
+        push(Severity::Error, None, format!("{} stations, at most {} are allowed", stations, MAX_STATIONS));
+    }
+    let placed = track::walk(&td.elements);
+    if let (Some(first), Some(last)) = (placed.first(), placed.last()) {
+        let closed = last.end == (first.x, first.y, first.z) && last.end_direction == first.direction;
+        if !closed && !is_shuttle_mode(RideMode::from(td.ride_mode)) {
+            let (x, y, z) = last.end;
+            push(Severity::Error, Some(last.index), format!("circuit does not close: track ends at ({}, {}, {}) facing {}, not at the start",
+                x / track::TILE_SIZE, y / track::TILE_SIZE, z / 8, last.end_direction));
+        }
+    }
+    diags
+}
+
+/// Whether any diagnostic is an error.
+pub fn has_errors(diags: &[Diagnostic]) -> bool {
+    diags.iter().any(|d| d.severity == Severity::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::td6::TrackElement;
+
+    const LOOPING: u8 = 0x0F;
+    const WOODEN: u8 = 0x34;
+
+    fn design(ride_type: u8, kinds: &[u8]) -> TrackDesign {
+        let mut v = vec![0u8; 0xA3];
+        v[0] = ride_type;
+        for &k in kinds {
+            v.extend_from_slice(&[k, 0]);
+        }
+        v.extend_from_slice(&[0xFF, 0xFF, 0xFF]);
+        TrackDesign::from_data(&v).unwrap()
+    }
+
+    /// Station, then two pairs of left quarter turns joined by a straight.
+    fn circuit() -> Vec<u8> {
+        vec![0x02, 0x2A, 0x2A, 0x00, 0x2A, 0x2A]
+    }
+
+    fn messages(td: &TrackDesign) -> Vec<(Severity, Option<usize>, String)> {
+        validate_track(td).into_iter().map(|d| (d.severity, d.element, d.message)).collect()
+    }
+
+    #[test]
+    fn closed_circuit_is_valid() {
+        assert!(validate_track(&design(LOOPING, &circuit())).is_empty());
+    }
+
+    #[test]
+    fn slope_breaks() {
+        let mut kinds = circuit();
+        kinds.insert(4, 0x04);
+        let diags = messages(&design(LOOPING, &kinds));
+        assert_eq!(diags[0], (Severity::Error, Some(4), "starts Up25/Flat but previous element ends Flat/Flat".to_string()));
+    }
+
+    #[test]
+    fn unclosed_circuits() {
+        let kinds = &circuit()[..5];
+        let diags = messages(&design(LOOPING, kinds));
+        assert_eq!(diags.len(), 1);
+        assert_eq!((diags[0].0, diags[0].1), (Severity::Error, Some(4)));
+        assert!(diags[0].2.starts_with("circuit does not close"));
+        // Shuttles run back the way they came
+        let mut td = design(LOOPING, kinds);
+        td.ride_mode = 4;
+        assert!(validate_track(&td).is_empty());
+    }
+
+    #[test]
+    fn station_counts() {
+        let none = messages(&design(LOOPING, &[0x00, 0x2A, 0x2A, 0x00, 0x2A, 0x2A]));
+        assert_eq!(none, [(Severity::Error, None, "track has no station".to_string())]);
+
+        let mut kinds = vec![0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x02];
+        kinds.extend_from_slice(&[0x2A, 0x2A]);
+        kinds.extend_from_slice(&[0x00; 9]);
+        kinds.extend_from_slice(&[0x2A, 0x2A]);
+        let too_many = messages(&design(LOOPING, &kinds));
+        assert_eq!(too_many, [(Severity::Error, None, "5 stations, at most 4 are allowed".to_string())]);
+
+        let mut td = design(LOOPING, &circuit());
+        td.elements[0].flags = 4;
+        assert_eq!(messages(&td), [(Severity::Warning, Some(0), "station index 4 out of range".to_string())]);
+    }
+
+    #[test]
+    fn ride_type_limits() {
+        let mut kinds = circuit();
+        kinds.insert(3, 0x28);
+        // The loop moves the track a tile sideways, so neither circuit closes
+        let looping = messages(&design(LOOPING, &kinds));
+        assert_eq!(looping.len(), 1);
+        let wooden = messages(&design(WOODEN, &kinds));
+        assert_eq!(wooden[0], (Severity::Error, Some(3), "Wooden Roller Coaster cannot have inversions".to_string()));
+        assert_eq!(wooden[1..], looping[..]);
+        assert!(has_errors(&validate_track(&design(0x1C, &circuit()))));
+        assert!(has_errors(&validate_track(&design(0xF0, &circuit()))));
+        assert!(has_errors(&validate_track(&design(LOOPING, &[]))));
+    }
+
+    #[test]
+    fn flag_warnings() {
+        let mut td = design(LOOPING, &circuit());
+        td.elements[0].flags = TrackElement::FLAG_CHAIN_LIFT;
+        td.elements[3].flags = TrackElement::FLAG_INVERTED;
+        let diags = validate_track(&td);
+        assert!(!has_errors(&diags));
+        assert_eq!(diags.iter().map(|d| d.to_string()).collect::<Vec<_>>(), [
+            "warning [element 0]: chain lift on an element that cannot have one",
+            "warning [element 3]: inverted flag set but Looping Roller Coaster track cannot be inverted"
+        ]);
+    }
+}